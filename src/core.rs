@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fmt;
 use std::fs::{self, File, OpenOptions};
@@ -7,14 +7,12 @@ use std::net::{TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Error, Result};
 use chrono::{DateTime, Local, Timelike};
 use dirs;
-use memchr::memmem;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use rayon::prelude::*;
 use regex::Regex;
 use reqwest;
@@ -23,6 +21,50 @@ use sha2::{Digest, Sha256};
 use sysinfo::{ComponentExt, CpuExt, DiskExt, NetworkExt, PidExt, ProcessExt, System, SystemExt};
 use walkdir::WalkDir;
 
+mod archives;
+mod behavior;
+mod canary;
+mod clamav;
+mod documents;
+mod elf;
+mod exclusions;
+mod hashes;
+mod heuristics;
+mod integrity;
+mod mail;
+mod matcher;
+mod packages;
+mod persistence;
+mod procmem;
+mod realtime;
+mod reputation;
+mod rules;
+mod scancache;
+mod scripts;
+mod sigdb;
+mod updater;
+
+pub use archives::{ArchiveEntry, ArchiveLimits, ArchiveStats, ContainerKind};
+pub use behavior::{BehaviorAlert, BehaviorConfig, BurstKind, SuspectProcess};
+pub use canary::{Canary, CanaryAction, CanaryAlert, CanaryConfig, CanaryState, CanaryStore};
+pub use documents::DocumentFinding;
+pub use elf::{ElfInfo, ElfSection, ElfSegment};
+pub use exclusions::ScanExclusions;
+pub use hashes::{FileDigests, HashAlgorithm, HashSignature};
+pub use heuristics::{shannon_entropy, HeuristicFinding};
+pub use integrity::{ChangeKind, FileRecord, IntegrityBaseline, IntegrityChange, IntegrityConfig};
+pub use matcher::{ContentTap, SignatureDatabase, SignatureMatch};
+pub use packages::{MismatchKind, PackageManager, PackageMismatch, PackageOwners, PackageVerifyConfig, Remediation};
+pub use persistence::{PersistenceEntry, PersistenceKind};
+pub use procmem::ProcessMatch;
+pub use realtime::{FileActivity, RealtimeConfig, RealtimeMonitor, WatchedRoot};
+pub use reputation::{ReputationClient, ReputationConfig, ReputationOutcome, ReputationVerdict, Verdict};
+pub use rules::{parse_rules, BodyOffset, Rule};
+pub use scancache::{ScanCache, ScanCacheStats};
+pub use scripts::{DecodedLayer, ScriptAnalysis};
+pub use sigdb::{InstalledVersion, SignatureManifest};
+pub use updater::{SignaturePackage, UpdateConfig, UpdateIndex, UpdateOutcome, Updater};
+
 // ==================== YAPILAR VE TANIMLAMALAR ====================
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -62,6 +104,7 @@ pub enum ThreatCategory {
     Worm,
     Virus,
     PUP,
+    ActiveContent,
     Unknown,
 }
 
@@ -76,19 +119,78 @@ impl fmt::Display for ThreatCategory {
             ThreatCategory::Worm => write!(f, "Worm"),
             ThreatCategory::Virus => write!(f, "Virus"),
             ThreatCategory::PUP => write!(f, "PUP"),
+            ThreatCategory::ActiveContent => write!(f, "Active Content"),
             ThreatCategory::Unknown => write!(f, "Unknown"),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DetectionSource {
+    Signature,
+    Rule,
+    Hash,
+    Reputation,
+    Heuristic,
+    Script,
+    Document,
+    Persistence,
+    Behavior,
+}
+
+impl fmt::Display for DetectionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DetectionSource::Signature => write!(f, "Signature"),
+            DetectionSource::Rule => write!(f, "Rule"),
+            DetectionSource::Hash => write!(f, "Hash"),
+            DetectionSource::Reputation => write!(f, "Reputation"),
+            DetectionSource::Heuristic => write!(f, "Heuristic"),
+            DetectionSource::Script => write!(f, "Script"),
+            DetectionSource::Document => write!(f, "Document"),
+            DetectionSource::Persistence => write!(f, "Persistence"),
+            DetectionSource::Behavior => write!(f, "Behavior"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectedThreat {
     pub signature: ThreatSignature,
     pub file_path: PathBuf,
+    // Location inside file_path for threats found in archives, e.g. "inner/dropper.sh"
+    pub nested_path: Option<String>,
+    // Set for threats found in process memory, where offset is the match address
+    pub process: Option<ProcessMatch>,
     pub offset: usize,
+    pub context: String,
+    pub file_hash: Option<String>,
+    pub source: DetectionSource,
+    // 1.0 for exact matches; heuristic findings carry their score
+    pub confidence: f32,
     pub timestamp: DateTime<Local>,
 }
 
+impl DetectedThreat {
+    // "archive.zip!inner/dropper.sh" for threats found inside containers
+    pub fn display_path(&self) -> String {
+        if let Some(process) = &self.process {
+            return format!("{} (pid {}) @ {:#x}", process.name, process.pid, self.offset);
+        }
+        match &self.nested_path {
+            Some(nested) => format!("{}!{}", self.file_path.display(), nested),
+            None => self.file_path.display().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureDatabaseInfo {
+    pub version: u64,
+    pub built: Option<DateTime<Local>>,
+    pub signature_count: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuarantineItem {
     pub id: String,
@@ -104,10 +206,16 @@ pub struct ScanConfig {
     pub target_paths: Vec<PathBuf>,
     pub scan_type: ScanType,
     pub heuristic_enabled: bool,
+    // Heuristic findings scoring below this (0.0 - 1.0) are dropped
+    pub heuristic_threshold: f32,
     pub cloud_lookup_enabled: bool,
     pub max_file_size: u64,
     pub excluded_extensions: Vec<String>,
     pub excluded_paths: Vec<PathBuf>,
+    pub worker_threads: usize,
+    pub archive_limits: ArchiveLimits,
+    // Memory scans kill processes found running known malicious code
+    pub terminate_processes: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -124,7 +232,7 @@ pub enum ScanEvent {
     Started,
     Progress { current: usize, total: usize },
     ThreatFound(DetectedThreat),
-    Completed { threats_found: usize, files_scanned: usize },
+    Completed { threats_found: usize, files_scanned: usize, cache: ScanCacheStats },
     Error(String),
     Cancelled,
 }
@@ -293,11 +401,44 @@ pub enum NotificationLevel {
 
 // ==================== ENGINE YAPISI ====================
 
+// Rules and the content analyzers see at most this many leading bytes of each file
+const RULE_SAMPLE_LIMIT: usize = 8 * 1024 * 1024;
+// Larger ELF files are parsed from the sample alone
+const ELF_IMAGE_LIMIT: u64 = 64 * 1024 * 1024;
+// Documents keep their package directory or trailer at the end, so they are read whole up to this size
+const DOCUMENT_IMAGE_LIMIT: u64 = 64 * 1024 * 1024;
+
+const BUILTIN_RULES: &str = r#"
+rule Linux_CoinMiner_Config {
+    meta:
+        category = "PUP"
+        severity = "Warning"
+    strings:
+        $pool = "stratum+tcp://" nocase
+        $pool_tls = "stratum+ssl://" nocase
+        $algo = "\"algo\":" nocase
+        $xmrig = "xmrig" nocase
+    condition:
+        any of ($pool*) and ($algo or $xmrig)
+}
+
+rule Linux_Shell_Reverse_Tcp {
+    meta:
+        category = "Trojan"
+        severity = "Critical"
+    strings:
+        $bash = /(bash|sh) -i >& ?\/dev\/tcp\/[0-9.]+\/[0-9]+/
+        $redirect = /0>&1/
+    condition:
+        $bash and $redirect and filesize < 1MB
+}
+"#;
+
 pub struct Engine {
     pub system: System,
-    pub threat_signatures: Arc<RwLock<Vec<ThreatSignature>>>,
+    pub threat_signatures: Arc<RwLock<Arc<SignatureDatabase>>>,
     pub quarantine_items: Arc<Mutex<Vec<QuarantineItem>>>,
-    pub realtime_watcher: Option<RecommendedWatcher>,
+    pub realtime_monitor: Option<RealtimeMonitor>,
     pub scan_in_progress: Arc<AtomicBool>,
     pub scan_cancelled: Arc<AtomicBool>,
     pub files_scanned: Arc<AtomicU64>,
@@ -306,14 +447,32 @@ pub struct Engine {
     pub notifications: Arc<Mutex<Vec<Notification>>>,
     pub notification_id_counter: Arc<AtomicU64>,
     pub localization: Arc<Mutex<Localization>>,
+    pub update_config: UpdateConfig,
+    pub reputation_config: ReputationConfig,
+    pub realtime_config: RealtimeConfig,
+    pub behavior_config: BehaviorConfig,
+    pub behavior_alerts: Arc<Mutex<Vec<BehaviorAlert>>>,
+    pub canary_config: CanaryConfig,
+    pub canaries: Arc<Mutex<CanaryStore>>,
+    pub canary_alerts: Arc<Mutex<Vec<CanaryAlert>>>,
+    pub integrity_config: IntegrityConfig,
+    pub package_verify_config: PackageVerifyConfig,
+    // Read from the package databases the first time a file's owner is needed
+    pub package_owners: OnceLock<PackageOwners>,
+    pub data_dir: Option<PathBuf>,
 }
 
 impl Engine {
     pub fn new() -> Result<Self> {
+        Self::with_data_dir(dirs::data_dir().map(|dir| dir.join("clean-master-privacy")))
+    }
+
+    // Everything the engine persists lives below data_dir; None keeps it from persisting anything
+    pub fn with_data_dir(data_dir: Option<PathBuf>) -> Result<Self> {
         let mut system = System::new_all();
         system.refresh_all();
 
-        let threat_signatures = Arc::new(RwLock::new(Vec::new()));
+        let threat_signatures = Arc::new(RwLock::new(Arc::new(SignatureDatabase::default())));
         let quarantine_items = Arc::new(Mutex::new(Vec::new()));
         let scan_in_progress = Arc::new(AtomicBool::new(false));
         let scan_cancelled = Arc::new(AtomicBool::new(false));
@@ -323,12 +482,13 @@ impl Engine {
         let notifications = Arc::new(Mutex::new(Vec::new()));
         let notification_id_counter = Arc::new(AtomicU64::new(0));
         let localization = Arc::new(Mutex::new(Localization::new()));
+        let canaries = Arc::new(Mutex::new(CanaryStore::new(data_dir.as_ref().map(|dir| dir.join("canaries.json")))));
 
         Ok(Engine {
             system,
             threat_signatures,
             quarantine_items,
-            realtime_watcher: None,
+            realtime_monitor: None,
             scan_in_progress,
             scan_cancelled,
             files_scanned,
@@ -337,6 +497,18 @@ impl Engine {
             notifications,
             notification_id_counter,
             localization,
+            update_config: UpdateConfig::default(),
+            reputation_config: ReputationConfig::default(),
+            realtime_config: RealtimeConfig::default(),
+            behavior_config: BehaviorConfig::default(),
+            behavior_alerts: Arc::new(Mutex::new(Vec::new())),
+            canary_config: CanaryConfig::default(),
+            canaries,
+            canary_alerts: Arc::new(Mutex::new(Vec::new())),
+            integrity_config: IntegrityConfig::default(),
+            package_verify_config: PackageVerifyConfig::default(),
+            package_owners: OnceLock::new(),
+            data_dir,
         })
     }
 
@@ -400,18 +572,205 @@ impl Engine {
     pub fn update_threat_database(&mut self) -> Result<()> {
         log::info!("Updating threat database...");
         
-        // Load built-in signatures
-        let signatures = Self::load_builtin_signatures();
+        let signature_dir = self.signature_dir()?;
+        let active_version = self.get_signature_database_info()?.version;
+        let installed = InstalledVersion::load(&self.installed_version_path()?)?;
+
+        // On any failure the currently active database stays in place
+        let database = Self::load_signature_database(&signature_dir, active_version.max(installed.version))?;
+        self.install_signature_database(database, installed.pinned)?;
+
+        Ok(())
+    }
+
+    fn install_signature_database(&self, database: SignatureDatabase, pinned: bool) -> Result<()> {
+        let count = database.len();
+        let version = database.version();
         
         let mut db = self.threat_signatures.write().map_err(|_| {
             anyhow::anyhow!("Failed to acquire write lock on threat signatures")
         })?;
-        *db = signatures;
-        
-        log::info!("Threat database updated with {} signatures", db.len());
+        *db = Arc::new(database);
+        drop(db);
+
+        InstalledVersion { version, pinned }.save(&self.installed_version_path()?)?;
+        log::info!("Threat database v{} loaded with {} signatures", version, count);
         Ok(())
     }
 
+    // Forgets the installed version so a missing or older on-disk database loads again;
+    // without a manifest this falls back to the built-in signatures
+    pub fn reset_signature_database(&mut self) -> Result<u64> {
+        let signature_dir = self.signature_dir()?;
+        let database = Self::load_signature_database(&signature_dir, 0)?;
+        let version = database.version();
+        self.install_signature_database(database, false)?;
+
+        log::warn!("Signature database reset to v{}", version);
+        Ok(version)
+    }
+
+    // Downloads, verifies and compiles the mirror's packages without holding the engine, which
+    // is only locked to read the configuration and to swap the finished database in
+    pub fn update_from_mirror(engine: &Mutex<Engine>) -> Result<UpdateOutcome> {
+        let lock = || engine.lock().map_err(|_| anyhow::anyhow!("Failed to lock engine"));
+        let (config, active_version, version_path, signature_dir) = {
+            let engine = lock()?;
+            if !engine.update_config.enabled || engine.update_config.mirror_url.is_empty() {
+                return Ok(UpdateOutcome::Disabled);
+            }
+            (
+                engine.update_config.clone(),
+                engine.get_signature_database_info()?.version,
+                engine.installed_version_path()?,
+                engine.signature_dir()?,
+            )
+        };
+
+        let installed = InstalledVersion::load(&version_path)?;
+        if installed.pinned {
+            return Ok(UpdateOutcome::Pinned(installed.version));
+        }
+
+        let updater = Updater::new(config.clone())?;
+        let index = updater.fetch_index()?;
+        let steps = match updater.plan(&index, active_version) {
+            Some(steps) => steps,
+            None => return Ok(UpdateOutcome::UpToDate(active_version)),
+        };
+
+        // Compile the staged database before it replaces the active one
+        let staged = updater.stage(&signature_dir, &steps, active_version).and_then(|staging| {
+            let database = Self::load_signature_database(&staging, active_version)?;
+            Ok((staging, database))
+        });
+        let (staging, database) = match staged {
+            Ok(staged) => staged,
+            Err(e) => {
+                updater::discard_staging(&signature_dir);
+                return Err(e);
+            }
+        };
+
+        let engine = lock()?;
+        // A rollback or another update may have finished while this one was downloading
+        let current = engine.get_signature_database_info()?.version;
+        if current != active_version || InstalledVersion::load(&version_path)?.pinned {
+            updater::discard_staging(&signature_dir);
+            return Err(anyhow::anyhow!("Signature database changed to v{} during the update", current));
+        }
+
+        let version = database.version();
+        updater::swap_in(&signature_dir, &staging, active_version, config.keep_versions)?;
+        engine.install_signature_database(database, false)?;
+
+        Ok(UpdateOutcome::Updated {
+            from: active_version,
+            to: version,
+            packages: steps.len(),
+        })
+    }
+
+    pub fn signature_history(&self) -> Result<Vec<u64>> {
+        updater::history_versions(&self.signature_dir()?)
+    }
+
+    // Reinstates the newest archived version older than the active one and pins it,
+    // so the next mirror update does not silently undo the rollback
+    pub fn rollback_signature_database(&mut self) -> Result<u64> {
+        let signature_dir = self.signature_dir()?;
+        let active_version = self.get_signature_database_info()?.version;
+
+        let target = updater::history_versions(&signature_dir)?
+            .into_iter()
+            .find(|version| *version < active_version)
+            .ok_or_else(|| anyhow::anyhow!("No older signature database to roll back to"))?;
+
+        let staging = updater::take_from_history(&signature_dir, target)?;
+        // Rollback is the only path allowed to lower the version
+        let database = match Self::load_signature_database(&staging, 0) {
+            Ok(database) => database,
+            Err(e) => {
+                updater::return_to_history(&signature_dir, &staging, target)?;
+                return Err(e);
+            }
+        };
+
+        updater::swap_in(&signature_dir, &staging, active_version, self.update_config.keep_versions)?;
+        self.install_signature_database(database, true)?;
+
+        log::info!("Signature database rolled back from v{} to v{}", active_version, target);
+        Ok(target)
+    }
+
+    // Lets mirror updates replace a rolled-back database again
+    pub fn resume_signature_updates(&self) -> Result<()> {
+        let path = self.installed_version_path()?;
+        let mut installed = InstalledVersion::load(&path)?;
+        installed.pinned = false;
+        installed.save(&path)
+    }
+
+    fn load_signature_database(signature_dir: &Path, active_version: u64) -> Result<SignatureDatabase> {
+        let manifest = SignatureManifest::read(signature_dir)?;
+        match &manifest {
+            Some(manifest) => {
+                if manifest.version < active_version {
+                    return Err(anyhow::anyhow!(
+                        "Refusing to downgrade signature database from v{} to v{}",
+                        active_version,
+                        manifest.version
+                    ));
+                }
+                manifest.verify(signature_dir)?;
+            }
+            None => {
+                if !sigdb::database_files(signature_dir)?.is_empty() {
+                    return Err(anyhow::anyhow!("Signature database at {:?} has no manifest", signature_dir));
+                }
+                // A deleted database would otherwise silently downgrade to the built-in set
+                if active_version > 0 {
+                    return Err(anyhow::anyhow!(
+                        "Signature database v{} is missing from {:?}; reset it to use the built-in signatures",
+                        active_version,
+                        signature_dir
+                    ));
+                }
+            }
+        }
+
+        let clamav = clamav::load_clamav_dir(signature_dir);
+
+        // Load built-in signatures and compile them once for every scan
+        let mut signatures = Self::load_builtin_signatures();
+        signatures.extend(clamav.signatures);
+        let mut database = SignatureDatabase::new(signatures)?;
+        database.add_rules(parse_rules(BUILTIN_RULES)?);
+        database.add_rules(rules::load_rules_from_dir(signature_dir));
+        database.add_rules(clamav.rules);
+        database.add_hash_signatures(Self::load_builtin_hash_signatures());
+        database.add_hash_signatures(hashes::load_hash_lists_from_dir(signature_dir));
+        database.add_hash_signatures(clamav.hashes);
+
+        // Without a manifest only the built-in set is loaded, reported as version 0
+        if let Some(manifest) = manifest {
+            database.set_version(manifest.version, Some(manifest.built));
+        }
+
+        Ok(database)
+    }
+
+    pub fn get_signature_database_info(&self) -> Result<SignatureDatabaseInfo> {
+        let db = self.threat_signatures.read().map_err(|_| {
+            anyhow::anyhow!("Failed to read threat signatures")
+        })?;
+        Ok(SignatureDatabaseInfo {
+            version: db.version(),
+            built: db.built(),
+            signature_count: db.len(),
+        })
+    }
+
     fn load_builtin_signatures() -> Vec<ThreatSignature> {
         let mut signatures = Vec::new();
         
@@ -427,15 +786,280 @@ impl Engine {
         signatures
     }
 
-    pub fn start_realtime_protection(&self) -> Result<()> {
+    fn load_builtin_hash_signatures() -> Vec<HashSignature> {
+        let eicar = ThreatSignature {
+            name: "EICAR-Test-File".to_string(),
+            pattern: Vec::new(),
+            category: ThreatCategory::Virus,
+            severity: Severity::Info,
+        };
+
+        vec![
+            HashSignature {
+                algorithm: HashAlgorithm::Sha256,
+                digest: "275a021bbfb6489e54d471899f7db9d1663fc695ec2fe2a2c4538aabf651fd0f".to_string(),
+                size: Some(68),
+                signature: eicar.clone(),
+            },
+            HashSignature {
+                algorithm: HashAlgorithm::Md5,
+                digest: "44d88612fea8a8f36de82e1278abb02f".to_string(),
+                size: Some(68),
+                signature: eicar,
+            },
+        ]
+    }
+
+    fn installed_version_path(&self) -> Result<PathBuf> {
+        self.data_path("signature-version.json")
+    }
+
+    fn data_path(&self, name: &str) -> Result<PathBuf> {
+        Ok(self
+            .data_dir
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Could not find data directory"))?
+            .join(name))
+    }
+
+    fn signature_dir(&self) -> Result<PathBuf> {
+        let signature_dir = self.data_path("signatures")?;
+
+        updater::recover_interrupted_swap(&signature_dir)?;
+        fs::create_dir_all(&signature_dir)?;
+        Ok(signature_dir)
+    }
+
+    pub fn start_realtime_protection(&mut self) -> Result<()> {
+        if self.is_realtime_protection_active() {
+            return Ok(());
+        }
+        if !self.realtime_config.enabled {
+            log::info!("Real-time protection is disabled");
+            return Ok(());
+        }
         log::info!("Starting real-time protection...");
-        
-        // This is a simplified implementation
-        // In a real application, you would set up file system watchers
-        
+
+        let mut realtime = self.realtime_config.clone();
+        // Quarantine, caches and signature updates are our own writes
+        if let Some(data_dir) = &self.data_dir {
+            realtime.excluded_paths.push(data_dir.clone());
+        }
+        let config = ScanConfig {
+            target_paths: Vec::new(),
+            scan_type: ScanType::Custom,
+            heuristic_enabled: realtime.heuristic_enabled,
+            heuristic_threshold: realtime.heuristic_threshold,
+            cloud_lookup_enabled: false,
+            max_file_size: realtime.max_file_size,
+            excluded_extensions: realtime.excluded_extensions.clone(),
+            excluded_paths: realtime.excluded_paths.clone(),
+            worker_threads: realtime.scanner_threads,
+            archive_limits: ArchiveLimits::default(),
+            terminate_processes: false,
+        };
+
+        let mut listeners = Vec::new();
+        if self.behavior_config.enabled {
+            let notifications = self.notifications.clone();
+            let notification_id_counter = self.notification_id_counter.clone();
+            let behavior_alerts = self.behavior_alerts.clone();
+            listeners.push(behavior::spawn(self.behavior_config.clone(), move |alert| {
+                let threat = alert.to_threat();
+                log::error!("Real-time protection detected {}: {}", threat.signature.name, threat.context);
+                if let Err(e) = Self::push_notification(
+                    &notifications,
+                    &notification_id_counter,
+                    "Ransomware activity detected".to_string(),
+                    format!("{}: {}", threat.signature.name, threat.context),
+                    NotificationLevel::Error,
+                ) {
+                    log::error!("Failed to report ransomware behavior: {}", e);
+                }
+                if let Ok(mut alerts) = behavior_alerts.lock() {
+                    alerts.push(alert);
+                }
+            })?);
+        }
+
+        if self.canary_config.enabled {
+            if let Err(e) = self.create_canaries() {
+                log::warn!("Failed to plant canary files: {}", e);
+            }
+            let notifications = self.notifications.clone();
+            let notification_id_counter = self.notification_id_counter.clone();
+            let canary_alerts = self.canary_alerts.clone();
+            listeners.push(canary::spawn(self.canaries.clone(), move |alert| {
+                Self::record_canary_alert(&notifications, &notification_id_counter, &canary_alerts, alert);
+            })?);
+        }
+
+        let threat_signatures = self.threat_signatures.clone();
+        let notifications = self.notifications.clone();
+        let notification_id_counter = self.notification_id_counter.clone();
+        let mut monitor = RealtimeMonitor::start(&realtime, listeners, move |path| {
+            // Read per file so signature updates apply without a restart
+            let signatures = match threat_signatures.read() {
+                Ok(signatures) => signatures.clone(),
+                Err(_) => return,
+            };
+            let (threats, _) = Self::scan_file(&signatures, &config, path);
+
+            // A file usually matches several signatures for the same threat; one notification per location
+            let mut found: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            for threat in threats {
+                let names = found.entry(threat.display_path()).or_default();
+                if !names.contains(&threat.signature.name) {
+                    names.push(threat.signature.name);
+                }
+            }
+
+            for (location, names) in found {
+                log::warn!("Real-time protection detected {} in {}", names.join(", "), location);
+                let message = format!("{} was found in {}", names.join(", "), location);
+                if let Err(e) = Self::push_notification(
+                    &notifications,
+                    &notification_id_counter,
+                    "Threat detected".to_string(),
+                    message,
+                    NotificationLevel::Warning,
+                ) {
+                    log::error!("Failed to report real-time detection: {}", e);
+                }
+            }
+        })?;
+
+        // Decoys outside the watched roots still need their directory watched
+        for canary in self.list_canaries()? {
+            if let Some(dir) = canary.path.parent() {
+                if let Err(e) = monitor.ensure_watched(dir) {
+                    log::warn!("Not watching canary {:?}: {}", canary.path, e);
+                }
+            }
+        }
+
+        self.realtime_monitor = Some(monitor);
         Ok(())
     }
 
+    pub fn stop_realtime_protection(&mut self) {
+        if self.realtime_monitor.take().is_some() {
+            log::info!("Stopping real-time protection...");
+        }
+    }
+
+    // One decoy per configured directory; directories that do not exist are skipped
+    pub fn create_canaries(&mut self) -> Result<Vec<Canary>> {
+        let mut planted = Vec::new();
+        for dir in self.canary_config.directories.clone() {
+            if dir.is_dir() {
+                planted.push(self.create_canary(&dir)?);
+            }
+        }
+        Ok(planted)
+    }
+
+    pub fn create_canary(&mut self, dir: &Path) -> Result<Canary> {
+        let mut canaries = self.canaries.lock().map_err(|_| {
+            anyhow::anyhow!("Failed to lock canaries")
+        })?;
+        // A decoy changed or removed while nobody was watching is reported, then replaced
+        if let Some(existing) = canaries.in_dir(dir).cloned() {
+            let action = match existing.state() {
+                CanaryState::Intact => None,
+                CanaryState::Modified => Some(CanaryAction::Modified),
+                CanaryState::Missing => Some(CanaryAction::Deleted),
+            };
+            if let Some(action) = action {
+                canaries.remove(&existing.id)?;
+                Self::record_canary_alert(
+                    &self.notifications,
+                    &self.notification_id_counter,
+                    &self.canary_alerts,
+                    CanaryAlert {
+                        canary: existing,
+                        action,
+                        process: None,
+                        timestamp: Local::now(),
+                    },
+                );
+            }
+        }
+        let canary = canaries.plant(dir, &self.canary_config.names)?;
+        drop(canaries);
+
+        if let Some(monitor) = self.realtime_monitor.as_mut() {
+            monitor.ensure_watched(dir)?;
+        }
+        Ok(canary)
+    }
+
+    pub fn list_canaries(&self) -> Result<Vec<Canary>> {
+        let mut canaries = self.canaries.lock().map_err(|_| {
+            anyhow::anyhow!("Failed to lock canaries")
+        })?;
+        Ok(canaries.canaries().to_vec())
+    }
+
+    pub fn remove_canary(&self, id: &str) -> Result<()> {
+        let canary = self
+            .canaries
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Failed to lock canaries"))?
+            .remove(id)?;
+        log::info!("Removed canary {:?}", canary.path);
+        Ok(())
+    }
+
+    fn record_canary_alert(
+        notifications: &Mutex<Vec<Notification>>,
+        notification_id_counter: &AtomicU64,
+        canary_alerts: &Mutex<Vec<CanaryAlert>>,
+        alert: CanaryAlert,
+    ) {
+        log::error!("Canary tripped: {}", alert.describe());
+        if let Err(e) = Self::push_notification(
+            notifications,
+            notification_id_counter,
+            "Canary file touched".to_string(),
+            alert.describe(),
+            NotificationLevel::Error,
+        ) {
+            log::error!("Failed to report canary alert: {}", e);
+        }
+        if let Ok(mut alerts) = canary_alerts.lock() {
+            alerts.push(alert);
+        }
+    }
+
+    pub fn get_canary_alerts(&self) -> Result<Vec<CanaryAlert>> {
+        let alerts = self.canary_alerts.lock().map_err(|_| {
+            anyhow::anyhow!("Failed to lock canary alerts")
+        })?;
+        Ok(alerts.clone())
+    }
+
+    pub fn get_behavior_alerts(&self) -> Result<Vec<BehaviorAlert>> {
+        let alerts = self.behavior_alerts.lock().map_err(|_| {
+            anyhow::anyhow!("Failed to lock behavior alerts")
+        })?;
+        Ok(alerts.clone())
+    }
+
+    // Lets a process suspended by the behavior monitor continue after a false alarm
+    pub fn resume_process(&self, pid: u32) -> Result<()> {
+        procmem::resume(pid)?;
+        log::info!("Resumed process {}", pid);
+        Ok(())
+    }
+
+    pub fn is_realtime_protection_active(&self) -> bool {
+        self.realtime_monitor
+            .as_ref()
+            .map(|monitor| monitor.is_running())
+            .unwrap_or(false)
+    }
+
     pub fn update_system_health(&mut self) -> Result<()> {
         self.system.refresh_all();
         
@@ -509,6 +1133,12 @@ impl Engine {
             return Err(anyhow::anyhow!("A scan is already in progress"));
         }
 
+        // 0 lets rayon pick one worker per logical CPU
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.worker_threads)
+            .thread_name(|i| format!("scan-worker-{}", i))
+            .build()?;
+
         self.scan_in_progress.store(true, Ordering::SeqCst);
         self.scan_cancelled.store(false, Ordering::SeqCst);
         self.files_scanned.store(0, Ordering::SeqCst);
@@ -518,13 +1148,20 @@ impl Engine {
             sender.send(ScanEvent::Started).ok();
         }
 
+        let exclusions = ScanExclusions::new(&config.excluded_extensions, &config.excluded_paths);
         let mut all_files = Vec::new();
-        
-        for path in &config.target_paths {
+        // Memory and boot scans look at running processes and persistence entries instead of the targets
+        let memory_scan = config.scan_type == ScanType::Memory;
+        let boot_scan = config.scan_type == ScanType::Boot;
+        let targets: &[PathBuf] = if memory_scan || boot_scan { &[] } else { &config.target_paths };
+
+        for path in targets {
             if path.is_dir() {
+                // Excluded directories are pruned here instead of being walked and filtered
                 for entry in WalkDir::new(path)
                     .follow_links(false)
                     .into_iter()
+                    .filter_entry(|e| !exclusions.is_excluded(e.path(), e.file_type().is_dir()))
                     .filter_map(|e| e.ok())
                 {
                     if self.scan_cancelled.load(Ordering::SeqCst) {
@@ -534,64 +1171,145 @@ impl Engine {
                     let path = entry.path();
                     if path.is_file() {
                         if let Ok(metadata) = entry.metadata() {
-                            if metadata.len() <= config.max_file_size {
+                            // Files are streamed, so the size limit is only a policy; 0 disables it
+                            if config.max_file_size == 0 || metadata.len() <= config.max_file_size {
                                 all_files.push(path.to_path_buf());
                             }
                         }
                     }
                 }
-            } else if path.is_file() {
+            } else if path.is_file() && !exclusions.is_excluded(path, false) {
                 all_files.push(path.clone());
             }
         }
 
         let total_files = all_files.len();
-        let signatures = self.threat_signatures.read().map_err(|_| {
-            anyhow::anyhow!("Failed to read threat signatures")
-        })?;
+        let signatures = match self.threat_signatures.read() {
+            Ok(db) => db.clone(),
+            Err(_) => {
+                self.scan_in_progress.store(false, Ordering::SeqCst);
+                return Err(anyhow::anyhow!("Failed to read threat signatures"));
+            }
+        };
+
+        // Unchanged files already found clean against this database are not read again
+        let scan_cache = self.scan_cache_path()
+            .map(|path| Mutex::new(ScanCache::load(&path, signatures.version(), Self::detection_settings(&config))))
+            .ok();
 
-        for (i, file_path) in all_files.iter().enumerate() {
-            if self.scan_cancelled.load(Ordering::SeqCst) {
-                if let Some(sender) = &event_sender {
-                    sender.send(ScanEvent::Cancelled).ok();
+        // Workers report through a single lock so progress reaches the UI in order
+        let reporter = Mutex::new(event_sender.clone());
+        // Hashes of files with no local detection, looked up in batches once the walk is done
+        let reputation_candidates = Mutex::new(Vec::new());
+
+        pool.install(|| {
+            all_files.par_iter().for_each(|file_path| {
+                if self.scan_cancelled.load(Ordering::SeqCst) {
+                    return;
                 }
-                break;
-            }
 
-            self.files_scanned.fetch_add(1, Ordering::SeqCst);
+                let metadata = fs::metadata(file_path).ok();
+                let cached = match (&scan_cache, &metadata) {
+                    (Some(cache), Some(metadata)) => cache.lock().ok().and_then(|mut cache| cache.lookup(file_path, metadata)),
+                    _ => None,
+                };
+                let (threats, file_hash) = match cached {
+                    Some(file_hash) => (Vec::new(), file_hash),
+                    None => {
+                        let (threats, file_hash) = Self::scan_file(&signatures, &config, file_path);
+                        if let (true, Some(cache), Some(metadata)) = (threats.is_empty(), &scan_cache, &metadata) {
+                            if let Ok(mut cache) = cache.lock() {
+                                cache.record_clean(file_path, metadata, file_hash.clone());
+                            }
+                        }
+                        (threats, file_hash)
+                    }
+                };
+                if config.cloud_lookup_enabled && threats.is_empty() {
+                    if let (Some(file_hash), Ok(mut candidates)) = (file_hash, reputation_candidates.lock()) {
+                        candidates.push((file_path.clone(), file_hash));
+                    }
+                }
 
-            if let Some(sender) = &event_sender {
-                sender.send(ScanEvent::Progress { current: i + 1, total: total_files }).ok();
-            }
+                self.report_progress(&reporter, total_files, threats);
+            });
+        });
+
+        if memory_scan {
+            let processes = procmem::processes();
+            pool.install(|| {
+                processes.par_iter().for_each(|process| {
+                    if self.scan_cancelled.load(Ordering::SeqCst) {
+                        return;
+                    }
+
+                    let threats = Self::scan_process(&signatures, process);
+                    // Only a critical match justifies killing a process
+                    let critical = threats.iter().any(|threat| threat.signature.severity == Severity::Critical);
+                    if config.terminate_processes && critical {
+                        if let Err(e) = self.terminate_process(process) {
+                            log::warn!("{}", e);
+                        }
+                    }
+                    self.report_progress(&reporter, processes.len(), threats);
+                });
+            });
+        }
+
+        if boot_scan {
+            let items = Self::persistence_items(persistence::enumerate());
+            pool.install(|| {
+                items.par_iter().for_each(|(path, findings)| {
+                    if self.scan_cancelled.load(Ordering::SeqCst) {
+                        return;
+                    }
 
-            // Scan file for threats
-            if let Ok(content) = fs::read(file_path) {
-                for signature in signatures.iter() {
-                    if memmem::find(&content, &signature.pattern).is_some() {
-                        let threat = DetectedThreat {
-                            signature: signature.clone(),
-                            file_path: file_path.clone(),
-                            offset: 0,
-                            timestamp: Local::now(),
-                        };
-                        
+                    let (mut threats, _) = Self::scan_file(&signatures, &config, path);
+                    threats.extend(findings.iter().cloned());
+                    self.report_progress(&reporter, items.len(), threats);
+                });
+            });
+        }
+
+        if config.cloud_lookup_enabled && !self.scan_cancelled.load(Ordering::SeqCst) {
+            let candidates = reputation_candidates.into_inner().unwrap_or_default();
+            match self.reputation_lookup(&candidates) {
+                ReputationOutcome::Answered(threats) => {
+                    for threat in threats {
                         self.threats_found.fetch_add(1, Ordering::SeqCst);
-                        
                         if let Some(sender) = &event_sender {
                             sender.send(ScanEvent::ThreatFound(threat)).ok();
                         }
-                        
-                        break;
                     }
                 }
+                ReputationOutcome::Disabled => {
+                    log::info!("Reputation service not configured; {} files were only checked locally", candidates.len());
+                }
+            }
+        }
+
+        if self.scan_cancelled.load(Ordering::SeqCst) {
+            if let Some(sender) = &event_sender {
+                sender.send(ScanEvent::Cancelled).ok();
             }
         }
 
-        let files_scanned = self.files_scanned.load(Ordering::SeqCst);
+        let cache = match scan_cache.map(|cache| cache.into_inner()) {
+            Some(Ok(mut cache)) => {
+                let complete = !self.scan_cancelled.load(Ordering::SeqCst);
+                if let Err(e) = cache.save(targets, complete) {
+                    log::warn!("Failed to save scan cache: {}", e);
+                }
+                cache.stats()
+            }
+            _ => ScanCacheStats::default(),
+        };
+
+        let files_scanned = self.files_scanned.load(Ordering::SeqCst) as usize;
         let threats_found = self.threats_found.load(Ordering::SeqCst);
 
         if let Some(sender) = &event_sender {
-            sender.send(ScanEvent::Completed { threats_found, files_scanned }).ok();
+            sender.send(ScanEvent::Completed { threats_found, files_scanned, cache }).ok();
         }
 
         self.scan_in_progress.store(false, Ordering::SeqCst);
@@ -599,19 +1317,535 @@ impl Engine {
         Ok((threats_found, files_scanned))
     }
 
+    // Every configuration file and referenced executable to scan, with the findings about
+    // suspicious locations attached to the configuration file that names them
+    fn persistence_items(entries: Vec<PersistenceEntry>) -> Vec<(PathBuf, Vec<DetectedThreat>)> {
+        let mut items: BTreeMap<PathBuf, Vec<DetectedThreat>> = BTreeMap::new();
+
+        for entry in entries {
+            let suspicious = entry.suspicious_paths();
+            let findings = items.entry(entry.source.clone()).or_default();
+            if !suspicious.is_empty() {
+                let writable = suspicious.iter().any(|path| persistence::in_writable_dir(path));
+                findings.push(DetectedThreat {
+                    signature: ThreatSignature {
+                        name: format!("Persistence.{}.SuspiciousPath", entry.kind),
+                        pattern: Vec::new(),
+                        category: match entry.kind {
+                            PersistenceKind::LdPreload | PersistenceKind::ModuleLoad => ThreatCategory::Rootkit,
+                            _ => ThreatCategory::Trojan,
+                        },
+                        severity: Severity::Warning,
+                    },
+                    file_path: entry.source.clone(),
+                    nested_path: None,
+                    process: None,
+                    offset: 0,
+                    context: format!("line {}: {} ({})", entry.line, entry.command, suspicious.join(", ")),
+                    file_hash: None,
+                    source: DetectionSource::Persistence,
+                    // Temporary directories are never a legitimate home for something started at boot
+                    confidence: if writable { 0.8 } else { 0.6 },
+                    timestamp: Local::now(),
+                });
+            }
+            for executable in entry.executables {
+                items.entry(executable).or_default();
+            }
+        }
+
+        items.into_iter().collect()
+    }
+
+    // Counts one scanned file or process and forwards its threats
+    fn report_progress(
+        &self,
+        reporter: &Mutex<Option<std::sync::mpsc::Sender<ScanEvent>>>,
+        total: usize,
+        threats: Vec<DetectedThreat>,
+    ) {
+        let reporter = match reporter.lock() {
+            Ok(reporter) => reporter,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let current = self.files_scanned.fetch_add(1, Ordering::SeqCst) as usize + 1;
+        self.threats_found.fetch_add(Self::distinct_threats(&threats), Ordering::SeqCst);
+
+        if let Some(sender) = reporter.as_ref() {
+            sender.send(ScanEvent::Progress { current, total }).ok();
+            for threat in threats {
+                sender.send(ScanEvent::ThreatFound(threat)).ok();
+            }
+        }
+    }
+
+    // Every hit is reported, but several hits of one signature in one file count as a single threat
+    fn distinct_threats(threats: &[DetectedThreat]) -> usize {
+        threats
+            .iter()
+            .map(|threat| {
+                (
+                    &threat.file_path,
+                    &threat.nested_path,
+                    threat.process.as_ref().map(|process| process.pid),
+                    &threat.signature.name,
+                )
+            })
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    // Runs the signatures over the anonymous and executable mappings of one process.
+    // Processes we may not trace are skipped quietly.
+    fn scan_process(signatures: &SignatureDatabase, process: &procmem::RunningProcess) -> Vec<DetectedThreat> {
+        let (regions, mem) = match procmem::regions(process.pid).and_then(|regions| {
+            procmem::open_memory(process.pid).map(|mem| (regions, mem))
+        }) {
+            Ok(found) => found,
+            Err(e) => {
+                log::debug!("Skipping process {} ({}): {}", process.pid, process.name, e);
+                return Vec::new();
+            }
+        };
+        let file_path = process
+            .exe
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("/proc/{}", process.pid)));
+
+        let mut threats = Vec::new();
+        for region in regions.iter().filter(|region| region.worth_scanning()) {
+            let matches = match signatures.scan_reader(procmem::RegionReader::new(&mem, region)) {
+                Ok(matches) => matches,
+                Err(_) => continue,
+            };
+            for m in matches {
+                let (start, len) = matcher::context_window(m.offset, m.signature.pattern.len());
+                let len = len.min((region.len() - start) as usize);
+                threats.push(DetectedThreat {
+                    signature: m.signature.clone(),
+                    file_path: file_path.clone(),
+                    nested_path: None,
+                    process: Some(ProcessMatch {
+                        pid: process.pid,
+                        name: process.name.clone(),
+                        region: region.describe(),
+                    }),
+                    offset: (region.start + m.offset) as usize,
+                    context: matcher::format_context(&procmem::read_at(&mem, region.start + start, len)),
+                    file_hash: None,
+                    source: DetectionSource::Signature,
+                    confidence: 1.0,
+                    timestamp: Local::now(),
+                });
+            }
+        }
+
+        threats
+    }
+
+    fn terminate_process(&self, process: &procmem::RunningProcess) -> Result<()> {
+        procmem::terminate_if_unchanged(process)?;
+        log::warn!("Terminated process {} ({})", process.pid, process.name);
+        self.add_notification(
+            "Process terminated".to_string(),
+            format!("{} (pid {}) was running malicious code", process.name, process.pid),
+            NotificationLevel::Warning,
+        )?;
+        Ok(())
+    }
+
+    // Reputation failures never fail the scan; without the service only local detections count
+    fn reputation_lookup(&self, candidates: &[(PathBuf, String)]) -> ReputationOutcome<Vec<DetectedThreat>> {
+        if !self.reputation_config.is_configured() {
+            return ReputationOutcome::Disabled;
+        }
+        if candidates.is_empty() {
+            return ReputationOutcome::Answered(Vec::new());
+        }
+
+        let mut client = match self.reputation_cache_path()
+            .and_then(|path| ReputationClient::new(self.reputation_config.clone(), &path))
+        {
+            Ok(client) => client,
+            Err(e) => {
+                log::warn!("Reputation lookups unavailable: {}", e);
+                return ReputationOutcome::Answered(Vec::new());
+            }
+        };
+
+        let hashes: Vec<String> = candidates.iter().map(|(_, hash)| hash.clone()).collect();
+        let verdicts = match client.lookup(&hashes) {
+            ReputationOutcome::Answered(verdicts) => verdicts,
+            ReputationOutcome::Disabled => return ReputationOutcome::Disabled,
+        };
+
+        let threats = candidates
+            .iter()
+            .filter_map(|(path, hash)| {
+                let verdict = verdicts.get(hash).filter(|verdict| verdict.is_malicious())?;
+                Some(DetectedThreat {
+                    signature: verdict.to_signature(),
+                    file_path: path.clone(),
+                    nested_path: None,
+                    process: None,
+                    offset: 0,
+                    context: String::new(),
+                    file_hash: Some(hash.clone()),
+                    source: DetectionSource::Reputation,
+                    confidence: 1.0,
+                    timestamp: Local::now(),
+                })
+            })
+            .collect();
+        ReputationOutcome::Answered(threats)
+    }
+
+    // Forgets every file recorded as clean, so the next scan reads everything again
+    pub fn invalidate_scan_cache(&self) -> Result<()> {
+        match fs::remove_file(self.scan_cache_path()?) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn scan_cache_path(&self) -> Result<PathBuf> {
+        self.data_path("scan-cache.json")
+    }
+
+    // Anything besides the database that can turn a clean result into a detection
+    fn detection_settings(config: &ScanConfig) -> String {
+        format!(
+            "{}|{}|{}|{:?}",
+            env!("CARGO_PKG_VERSION"),
+            config.heuristic_enabled,
+            config.heuristic_threshold,
+            config.archive_limits
+        )
+    }
+
+    fn reputation_cache_path(&self) -> Result<PathBuf> {
+        self.data_path("reputation-cache.json")
+    }
+
+    fn scan_file(signatures: &SignatureDatabase, config: &ScanConfig, file_path: &Path) -> (Vec<DetectedThreat>, Option<String>) {
+        let file = match File::open(file_path) {
+            Ok(file) => file,
+            Err(e) => {
+                log::debug!("Skipping {:?}: {}", file_path, e);
+                return (Vec::new(), None);
+            }
+        };
+        let (mut threats, file_hash, container) = Self::scan_content(signatures, config, file_path, None, file);
+
+        if let Some(kind) = container {
+            threats.extend(Self::scan_archive(signatures, config, file_path, kind));
+        }
+
+        (threats, file_hash)
+    }
+
+    // Runs every detector over one stream: a file on disk, or an entry extracted from one
+    fn scan_content<R: Read>(
+        signatures: &SignatureDatabase,
+        config: &ScanConfig,
+        file_path: &Path,
+        entry: Option<&ArchiveEntry>,
+        reader: R,
+    ) -> (Vec<DetectedThreat>, Option<String>, Option<ContainerKind>) {
+        // Scan content for threats in fixed-size chunks, hashing it on the way
+        let mut tap = ContentTap::new(reader, RULE_SAMPLE_LIMIT, signatures.needs_md5());
+        let matches = match signatures.scan_reader(&mut tap) {
+            Ok(matches) => matches,
+            Err(e) => {
+                log::debug!("Skipping {:?}: {}", file_path, e);
+                return (Vec::new(), None, None);
+            }
+        };
+        let digests = tap.digests();
+        let file_hash = Some(digests.sha256_hex());
+
+        // Entries are judged by their name inside the archive, placed under the archive itself
+        let analysis_path = match entry {
+            Some(entry) => file_path.join(entry.path.replace('!', "/").trim_start_matches('/')),
+            None => file_path.to_path_buf(),
+        };
+        let context = |offset: u64, pattern_len: usize| match entry {
+            Some(entry) => Self::buffer_context(&entry.data, offset, pattern_len),
+            None => Self::read_match_context(file_path, offset, pattern_len).unwrap_or_default(),
+        };
+        let threat = |signature: ThreatSignature, offset: u64, context: String, source: DetectionSource, confidence: f32| {
+            DetectedThreat {
+                signature,
+                file_path: file_path.to_path_buf(),
+                nested_path: entry.map(|entry| entry.path.clone()),
+                process: None,
+                offset: offset as usize,
+                context,
+                file_hash: file_hash.clone(),
+                source,
+                confidence,
+                timestamp: Local::now(),
+            }
+        };
+
+        let mut threats: Vec<DetectedThreat> = matches
+            .into_iter()
+            .map(|m| {
+                let context = context(m.offset, m.signature.pattern.len());
+                threat(m.signature.clone(), m.offset, context, DetectionSource::Signature, 1.0)
+            })
+            .collect();
+
+        let elf = match entry {
+            Some(entry) if entry.data.starts_with(elf::ELF_MAGIC) => ElfInfo::parse(&entry.data).ok(),
+            Some(_) => None,
+            None => Self::analyze_elf(file_path, tap.sample(), tap.total()),
+        };
+
+        // Rules need random access, so they run over the leading sample of the file
+        for rule in signatures.candidate_rules(tap.sample()) {
+            if let Some(offset) = rule.evaluate(tap.sample(), tap.total(), elf.as_ref()) {
+                threats.push(threat(rule.signature.clone(), offset, context(offset, 0), DetectionSource::Rule, 1.0));
+            }
+        }
+
+        if let Some(signature) = signatures.lookup_hash(&digests, tap.total()) {
+            // A file already caught by the same signature's pattern is not reported twice
+            if !threats.iter().any(|found| found.signature.name == signature.name) {
+                threats.push(threat(signature.clone(), 0, String::new(), DetectionSource::Hash, 1.0));
+            }
+        }
+
+        if let Some(analysis) = scripts::analyze(&analysis_path, tap.sample()) {
+            threats.extend(Self::script_threats(signatures, config, analysis, &threat));
+        }
+
+        // Active document content is held to the heuristic threshold; Info findings are only notes
+        for finding in Self::document_findings(file_path, entry, tap.sample(), tap.total()) {
+            if finding.severity == Severity::Info || finding.confidence < config.heuristic_threshold {
+                log::debug!("{:?}: {}", file_path, finding.description);
+                continue;
+            }
+            threats.push(threat(
+                finding.to_signature(),
+                finding.offset,
+                finding.description,
+                DetectionSource::Document,
+                finding.confidence,
+            ));
+        }
+
+        if config.heuristic_enabled {
+            for finding in heuristics::analyze(&analysis_path, tap.sample(), tap.total(), elf.as_ref()) {
+                if finding.confidence >= config.heuristic_threshold {
+                    threats.push(threat(
+                        finding.to_signature(),
+                        finding.offset,
+                        finding.description,
+                        DetectionSource::Heuristic,
+                        finding.confidence,
+                    ));
+                }
+            }
+        }
+
+        // Nested containers are expanded by the archive walker itself
+        let container = if entry.is_none() && config.archive_limits.max_depth > 0 {
+            archives::container_kind(tap.sample())
+        } else {
+            None
+        };
+
+        (threats, file_hash, container)
+    }
+
+    fn scan_archive(
+        signatures: &SignatureDatabase,
+        config: &ScanConfig,
+        file_path: &Path,
+        kind: ContainerKind,
+    ) -> Vec<DetectedThreat> {
+        let file = match File::open(file_path) {
+            Ok(file) => BufReader::new(file),
+            Err(_) => return Vec::new(),
+        };
+        let name = file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut threats = Vec::new();
+        let walked = archives::walk(file, kind, &name, &config.archive_limits, &mut |entry| {
+            let (found, _, _) = Self::scan_content(signatures, config, file_path, Some(entry), entry.data.as_slice());
+            threats.extend(found);
+        });
+
+        match walked {
+            Ok(stats) => {
+                if stats.truncated {
+                    log::info!(
+                        "Archive {:?} exceeds extraction limits, scanned {} entries",
+                        file_path,
+                        stats.entries
+                    );
+                }
+                if !stats.skipped.is_empty() {
+                    log::info!(
+                        "Archive {:?}: skipped {} oversized entries: {}",
+                        file_path,
+                        stats.skipped.len(),
+                        stats.skipped.join(", ")
+                    );
+                }
+            }
+            Err(e) => log::debug!("Cannot read archive {:?}: {}", file_path, e),
+        }
+
+        threats
+    }
+
+    // Script indicators, plus signatures and rules run again over each base64-decoded layer
+    fn script_threats(
+        signatures: &SignatureDatabase,
+        config: &ScanConfig,
+        analysis: ScriptAnalysis,
+        threat: &dyn Fn(ThreatSignature, u64, String, DetectionSource, f32) -> DetectedThreat,
+    ) -> Vec<DetectedThreat> {
+        let mut threats = Vec::new();
+
+        for finding in analysis.findings {
+            if finding.confidence >= config.heuristic_threshold {
+                threats.push(threat(
+                    finding.to_signature(),
+                    finding.offset,
+                    finding.description,
+                    DetectionSource::Script,
+                    finding.confidence,
+                ));
+            }
+        }
+
+        for layer in &analysis.decoded {
+            let excerpt = |offset: u64, pattern_len: usize| {
+                format!(
+                    "base64 layer {}: {}",
+                    layer.depth,
+                    Self::buffer_context(&layer.data, offset, pattern_len)
+                )
+            };
+
+            for m in signatures.find_matches(&layer.data) {
+                let context = excerpt(m.offset, m.signature.pattern.len());
+                threats.push(threat(m.signature.clone(), layer.offset, context, DetectionSource::Signature, 1.0));
+            }
+            for rule in signatures.candidate_rules(&layer.data) {
+                if let Some(offset) = rule.evaluate(&layer.data, layer.data.len() as u64, None) {
+                    let context = excerpt(offset, 0);
+                    threats.push(threat(rule.signature.clone(), layer.offset, context, DetectionSource::Rule, 1.0));
+                }
+            }
+        }
+
+        threats
+    }
+
+    fn analyze_elf(file_path: &Path, sample: &[u8], filesize: u64) -> Option<ElfInfo> {
+        if !sample.starts_with(elf::ELF_MAGIC) {
+            return None;
+        }
+
+        // Section headers usually sit at the end of the file, past the sample
+        if (sample.len() as u64) < filesize && filesize <= ELF_IMAGE_LIMIT {
+            if let Ok(image) = fs::read(file_path) {
+                return ElfInfo::parse(&image).ok();
+            }
+        }
+
+        match ElfInfo::parse(sample) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                log::debug!("Malformed ELF {:?}: {}", file_path, e);
+                None
+            }
+        }
+    }
+
+    fn document_findings(
+        file_path: &Path,
+        entry: Option<&ArchiveEntry>,
+        sample: &[u8],
+        filesize: u64,
+    ) -> Vec<DocumentFinding> {
+        if !documents::is_document(sample) {
+            return Vec::new();
+        }
+
+        match entry {
+            Some(entry) => documents::analyze(&entry.path, &entry.data),
+            None => {
+                let name = file_path.to_string_lossy();
+                if (sample.len() as u64) < filesize && filesize <= DOCUMENT_IMAGE_LIMIT {
+                    if let Ok(image) = fs::read(file_path) {
+                        return documents::analyze(&name, &image);
+                    }
+                }
+                documents::analyze(&name, sample)
+            }
+        }
+    }
+
+    fn buffer_context(data: &[u8], offset: u64, pattern_len: usize) -> String {
+        let (start, len) = matcher::context_window(offset, pattern_len);
+        let end = (start as usize).saturating_add(len).min(data.len());
+        let start = (start as usize).min(end);
+        matcher::format_context(&data[start..end])
+    }
+
+    fn read_match_context(file_path: &Path, offset: u64, pattern_len: usize) -> io::Result<String> {
+        let (start, len) = matcher::context_window(offset, pattern_len);
+        let mut file = File::open(file_path)?;
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut bytes = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut bytes)?;
+        Ok(matcher::format_context(&bytes))
+    }
+
     pub fn cancel_scan(&self) {
         self.scan_cancelled.store(true, Ordering::SeqCst);
     }
 
     pub fn quarantine(&self, file_path: &Path, threat_name: &str) -> Result<QuarantineItem> {
-        let quarantine_dir = dirs::data_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find data directory"))?
-            .join("clean-master-privacy")
-            .join("quarantine");
+        let file_hash = Self::calculate_file_hash(file_path)?;
+        self.quarantine_with_hash(file_path, threat_name, file_hash)
+    }
+
+    // Reuses the digest computed during the scan instead of reading the file again
+    pub fn quarantine_threat(&self, threat: &DetectedThreat) -> Result<QuarantineItem> {
+        let file_hash = match &threat.file_hash {
+            Some(hash) => hash.clone(),
+            None => Self::calculate_file_hash(&threat.file_path)?,
+        };
+        self.quarantine_with_hash(&threat.file_path, &threat.signature.name, file_hash)
+    }
+
+    fn quarantine_with_hash(&self, file_path: &Path, threat_name: &str, file_hash: String) -> Result<QuarantineItem> {
+        // Moving a packaged file away can break the system; it is restored from its package instead
+        if let Remediation::ReinstallPackage { manager, package } = self.remediation_for(file_path) {
+            return Err(anyhow::anyhow!(
+                "{} belongs to the {} package {}; reinstall the package instead of quarantining it: {}",
+                file_path.display(),
+                manager,
+                package,
+                manager.reinstall_command(&package).join(" ")
+            ));
+        }
+
+        let quarantine_dir = self.data_path("quarantine")?;
 
         fs::create_dir_all(&quarantine_dir)?;
 
-        let file_hash = Self::calculate_file_hash(file_path)?;
         let id = format!("{}_{}", file_hash[..16].to_string(), Local::now().timestamp());
         
         let quarantine_path = quarantine_dir.join(&id);
@@ -634,6 +1868,16 @@ impl Engine {
         Ok(item)
     }
 
+    pub fn remediation_for(&self, file_path: &Path) -> Remediation {
+        let owners = self
+            .package_owners
+            .get_or_init(|| PackageOwners::load(&self.package_verify_config.path_prefixes));
+        match owners.owner(file_path) {
+            Some((manager, package)) => Remediation::ReinstallPackage { manager, package },
+            None => Remediation::Quarantine,
+        }
+    }
+
     fn calculate_file_hash(file_path: &Path) -> Result<String> {
         let mut file = File::open(file_path)?;
         let mut hasher = Sha256::new();
@@ -858,6 +2102,122 @@ impl Engine {
         Ok(items)
     }
 
+    // Replaces any previous baseline; returns the number of files recorded
+    pub fn build_integrity_baseline(&self) -> Result<usize> {
+        let baseline = IntegrityBaseline::build(&self.integrity_config.paths, &self.integrity_config.excluded_paths);
+        baseline.save(&self.integrity_baseline_path()?)?;
+        log::info!("Integrity baseline recorded {} files", baseline.files.len());
+        Ok(baseline.files.len())
+    }
+
+    // Hashes every file under the baseline roots, so it needs neither the engine nor its lock
+    // and belongs on a worker thread; the caller passes integrity_baseline_path() along
+    pub fn check_integrity(baseline_path: Option<PathBuf>) -> Result<Vec<AuditItem>> {
+        let unavailable = |description: String, recommendation: &str| AuditItem {
+            id: "integrity".to_string(),
+            title: "File Integrity".to_string(),
+            description,
+            status: AuditStatus::Warning,
+            severity: Severity::Warning,
+            recommendation: recommendation.to_string(),
+        };
+
+        let Some(path) = baseline_path else {
+            return Ok(vec![unavailable(
+                "Could not find data directory".to_string(),
+                "Set XDG_DATA_HOME or HOME so a baseline can be stored",
+            )]);
+        };
+        match IntegrityBaseline::load(&path)? {
+            Some(baseline) => Ok(Self::integrity_items(&baseline)),
+            None => Ok(vec![unavailable(
+                "No integrity baseline has been built yet".to_string(),
+                "Build a baseline while the system is known to be clean",
+            )]),
+        }
+    }
+
+    // Accepts intentional changes to the given files; an empty list accepts every change
+    pub fn update_integrity_baseline(&self, paths: &[PathBuf]) -> Result<usize> {
+        let path = self.integrity_baseline_path()?;
+        let mut baseline = IntegrityBaseline::load(&path)?
+            .ok_or_else(|| anyhow::anyhow!("No integrity baseline has been built yet"))?;
+
+        let paths: Vec<PathBuf> = if paths.is_empty() {
+            baseline.diff().into_iter().map(|change| change.path).collect()
+        } else {
+            paths.to_vec()
+        };
+        baseline.refresh(&paths);
+        baseline.save(&path)?;
+        Ok(paths.len())
+    }
+
+    fn integrity_items(baseline: &IntegrityBaseline) -> Vec<AuditItem> {
+        let changes = baseline.diff();
+        if !changes.is_empty() {
+            return changes.iter().map(IntegrityChange::to_audit_item).collect();
+        }
+
+        vec![AuditItem {
+            id: "integrity".to_string(),
+            title: "File Integrity".to_string(),
+            description: format!(
+                "{} files match the baseline of {}",
+                baseline.files.len(),
+                baseline.updated.format("%Y-%m-%d %H:%M")
+            ),
+            status: AuditStatus::Pass,
+            severity: Severity::Ok,
+            recommendation: "Update the baseline after installing or upgrading packages".to_string(),
+        }]
+    }
+
+    // Checks installed files against the checksums recorded by dpkg and rpm. Reads every
+    // packaged file below the prefixes, so like check_integrity it runs without the engine.
+    // None when there is no dpkg or rpm database to verify against
+    pub fn verify_packages(path_prefixes: &[PathBuf]) -> Option<Vec<PackageMismatch>> {
+        let mismatches = packages::verify(path_prefixes);
+        match &mismatches {
+            Some(mismatches) => log::info!("Package verification found {} mismatched files", mismatches.len()),
+            None => log::info!("No package database found; package files were not verified"),
+        }
+        mismatches
+    }
+
+    pub fn check_packages(path_prefixes: &[PathBuf]) -> Vec<AuditItem> {
+        Self::package_items(Self::verify_packages(path_prefixes))
+    }
+
+    fn package_items(mismatches: Option<Vec<PackageMismatch>>) -> Vec<AuditItem> {
+        let Some(mismatches) = mismatches else {
+            return vec![AuditItem {
+                id: "packages".to_string(),
+                title: "Package Integrity".to_string(),
+                description: "No package database found; package files were not verified".to_string(),
+                status: AuditStatus::Warning,
+                severity: Severity::Info,
+                recommendation: "Use the integrity baseline to watch system files instead".to_string(),
+            }];
+        };
+        if !mismatches.is_empty() {
+            return mismatches.iter().map(PackageMismatch::to_audit_item).collect();
+        }
+
+        vec![AuditItem {
+            id: "packages".to_string(),
+            title: "Package Integrity".to_string(),
+            description: "Installed system files match their package checksums".to_string(),
+            status: AuditStatus::Pass,
+            severity: Severity::Ok,
+            recommendation: "Keep packages up to date".to_string(),
+        }]
+    }
+
+    pub fn integrity_baseline_path(&self) -> Result<PathBuf> {
+        self.data_path("integrity-baseline.json")
+    }
+
     pub fn get_hardware_info(&self) -> Result<HardwareInfo> {
         self.system.refresh_all();
 
@@ -973,7 +2333,18 @@ impl Engine {
     }
 
     pub fn add_notification(&self, title: String, message: String, level: NotificationLevel) -> Result<u64> {
-        let id = self.notification_id_counter.fetch_add(1, Ordering::SeqCst);
+        Self::push_notification(&self.notifications, &self.notification_id_counter, title, message, level)
+    }
+
+    // Shared with background threads that only hold the notification list
+    fn push_notification(
+        notifications: &Mutex<Vec<Notification>>,
+        notification_id_counter: &AtomicU64,
+        title: String,
+        message: String,
+        level: NotificationLevel,
+    ) -> Result<u64> {
+        let id = notification_id_counter.fetch_add(1, Ordering::SeqCst);
         
         let notification = Notification {
             id,
@@ -983,7 +2354,7 @@ impl Engine {
             timestamp: SystemTime::now(),
         };
 
-        let mut notifications = self.notifications.lock().map_err(|_| {
+        let mut notifications = notifications.lock().map_err(|_| {
             anyhow::anyhow!("Failed to lock notifications")
         })?;
         notifications.push(notification);
@@ -1110,3 +2481,373 @@ impl Default for Localization {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{mpsc, OnceLock};
+
+    // Caches, baselines and quarantine of every test engine live here instead of the user's data directory
+    fn data_home() -> &'static Path {
+        static DATA_HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+        DATA_HOME.get_or_init(|| tempfile::tempdir().unwrap()).path()
+    }
+
+    fn test_engine(signatures: Vec<ThreatSignature>) -> Engine {
+        let engine = Engine::with_data_dir(Some(data_home().to_path_buf())).unwrap();
+        *engine.threat_signatures.write().unwrap() = Arc::new(SignatureDatabase::new(signatures).unwrap());
+        engine
+    }
+
+    fn signature(name: &str, pattern: &[u8], severity: Severity) -> ThreatSignature {
+        ThreatSignature {
+            name: name.to_string(),
+            pattern: pattern.to_vec(),
+            category: ThreatCategory::Trojan,
+            severity,
+        }
+    }
+
+    fn scan_config(targets: Vec<PathBuf>) -> ScanConfig {
+        ScanConfig {
+            target_paths: targets,
+            scan_type: ScanType::Custom,
+            heuristic_enabled: false,
+            heuristic_threshold: 0.6,
+            cloud_lookup_enabled: false,
+            max_file_size: 0,
+            excluded_extensions: Vec::new(),
+            excluded_paths: Vec::new(),
+            worker_threads: 4,
+            archive_limits: ArchiveLimits::default(),
+            terminate_processes: false,
+        }
+    }
+
+    fn run_scan(engine: &Engine, config: ScanConfig) -> Vec<ScanEvent> {
+        let (tx, rx) = mpsc::channel();
+        engine.scan(config, Some(tx)).unwrap();
+        rx.into_iter().collect()
+    }
+
+    fn found(events: &[ScanEvent]) -> Vec<DetectedThreat> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ScanEvent::ThreatFound(threat) => Some(threat.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parallel_scan_reports_progress_in_order() {
+        let engine = test_engine(vec![signature("Test.Marker", b"evil-marker", Severity::Critical)]);
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..40 {
+            let body = if i % 10 == 0 { "xx evil-marker xx" } else { "harmless" };
+            fs::write(dir.path().join(format!("file{}.txt", i)), body).unwrap();
+        }
+
+        let events = run_scan(&engine, scan_config(vec![dir.path().to_path_buf()]));
+
+        let progress: Vec<(usize, usize)> = events
+            .iter()
+            .filter_map(|event| match event {
+                ScanEvent::Progress { current, total } => Some((*current, *total)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(progress, (1..=40).map(|current| (current, 40)).collect::<Vec<_>>());
+        assert_eq!(found(&events).len(), 4);
+        assert!(matches!(events.first(), Some(ScanEvent::Started)));
+        assert!(matches!(
+            events.last(),
+            Some(ScanEvent::Completed { threats_found: 4, files_scanned: 40, .. })
+        ));
+        assert!(!engine.is_scanning());
+    }
+
+    #[test]
+    fn threats_carry_offsets_and_context() {
+        let engine = test_engine(vec![signature("Test.Marker", b"evil-marker", Severity::Critical)]);
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("twice.bin");
+        fs::write(&file, b"0123456789evil-marker....evil-marker").unwrap();
+
+        let mut threats = found(&run_scan(&engine, scan_config(vec![file.clone()])));
+        threats.sort_by_key(|threat| threat.offset);
+
+        assert_eq!(threats.iter().map(|threat| threat.offset).collect::<Vec<_>>(), vec![10, 25]);
+        assert!(threats[0].context.ends_with("| 0123456789evil-marker....evil-marker"));
+        assert_eq!(threats[0].file_path, file);
+        assert_eq!(threats[0].source, DetectionSource::Signature);
+    }
+
+    #[test]
+    fn document_findings_respect_the_threshold() {
+        let engine = test_engine(vec![signature("Test.Marker", b"evil-marker", Severity::Critical)]);
+        let dir = tempfile::tempdir().unwrap();
+        let page_jump = dir.path().join("page.pdf");
+        let script = dir.path().join("script.pdf");
+        fs::write(&page_jump, "%PDF-1.7\n1 0 obj << /OpenAction [3 0 R /Fit] >> endobj\n%%EOF\n").unwrap();
+        fs::write(&script, "%PDF-1.7\n1 0 obj << /OpenAction << /S /JavaScript /JS (app.alert(1)) >> >>\n%%EOF\n").unwrap();
+
+        let mut config = scan_config(vec![dir.path().to_path_buf()]);
+        let events = run_scan(&engine, config.clone());
+        let threats = found(&events);
+        assert_eq!(threats.len(), 1);
+        assert_eq!(threats[0].file_path, script);
+        assert_eq!(threats[0].signature.name, "Document.PDF.AutoJavaScript");
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, ScanEvent::Completed { threats_found: 1, .. })));
+
+        // Info findings stay out even when the threshold would let them through
+        config.heuristic_threshold = 0.1;
+        assert_eq!(found(&run_scan(&engine, config.clone())).len(), 1);
+        config.heuristic_threshold = 0.9;
+        assert!(found(&run_scan(&engine, config)).is_empty());
+    }
+
+    #[test]
+    fn excluded_paths_are_not_walked() {
+        let engine = test_engine(vec![signature("Test.Marker", b"evil-marker", Severity::Critical)]);
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("node_modules/pkg/index.js"), "evil-marker").unwrap();
+        fs::write(dir.path().join("notes.tmp"), "evil-marker").unwrap();
+        fs::write(dir.path().join("kept.txt"), "evil-marker").unwrap();
+
+        let mut config = scan_config(vec![dir.path().to_path_buf()]);
+        config.excluded_paths = vec![PathBuf::from("**/node_modules/**")];
+        config.excluded_extensions = vec![".tmp".to_string()];
+        let threats = found(&run_scan(&engine, config));
+
+        assert_eq!(threats.len(), 1);
+        assert_eq!(threats[0].file_path, dir.path().join("kept.txt"));
+    }
+
+    #[test]
+    fn eicar_counts_once_despite_pattern_and_hash() {
+        let engine = test_engine(Vec::new());
+        let mut database = SignatureDatabase::new(Engine::load_builtin_signatures()).unwrap();
+        database.add_hash_signatures(Engine::load_builtin_hash_signatures());
+        *engine.threat_signatures.write().unwrap() = Arc::new(database);
+
+        let dir = tempfile::tempdir().unwrap();
+        let eicar = Engine::load_builtin_signatures().remove(0).pattern;
+        fs::write(dir.path().join("eicar.com"), &eicar).unwrap();
+        fs::write(dir.path().join("twice.txt"), [&eicar[..], b" ", &eicar[..]].concat()).unwrap();
+
+        let events = run_scan(&engine, scan_config(vec![dir.path().to_path_buf()]));
+        let threats = found(&events);
+
+        let eicar_file: Vec<_> = threats.iter().filter(|t| t.file_path.ends_with("eicar.com")).collect();
+        assert_eq!(eicar_file.len(), 1);
+        assert_eq!(
+            eicar_file[0].file_hash.as_deref(),
+            Some("275a021bbfb6489e54d471899f7db9d1663fc695ec2fe2a2c4538aabf651fd0f")
+        );
+        // Both hits in the second file are reported, but they are one threat
+        assert_eq!(threats.len(), 3);
+        assert!(matches!(events.last(), Some(ScanEvent::Completed { threats_found: 2, .. })));
+    }
+
+    #[test]
+    fn clamav_offsets_past_the_rule_sample_are_not_imported() {
+        let dir = tempfile::tempdir().unwrap();
+        let edge = RULE_SAMPLE_LIMIT - 8;
+        fs::write(
+            dir.path().join("main.ndb"),
+            format!("Unix.Trojan.Edge-1:0:{}:45??4745\nUnix.Trojan.Tail-1:0:EOF-4:54??494c\n", edge),
+        )
+        .unwrap();
+        assert_eq!(clamav::load_clamav_dir(dir.path()).skipped, 1);
+        SignatureManifest::build(dir.path(), 1).unwrap().write(dir.path()).unwrap();
+
+        let engine = test_engine(Vec::new());
+        *engine.threat_signatures.write().unwrap() = Arc::new(Engine::load_signature_database(dir.path(), 1).unwrap());
+
+        let target = tempfile::tempdir().unwrap();
+        let mut data = vec![0u8; RULE_SAMPLE_LIMIT + 1024 * 1024];
+        data[edge..edge + 4].copy_from_slice(b"EDGE");
+        let len = data.len();
+        data[len - 4..].copy_from_slice(b"TAIL");
+        fs::write(target.path().join("large.bin"), &data).unwrap();
+
+        let threats = found(&run_scan(&engine, scan_config(vec![target.path().to_path_buf()])));
+        let names: Vec<_> = threats.iter().map(|t| t.signature.name.as_str()).collect();
+        assert_eq!(names, vec!["Unix.Trojan.Edge-1"]);
+    }
+
+    #[test]
+    fn missing_database_does_not_downgrade_to_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(Engine::load_signature_database(dir.path(), 0).unwrap().version(), 0);
+        assert!(Engine::load_signature_database(dir.path(), 4).is_err());
+
+        fs::write(dir.path().join("main.hdb"), "44d88612fea8a8f36de82e1278abb02f:68:Eicar\n").unwrap();
+        assert!(Engine::load_signature_database(dir.path(), 0).is_err());
+
+        SignatureManifest::build(dir.path(), 3).unwrap().write(dir.path()).unwrap();
+        assert!(Engine::load_signature_database(dir.path(), 4).is_err());
+        let database = Engine::load_signature_database(dir.path(), 3).unwrap();
+        assert_eq!(database.version(), 3);
+    }
+
+    #[test]
+    fn mirror_updates_respect_a_rollback() {
+        use std::collections::HashMap;
+        use updater::tests::{package, package_ref, serve};
+
+        const V1: &str = "44d88612fea8a8f36de82e1278abb02f:68:Eicar-v1\n";
+        const V2: &str = "44d88612fea8a8f36de82e1278abb02f:68:Eicar-v2\n";
+        let mirror = |version: u64, files: &[(&str, &str)]| {
+            let full = package(version, None, files, &[], files);
+            let name = format!("full-{}.json", version);
+            let index = UpdateIndex {
+                latest: version,
+                full: package_ref(version, &name, &full),
+                deltas: Vec::new(),
+            };
+            serve(HashMap::from([
+                ("/index.json".to_string(), serde_json::to_vec(&index).unwrap()),
+                (format!("/{}", name), full),
+            ]))
+        };
+
+        let engine = Mutex::new(test_engine(Vec::new()));
+        assert!(matches!(Engine::update_from_mirror(&engine), Ok(UpdateOutcome::Disabled)));
+
+        let set_mirror = |url: String| {
+            let mut engine = engine.lock().unwrap();
+            engine.update_config.mirror_url = url;
+            engine.update_config.enabled = true;
+        };
+        set_mirror(mirror(1, &[("main.hdb", V1)]));
+        assert!(matches!(
+            Engine::update_from_mirror(&engine),
+            Ok(UpdateOutcome::Updated { from: 0, to: 1, packages: 1 })
+        ));
+        assert!(matches!(Engine::update_from_mirror(&engine), Ok(UpdateOutcome::UpToDate(1))));
+
+        set_mirror(mirror(2, &[("main.hdb", V2)]));
+        assert!(matches!(Engine::update_from_mirror(&engine), Ok(UpdateOutcome::Updated { to: 2, .. })));
+
+        assert_eq!(engine.lock().unwrap().rollback_signature_database().unwrap(), 1);
+        assert!(matches!(Engine::update_from_mirror(&engine), Ok(UpdateOutcome::Pinned(1))));
+        assert_eq!(engine.lock().unwrap().get_signature_database_info().unwrap().version, 1);
+
+        // Restarting keeps the pin
+        engine.lock().unwrap().update_threat_database().unwrap();
+        assert!(matches!(Engine::update_from_mirror(&engine), Ok(UpdateOutcome::Pinned(1))));
+
+        engine.lock().unwrap().resume_signature_updates().unwrap();
+        assert!(matches!(
+            Engine::update_from_mirror(&engine),
+            Ok(UpdateOutcome::Updated { from: 1, to: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn replants_decoys_changed_while_unwatched() {
+        let data = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let mut engine = test_engine(Vec::new());
+        assert!(!engine.canary_config.enabled);
+        *engine.canaries.lock().unwrap() = CanaryStore::new(Some(data.path().join("canaries.json")));
+
+        let canary = engine.create_canary(target.path()).unwrap();
+        assert_eq!(engine.create_canary(target.path()).unwrap().id, canary.id);
+        assert!(engine.get_canary_alerts().unwrap().is_empty());
+
+        fs::remove_file(&canary.path).unwrap();
+        let replanted = engine.create_canary(target.path()).unwrap();
+        assert_eq!(replanted.state(), CanaryState::Intact);
+        let alerts = engine.get_canary_alerts().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert!(matches!(alerts[0].action, CanaryAction::Deleted));
+        assert_eq!(alerts[0].canary.id, canary.id);
+
+        fs::write(&replanted.path, b"encrypted").unwrap();
+        let third = engine.create_canary(target.path()).unwrap();
+        assert_ne!(third.id, replanted.id);
+        assert!(matches!(engine.get_canary_alerts().unwrap()[1].action, CanaryAction::Modified));
+        assert_eq!(engine.list_canaries().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn integrity_check_without_a_baseline_is_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let items = Engine::check_integrity(Some(dir.path().join("integrity-baseline.json"))).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "integrity");
+        assert_eq!(items[0].status, AuditStatus::Warning);
+        // The audit itself no longer hashes anything
+        let engine = test_engine(Vec::new());
+        assert!(engine.security_audit().unwrap().iter().all(|item| !item.id.starts_with("integrity")));
+    }
+
+    #[test]
+    fn package_checks_stay_out_of_the_audit() {
+        let engine = test_engine(Vec::new());
+        let ids: Vec<String> = engine.security_audit().unwrap().into_iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec!["firewall", "updates", "password_policy"]);
+
+        // Nothing outside the verified system prefixes is treated as packaged
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("dropper");
+        fs::write(&file, b"payload").unwrap();
+        assert_eq!(engine.remediation_for(&file), Remediation::Quarantine);
+        assert!(engine.quarantine(&file, "Test.Dropper").is_ok());
+    }
+
+    #[test]
+    fn package_checks_without_a_database_are_a_warning() {
+        let items = Engine::package_items(None);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "packages");
+        assert_eq!(items[0].status, AuditStatus::Warning);
+        assert!(items[0].description.starts_with("No package database found"));
+
+        assert_eq!(Engine::package_items(Some(Vec::new()))[0].status, AuditStatus::Pass);
+    }
+
+    #[test]
+    fn keeps_its_state_in_the_given_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = Engine::with_data_dir(Some(dir.path().to_path_buf())).unwrap();
+        assert_eq!(engine.integrity_baseline_path().unwrap(), dir.path().join("integrity-baseline.json"));
+
+        let file = dir.path().join("sample");
+        fs::write(&file, b"payload").unwrap();
+        let item = engine.quarantine(&file, "Test.Sample").unwrap();
+        assert!(item.quarantine_path.starts_with(dir.path().join("quarantine")));
+    }
+
+    #[test]
+    fn persists_nothing_without_a_data_dir() {
+        let engine = Engine::with_data_dir(None).unwrap();
+        assert!(engine.integrity_baseline_path().is_err());
+        assert!(engine.signature_history().is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("sample");
+        fs::write(&file, b"payload").unwrap();
+        assert!(engine.quarantine(&file, "Test.Sample").is_err());
+        assert!(file.exists());
+
+        let items = Engine::check_integrity(None).unwrap();
+        assert_eq!(items[0].status, AuditStatus::Warning);
+    }
+
+    #[test]
+    fn reputation_lookups_are_off_until_configured() {
+        let engine = test_engine(Vec::new());
+        let candidates = vec![(PathBuf::from("/tmp/unknown"), "ab".repeat(32))];
+
+        assert!(matches!(engine.reputation_lookup(&candidates), ReputationOutcome::Disabled));
+    }
+}
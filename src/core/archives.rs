@@ -0,0 +1,461 @@
+use std::io::{self, BufReader, Cursor, Read, Seek};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::mail;
+
+// ==================== ARŞİV TARAMA ====================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    Zip,
+    Tar,
+    Gzip,
+    Bzip2,
+    Xz,
+    // Attachments are the entries of a mailbox or a single message
+    Mbox,
+    Message,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveLimits {
+    // 0 disables archive scanning
+    pub max_depth: usize,
+    pub max_entries: usize,
+    pub max_expanded_size: u64,
+    pub max_entry_size: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        ArchiveLimits {
+            max_depth: 3,
+            max_entries: 10_000,
+            max_expanded_size: 512 * 1024 * 1024,
+            max_entry_size: 128 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    // Path inside the outer file; nested containers are separated by '!'
+    pub path: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveStats {
+    pub entries: usize,
+    pub expanded: u64,
+    // Set when a limit stopped extraction early
+    pub truncated: bool,
+    // Entries over the per-entry size limit, read past without scanning
+    pub skipped: Vec<String>,
+}
+
+pub fn container_kind(head: &[u8]) -> Option<ContainerKind> {
+    if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        Some(ContainerKind::Zip)
+    } else if head.starts_with(b"\x1f\x8b") {
+        Some(ContainerKind::Gzip)
+    } else if head.starts_with(b"BZh") {
+        Some(ContainerKind::Bzip2)
+    } else if head.starts_with(b"\xfd7zXZ\x00") {
+        Some(ContainerKind::Xz)
+    } else if head.get(257..262) == Some(b"ustar") {
+        Some(ContainerKind::Tar)
+    } else if mail::is_mbox(head) {
+        Some(ContainerKind::Mbox)
+    } else if mail::is_message(head) {
+        Some(ContainerKind::Message)
+    } else {
+        None
+    }
+}
+
+// Extracts every entry in memory, recursing into nested containers, and hands each one to `visit`
+pub fn walk<R: Read + Seek>(
+    reader: R,
+    kind: ContainerKind,
+    name: &str,
+    limits: &ArchiveLimits,
+    visit: &mut dyn FnMut(&ArchiveEntry),
+) -> Result<ArchiveStats> {
+    let mut walker = Walker {
+        limits,
+        stats: ArchiveStats::default(),
+        visit,
+    };
+    walker.container(reader, kind, name, None, 1)?;
+    Ok(walker.stats)
+}
+
+struct Walker<'a> {
+    limits: &'a ArchiveLimits,
+    stats: ArchiveStats,
+    visit: &'a mut dyn FnMut(&ArchiveEntry),
+}
+
+impl Walker<'_> {
+    fn container<R: Read + Seek>(
+        &mut self,
+        reader: R,
+        kind: ContainerKind,
+        name: &str,
+        prefix: Option<&str>,
+        depth: usize,
+    ) -> Result<()> {
+        match kind {
+            ContainerKind::Zip => {
+                let mut archive = zip::ZipArchive::new(reader)?;
+                for i in 0..archive.len() {
+                    if self.stats.truncated {
+                        break;
+                    }
+                    let entry = match archive.by_index(i) {
+                        Ok(entry) => entry,
+                        // Encrypted or unsupported entries are skipped, not fatal
+                        Err(e) => {
+                            log::debug!("Skipping zip entry {} in {}: {}", i, name, e);
+                            continue;
+                        }
+                    };
+                    if !entry.is_file() {
+                        continue;
+                    }
+                    let path = join(prefix, entry.name());
+                    if let Some(data) = self.read_entry(entry, &path)? {
+                        self.entry(path, data, depth)?;
+                    }
+                }
+            }
+            ContainerKind::Tar => {
+                let mut archive = tar::Archive::new(reader);
+                for entry in archive.entries()? {
+                    if self.stats.truncated {
+                        break;
+                    }
+                    let entry = entry?;
+                    if !entry.header().entry_type().is_file() {
+                        continue;
+                    }
+                    let path = join(prefix, &entry.path()?.to_string_lossy());
+                    if let Some(data) = self.read_entry(entry, &path)? {
+                        self.entry(path, data, depth)?;
+                    }
+                }
+            }
+            ContainerKind::Gzip | ContainerKind::Bzip2 | ContainerKind::Xz => {
+                let path = join(prefix, &decompressed_name(name, kind));
+                let data = match kind {
+                    ContainerKind::Gzip => self.read_entry(flate2::read::MultiGzDecoder::new(reader), &path)?,
+                    ContainerKind::Bzip2 => self.read_entry(bzip2::read::MultiBzDecoder::new(reader), &path)?,
+                    _ => self.read_entry(xz2::read::XzDecoder::new_multi_decoder(reader), &path)?,
+                };
+                let Some(data) = data else {
+                    return Ok(());
+                };
+
+                // A compressed tar or zip is looked through rather than reported as one more level
+                match container_kind(&data) {
+                    Some(inner) if matches!(inner, ContainerKind::Tar | ContainerKind::Zip) => {
+                        self.container(Cursor::new(data), inner, name, prefix, depth)?;
+                    }
+                    _ => self.entry(path, data, depth)?,
+                }
+            }
+            ContainerKind::Mbox | ContainerKind::Message => {
+                // base64 grows attachments by a third, so raw messages get twice the entry budget
+                let max_message = self.limits.max_entry_size.saturating_mul(2);
+                let mut mailbox = mail::Mailbox::new(BufReader::new(reader), kind == ContainerKind::Mbox, max_message);
+                let mut index = 0;
+                while let Some(raw) = mailbox.next_message()? {
+                    if self.stats.truncated {
+                        break;
+                    }
+                    index += 1;
+                    let message = mail::parse_message(&raw);
+                    // "message-id!attachment", numbered by position when the message has no id
+                    let id = message.message_id.unwrap_or_else(|| format!("message-{}", index));
+                    for attachment in message.attachments {
+                        let path = join(prefix, &format!("{}!{}", id, attachment.name));
+                        if let Some(data) = self.read_entry(attachment.data.as_slice(), &path)? {
+                            self.entry(path, data, depth)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn entry(&mut self, path: String, data: Vec<u8>, depth: usize) -> Result<()> {
+        let entry = ArchiveEntry { path, data };
+        (self.visit)(&entry);
+
+        if let Some(kind) = container_kind(&entry.data) {
+            if depth >= self.limits.max_depth {
+                log::debug!("Not descending into {}: nesting limit reached", entry.path);
+                return Ok(());
+            }
+            let name = entry.path.rsplit(['!', '/']).next().unwrap_or_default().to_string();
+            // A damaged inner archive must not hide the rest of the outer one
+            if let Err(e) = self.container(Cursor::new(entry.data), kind, &name, Some(&entry.path), depth + 1) {
+                log::debug!("Cannot read nested archive {}: {}", entry.path, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Reads one entry within the remaining budget; None when the entry is skipped or a limit is hit.
+    // An entry over the per-entry limit is drained and skipped, and only the entry count or the
+    // total expansion budget end the walk.
+    fn read_entry<R: Read>(&mut self, mut reader: R, path: &str) -> io::Result<Option<Vec<u8>>> {
+        self.stats.entries += 1;
+        if self.stats.entries > self.limits.max_entries {
+            log::debug!("Archive entry limit reached");
+            self.stats.truncated = true;
+            return Ok(None);
+        }
+
+        let remaining = self.limits.max_expanded_size.saturating_sub(self.stats.expanded);
+        let allowed = self.limits.max_entry_size.min(remaining);
+        let mut data = Vec::new();
+        (&mut reader).take(allowed + 1).read_to_end(&mut data)?;
+
+        let read = data.len() as u64;
+        if read <= allowed {
+            self.stats.expanded += read;
+            return Ok(Some(data));
+        }
+
+        // Draining still decompresses, so it is paid for from the total budget
+        let drained = if allowed < remaining {
+            read + io::copy(&mut reader.take(remaining - read + 1), &mut io::sink())?
+        } else {
+            read
+        };
+        if drained > remaining {
+            log::debug!("Archive expansion limit reached");
+            self.stats.truncated = true;
+            return Ok(None);
+        }
+
+        log::debug!("Skipping archive entry {} over the entry size limit", path);
+        self.stats.expanded += drained;
+        self.stats.skipped.push(path.to_string());
+        Ok(None)
+    }
+}
+
+fn join(prefix: Option<&str>, path: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}!{}", prefix, path),
+        None => path.to_string(),
+    }
+}
+
+// "eicar.txt.gz" -> "eicar.txt"
+fn decompressed_name(name: &str, kind: ContainerKind) -> String {
+    let suffixes: &[&str] = match kind {
+        ContainerKind::Gzip => &[".gz", ".tgz", ".z"],
+        ContainerKind::Bzip2 => &[".bz2", ".tbz2", ".tbz"],
+        _ => &[".xz", ".txz"],
+    };
+    let lower = name.to_lowercase();
+    suffixes
+        .iter()
+        .find(|suffix| lower.ends_with(*suffix))
+        .map(|suffix| name[..name.len() - suffix.len()].to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine as _;
+    use std::io::Write;
+
+    fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn walk_all(data: &[u8], name: &str, limits: &ArchiveLimits) -> (Result<ArchiveStats>, Vec<(String, usize)>) {
+        let mut seen = Vec::new();
+        let kind = container_kind(data).expect("container");
+        let stats = walk(Cursor::new(data), kind, name, limits, &mut |entry| {
+            seen.push((entry.path.clone(), entry.data.len()));
+        });
+        (stats, seen)
+    }
+
+    fn paths(seen: &[(String, usize)]) -> Vec<&str> {
+        seen.iter().map(|(path, _)| path.as_str()).collect()
+    }
+
+    #[test]
+    fn detects_container_kinds() {
+        assert_eq!(container_kind(&zip(&[("a", b"1")])), Some(ContainerKind::Zip));
+        assert_eq!(container_kind(&tar(&[("a", b"1")])), Some(ContainerKind::Tar));
+        assert_eq!(container_kind(&gzip(b"x")), Some(ContainerKind::Gzip));
+        assert_eq!(container_kind(b"BZh91AY&SY"), Some(ContainerKind::Bzip2));
+        assert_eq!(container_kind(b"\xfd7zXZ\x00\x00"), Some(ContainerKind::Xz));
+        assert_eq!(container_kind(b"plain text"), None);
+        assert_eq!(container_kind(b""), None);
+    }
+
+    #[test]
+    fn walks_nested_containers() {
+        let inner = zip(&[("payload.bin", b"evil"), ("readme.txt", b"hi")]);
+        let outer = gzip(&tar(&[("dir/inner.zip", &inner), ("dir/notes.txt", b"ok")]));
+
+        let (stats, seen) = walk_all(&outer, "bundle.tar.gz", &ArchiveLimits::default());
+        let stats = stats.unwrap();
+        assert_eq!(
+            paths(&seen),
+            vec!["dir/inner.zip", "dir/inner.zip!payload.bin", "dir/inner.zip!readme.txt", "dir/notes.txt"]
+        );
+        assert_eq!(stats.entries, 5);
+        assert!(!stats.truncated && stats.skipped.is_empty());
+
+        let (_, seen) = walk_all(&gzip(b"eicar"), "eicar.txt.GZ", &ArchiveLimits::default());
+        assert_eq!(seen, vec![("eicar.txt".to_string(), 5)]);
+    }
+
+    #[test]
+    fn oversized_entry_is_skipped_and_the_walk_continues() {
+        let limits = ArchiveLimits {
+            max_entry_size: 10,
+            ..ArchiveLimits::default()
+        };
+        let big = [b'x'; 100];
+        for archive in [
+            zip(&[("a.txt", b"first"), ("big.bin", &big), ("c.txt", b"last")]),
+            tar(&[("a.txt", b"first"), ("big.bin", &big), ("c.txt", b"last")]),
+        ] {
+            let (stats, seen) = walk_all(&archive, "x", &limits);
+            let stats = stats.unwrap();
+            assert_eq!(paths(&seen), vec!["a.txt", "c.txt"]);
+            assert_eq!(stats.skipped, vec!["big.bin".to_string()]);
+            assert!(!stats.truncated);
+            assert_eq!(stats.expanded, 109);
+        }
+    }
+
+    #[test]
+    fn total_budget_and_entry_count_end_the_walk() {
+        let archive = tar(&[("a", &[1; 40]), ("b", &[2; 40]), ("c", &[3; 40])]);
+        let budget = ArchiveLimits {
+            max_expanded_size: 100,
+            ..ArchiveLimits::default()
+        };
+        let (stats, seen) = walk_all(&archive, "x", &budget);
+        assert_eq!(paths(&seen), vec!["a", "b"]);
+        assert!(stats.unwrap().truncated);
+
+        // Draining an oversized entry is paid for from the same budget
+        let draining = ArchiveLimits {
+            max_entry_size: 30,
+            max_expanded_size: 70,
+            ..ArchiveLimits::default()
+        };
+        let (stats, seen) = walk_all(&archive, "x", &draining);
+        let stats = stats.unwrap();
+        assert!(seen.is_empty());
+        assert_eq!(stats.skipped, vec!["a".to_string()]);
+        assert!(stats.truncated);
+
+        let count = ArchiveLimits {
+            max_entries: 2,
+            ..ArchiveLimits::default()
+        };
+        let (stats, seen) = walk_all(&archive, "x", &count);
+        assert_eq!(seen.len(), 2);
+        assert!(stats.unwrap().truncated);
+    }
+
+    #[test]
+    fn nesting_depth_is_limited() {
+        let innermost = zip(&[("deep.txt", b"x")]);
+        let middle = zip(&[("inner.zip", &innermost)]);
+        let outer = zip(&[("middle.zip", &middle)]);
+        let limits = ArchiveLimits {
+            max_depth: 2,
+            ..ArchiveLimits::default()
+        };
+
+        let (_, seen) = walk_all(&outer, "outer.zip", &limits);
+        assert_eq!(paths(&seen), vec!["middle.zip", "middle.zip!inner.zip"]);
+    }
+
+    #[test]
+    fn damaged_archives() {
+        // A broken inner archive is reported as an entry and the walk goes on
+        let mut broken = zip(&[("x", b"y")]);
+        broken.truncate(12);
+        let outer = tar(&[("broken.zip", &broken), ("after.txt", b"ok")]);
+        let (stats, seen) = walk_all(&outer, "x", &ArchiveLimits::default());
+        assert!(stats.is_ok());
+        assert_eq!(paths(&seen), vec!["broken.zip", "after.txt"]);
+
+        // A broken outer archive is an error
+        let (stats, seen) = walk_all(&broken, "broken.zip", &ArchiveLimits::default());
+        assert!(stats.is_err());
+        assert!(seen.is_empty());
+
+        let mut stream = gzip(&[b'z'; 1000]);
+        stream.truncate(stream.len() / 2);
+        assert!(walk_all(&stream, "x.gz", &ArchiveLimits::default()).0.is_err());
+    }
+
+    #[test]
+    fn walks_mailbox_attachments() {
+        let mbox = b"From a Mon\nMessage-ID: <one@x>\nContent-Type: multipart/mixed; boundary=b\n\n\
+--b\nContent-Disposition: attachment; filename=a.zip\nContent-Transfer-Encoding: base64\n\n";
+        let mut mbox = mbox.to_vec();
+        mbox.extend(base64::engine::general_purpose::STANDARD.encode(zip(&[("x.exe", b"MZ")])).into_bytes());
+        mbox.extend_from_slice(b"\n--b--\n\nFrom b Tue\nContent-Type: application/pdf\n\n%PDF\n");
+
+        let (stats, seen) = walk_all(&mbox, "inbox", &ArchiveLimits::default());
+        assert!(stats.is_ok());
+        // The second message has no Message-ID and no name, so both are numbered
+        assert_eq!(paths(&seen), vec!["one@x!a.zip", "one@x!a.zip!x.exe", "message-2!part-1"]);
+    }
+
+    #[test]
+    fn strips_compression_suffixes() {
+        assert_eq!(decompressed_name("a.tar.gz", ContainerKind::Gzip), "a.tar");
+        assert_eq!(decompressed_name("A.TBZ2", ContainerKind::Bzip2), "A");
+        assert_eq!(decompressed_name("notes.txz", ContainerKind::Xz), "notes");
+        assert_eq!(decompressed_name("blob", ContainerKind::Gzip), "blob");
+    }
+}
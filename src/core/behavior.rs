@@ -0,0 +1,555 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
+
+use super::persistence::in_writable_dir;
+use super::procmem;
+use super::realtime::FileActivity;
+use super::{shannon_entropy, DetectedThreat, DetectionSource, Severity, ThreatCategory, ThreatSignature};
+
+// ==================== DAVRANIŞ İZLEME ====================
+//
+// Ransomware gives itself away by volume: many files rewritten with encrypted content,
+// renamed to a new extension or deleted within seconds. Each kind is counted over a
+// sliding window and an alert is raised once it crosses its threshold.
+
+const ENTROPY_SAMPLE: usize = 64 * 1024;
+// Shorter samples cannot reach the entropy of encrypted data
+const MIN_ENTROPY_SAMPLE: usize = 1024;
+const ACTIVITY_QUEUE: usize = 4096;
+// Affected files listed in an alert
+const ALERT_PATHS: usize = 10;
+
+// Formats that are compressed by design, as (offset, magic)
+const COMPRESSED_MAGIC: &[(usize, &[u8])] = &[
+    (0, b"PK\x03\x04"),
+    (0, b"\x1f\x8b"),
+    (0, b"BZh"),
+    (0, b"\xfd7zXZ\x00"),
+    (0, b"7z\xbc\xaf\x27\x1c"),
+    (0, b"Rar!"),
+    (0, b"\x28\xb5\x2f\xfd"),
+    (0, b"\x89PNG"),
+    (0, b"\xff\xd8\xff"),
+    (0, b"GIF8"),
+    (0, b"RIFF"),
+    (0, b"OggS"),
+    (0, b"ID3"),
+    (0, b"fLaC"),
+    (0, b"\x1a\x45\xdf\xa3"),
+    (0, b"%PDF"),
+    (4, b"ftyp"),
+];
+// Browsers, editors and backup tools rename their scratch files into place
+const TEMPORARY_EXTENSIONS: &[&str] = &["part", "crdownload", "download", "partial", "tmp", "temp", "swp", "bak"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BehaviorConfig {
+    pub enabled: bool,
+    pub window_secs: u64,
+    // Events of one kind within the window that raise an alert
+    pub high_entropy_writes: usize,
+    pub extension_changes: usize,
+    pub deletions: usize,
+    // Bits per byte; encrypted data sits just under 8.0
+    pub entropy_threshold: f64,
+    // Stop the process held responsible instead of only reporting it
+    pub suspend_processes: bool,
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        BehaviorConfig {
+            enabled: true,
+            window_secs: 10,
+            high_entropy_writes: 20,
+            extension_changes: 20,
+            deletions: 100,
+            entropy_threshold: 7.5,
+            suspend_processes: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BurstKind {
+    HighEntropyWrites,
+    ExtensionChanges,
+    Deletions,
+}
+
+impl BurstKind {
+    fn describe(&self) -> &'static str {
+        match self {
+            BurstKind::HighEntropyWrites => "files rewritten with encrypted-looking content",
+            BurstKind::ExtensionChanges => "files renamed to a new extension",
+            BurstKind::Deletions => "files deleted",
+        }
+    }
+}
+
+impl fmt::Display for BurstKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BurstKind::HighEntropyWrites => write!(f, "HighEntropyWrites"),
+            BurstKind::ExtensionChanges => write!(f, "ExtensionChanges"),
+            BurstKind::Deletions => write!(f, "Deletions"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspectProcess {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<PathBuf>,
+    // Files it held open for writing in the affected directories when the alert was raised
+    pub open_files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BehaviorAlert {
+    pub kind: BurstKind,
+    pub count: usize,
+    pub window_secs: u64,
+    pub paths: Vec<PathBuf>,
+    // None when no process was writing in the affected directories
+    pub process: Option<SuspectProcess>,
+    pub suspended: bool,
+    pub timestamp: DateTime<Local>,
+}
+
+impl BehaviorAlert {
+    pub fn to_signature(&self) -> ThreatSignature {
+        ThreatSignature {
+            name: format!("Behavior.Ransomware.{}", self.kind),
+            pattern: Vec::new(),
+            category: ThreatCategory::Ransomware,
+            severity: Severity::Critical,
+        }
+    }
+
+    // Reported against the suspect's executable when there is one, else the first affected file
+    pub fn to_threat(&self) -> DetectedThreat {
+        let file_path = match &self.process {
+            Some(SuspectProcess { exe: Some(exe), .. }) => exe.clone(),
+            _ => self.paths.first().cloned().unwrap_or_default(),
+        };
+        DetectedThreat {
+            signature: self.to_signature(),
+            file_path,
+            nested_path: None,
+            process: None,
+            offset: 0,
+            context: self.describe(),
+            file_hash: None,
+            source: DetectionSource::Behavior,
+            confidence: 1.0,
+            timestamp: self.timestamp,
+        }
+    }
+
+    // The suspect is whoever still writes there once the alert fires, so it is only offered as a guess
+    pub fn describe(&self) -> String {
+        let culprit = match &self.process {
+            Some(process) => format!(
+                "best guess at the cause: {} (pid {}), which was writing in the same directories",
+                process.name, process.pid
+            ),
+            None => "the process responsible could not be identified".to_string(),
+        };
+        let mut text = format!("{} {} within {}s; {}", self.count, self.kind.describe(), self.window_secs, culprit);
+        if self.suspended {
+            text.push_str("; the process was suspended");
+        }
+        text
+    }
+}
+
+struct Burst {
+    kind: BurstKind,
+    threshold: usize,
+    events: VecDeque<(Instant, PathBuf)>,
+}
+
+pub struct BehaviorMonitor {
+    config: BehaviorConfig,
+    window: Duration,
+    bursts: Vec<Burst>,
+}
+
+impl BehaviorMonitor {
+    pub fn new(config: BehaviorConfig) -> Self {
+        let burst = |kind, threshold: usize| Burst {
+            kind,
+            threshold: threshold.max(1),
+            events: VecDeque::new(),
+        };
+        BehaviorMonitor {
+            window: Duration::from_secs(config.window_secs),
+            bursts: vec![
+                burst(BurstKind::HighEntropyWrites, config.high_entropy_writes),
+                burst(BurstKind::ExtensionChanges, config.extension_changes),
+                burst(BurstKind::Deletions, config.deletions),
+            ],
+            config,
+        }
+    }
+
+    pub fn observe(&mut self, activity: &FileActivity) -> Option<BehaviorAlert> {
+        match activity {
+            FileActivity::Written(path) => {
+                if !ignored(path) && encrypted_looking(path, self.config.entropy_threshold) {
+                    self.record(BurstKind::HighEntropyWrites, path);
+                }
+            }
+            FileActivity::Renamed { from, to } => {
+                if !ignored(to) {
+                    if extension_changed(from, to) {
+                        self.record(BurstKind::ExtensionChanges, to);
+                    }
+                    // Encrypted copies are often written under a scratch name and renamed into place;
+                    // a file already counted under its old name is not counted twice
+                    if !self.renamed(BurstKind::HighEntropyWrites, from, to)
+                        && encrypted_looking(to, self.config.entropy_threshold)
+                    {
+                        self.record(BurstKind::HighEntropyWrites, to);
+                    }
+                }
+            }
+            FileActivity::Removed(path) => {
+                if !ignored(path) {
+                    self.record(BurstKind::Deletions, path);
+                }
+            }
+        }
+        self.check()
+    }
+
+    fn record(&mut self, kind: BurstKind, path: &Path) {
+        if let Some(burst) = self.bursts.iter_mut().find(|burst| burst.kind == kind) {
+            burst.events.push_back((Instant::now(), path.to_path_buf()));
+        }
+    }
+
+    fn renamed(&mut self, kind: BurstKind, from: &Path, to: &Path) -> bool {
+        let Some(burst) = self.bursts.iter_mut().find(|burst| burst.kind == kind) else {
+            return false;
+        };
+        match burst.events.iter_mut().find(|(_, path)| path == from) {
+            Some((_, path)) => {
+                *path = to.to_path_buf();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The window restarts after an alert, so a process that keeps going is reported again
+    fn check(&mut self) -> Option<BehaviorAlert> {
+        let now = Instant::now();
+        for burst in &mut self.bursts {
+            while burst.events.front().is_some_and(|(at, _)| now.duration_since(*at) > self.window) {
+                burst.events.pop_front();
+            }
+        }
+
+        let burst = self.bursts.iter_mut().find(|burst| burst.events.len() >= burst.threshold)?;
+        let kind = burst.kind;
+        let paths: Vec<PathBuf> = burst.events.drain(..).map(|(_, path)| path).collect();
+        Some(self.alert(kind, paths))
+    }
+
+    fn alert(&self, kind: BurstKind, paths: Vec<PathBuf>) -> BehaviorAlert {
+        let process = likely_culprit(&paths);
+
+        let suspended = match &process {
+            // Only a process caught writing is ever stopped
+            Some(process) if self.config.suspend_processes && process.open_files > 0 => match procmem::suspend(process.pid) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    false
+                }
+            },
+            _ => false,
+        };
+
+        BehaviorAlert {
+            kind,
+            count: paths.len(),
+            window_secs: self.config.window_secs,
+            paths: paths.into_iter().take(ALERT_PATHS).collect(),
+            process,
+            suspended,
+            timestamp: Local::now(),
+        }
+    }
+}
+
+// Runs a monitor on its own thread, fed through the returned sender; it stops once every
+// clone of the sender is gone
+pub fn spawn<F>(config: BehaviorConfig, alert: F) -> Result<Sender<FileActivity>>
+where
+    F: Fn(BehaviorAlert) + Send + 'static,
+{
+    let (sender, receiver) = crossbeam_channel::bounded::<FileActivity>(ACTIVITY_QUEUE);
+    thread::Builder::new().name("behavior-monitor".to_string()).spawn(move || {
+        let mut monitor = BehaviorMonitor::new(config);
+        for activity in receiver {
+            if let Some(found) = monitor.observe(&activity) {
+                alert(found);
+            }
+        }
+    })?;
+    Ok(sender)
+}
+
+// Whoever is writing in the directories of the touched files is the best guess we get without fanotify
+pub fn likely_culprit(paths: &[PathBuf]) -> Option<SuspectProcess> {
+    let dirs: Vec<PathBuf> = paths
+        .iter()
+        .filter_map(|path| path.parent().map(Path::to_path_buf))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    procmem::open_file_writers(&dirs)
+        .into_iter()
+        .next()
+        .map(|(process, open_files)| SuspectProcess {
+            pid: process.pid,
+            name: process.name,
+            exe: process.exe,
+            open_files,
+        })
+}
+
+// Scratch space churns constantly and holds nothing worth ransoming
+fn ignored(path: &Path) -> bool {
+    in_writable_dir(&path.to_string_lossy())
+}
+
+fn encrypted_looking(path: &Path, threshold: f64) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut sample = Vec::with_capacity(ENTROPY_SAMPLE);
+    if file.take(ENTROPY_SAMPLE as u64).read_to_end(&mut sample).is_err() || sample.len() < MIN_ENTROPY_SAMPLE {
+        return false;
+    }
+    let compressed = COMPRESSED_MAGIC
+        .iter()
+        .any(|(offset, magic)| sample.get(*offset..).is_some_and(|rest| rest.starts_with(magic)));
+    !compressed && shannon_entropy(&sample) >= threshold
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension().map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+// "report.docx" -> "report.docx.locked"; scratch files renamed into place do not count
+fn extension_changed(from: &Path, to: &Path) -> bool {
+    match (extension(from), extension(to)) {
+        (Some(old), Some(new)) => {
+            old != new && !TEMPORARY_EXTENSIONS.contains(&old.as_str()) && !TEMPORARY_EXTENSIONS.contains(&new.as_str())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn config() -> BehaviorConfig {
+        BehaviorConfig {
+            high_entropy_writes: 3,
+            extension_changes: 3,
+            deletions: 3,
+            suspend_processes: true,
+            ..BehaviorConfig::default()
+        }
+    }
+
+    // Paths outside the scratch directories; nothing needs to exist for renames and deletions
+    fn document(name: &str) -> PathBuf {
+        PathBuf::from("/nonexistent/Documents").join(name)
+    }
+
+    fn random(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn raises_an_alert_on_a_rename_burst() {
+        let mut monitor = BehaviorMonitor::new(config());
+        for name in ["a", "b"] {
+            let renamed = FileActivity::Renamed {
+                from: document(&format!("{}.docx", name)),
+                to: document(&format!("{}.docx.locked", name)),
+            };
+            assert!(monitor.observe(&renamed).is_none());
+        }
+        // Scratch files renamed into place and files without extensions do not count
+        let download = FileActivity::Renamed {
+            from: document("c.pdf.part"),
+            to: document("c.pdf"),
+        };
+        assert!(monitor.observe(&download).is_none());
+        assert!(monitor.observe(&FileActivity::Renamed { from: document("d"), to: document("d.x") }).is_none());
+
+        let alert = monitor
+            .observe(&FileActivity::Renamed {
+                from: document("e.xlsx"),
+                to: document("e.xlsx.locked"),
+            })
+            .unwrap();
+        assert_eq!(alert.kind, BurstKind::ExtensionChanges);
+        assert_eq!(alert.count, 3);
+        assert_eq!(alert.paths[0], document("a.docx.locked"));
+        // Nobody is writing there, so nothing is blamed or suspended
+        assert!(alert.process.is_none());
+        assert!(!alert.suspended);
+
+        // The window starts over after an alert
+        assert!(monitor.observe(&FileActivity::Removed(document("f"))).is_none());
+    }
+
+    #[test]
+    fn counts_deletions_and_ignores_scratch_space() {
+        let mut monitor = BehaviorMonitor::new(config());
+        for _ in 0..5 {
+            assert!(monitor.observe(&FileActivity::Removed(PathBuf::from("/tmp/x"))).is_none());
+            assert!(monitor.observe(&FileActivity::Written(PathBuf::from("/dev/shm/x"))).is_none());
+        }
+        monitor.observe(&FileActivity::Removed(document("a")));
+        monitor.observe(&FileActivity::Removed(document("b")));
+        let alert = monitor.observe(&FileActivity::Removed(document("c"))).unwrap();
+        assert_eq!(alert.kind, BurstKind::Deletions);
+        assert_eq!(
+            alert.describe(),
+            "3 files deleted within 10s; the process responsible could not be identified"
+        );
+    }
+
+    #[test]
+    fn forgets_events_outside_the_window() {
+        let mut monitor = BehaviorMonitor::new(BehaviorConfig {
+            window_secs: 0,
+            ..config()
+        });
+        for name in ["a", "b", "c"] {
+            monitor.observe(&FileActivity::Removed(document(name)));
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(monitor.observe(&FileActivity::Removed(document("d"))).is_none());
+    }
+
+    #[test]
+    fn does_not_count_a_renamed_write_twice() {
+        let mut monitor = BehaviorMonitor::new(config());
+        monitor.record(BurstKind::HighEntropyWrites, &document("a.tmp"));
+        assert!(monitor.renamed(BurstKind::HighEntropyWrites, &document("a.tmp"), &document("a.docx")));
+        assert!(!monitor.renamed(BurstKind::HighEntropyWrites, &document("b.tmp"), &document("b.docx")));
+        let burst = &monitor.bursts[0];
+        assert_eq!(burst.events.len(), 1);
+        assert_eq!(burst.events[0].1, document("a.docx"));
+    }
+
+    #[test]
+    fn recognizes_encrypted_content() {
+        let dir = TempDir::new().unwrap();
+        let write = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path
+        };
+
+        assert!(encrypted_looking(&write("random", &random(8192)), 7.5));
+        // Compressed formats are high entropy by design
+        let mut zip = b"PK\x03\x04".to_vec();
+        zip.extend(random(8192));
+        assert!(!encrypted_looking(&write("zip", &zip), 7.5));
+        let mut mp4 = b"\0\0\0\x20ftypisom".to_vec();
+        mp4.extend(random(8192));
+        assert!(!encrypted_looking(&write("mp4", &mp4), 7.5));
+        assert!(!encrypted_looking(&write("text", &b"plain text ".repeat(1000)), 7.5));
+        // Too short to tell
+        assert!(!encrypted_looking(&write("short", &random(512)), 7.5));
+        assert!(!encrypted_looking(&dir.path().join("missing"), 7.5));
+    }
+
+    #[test]
+    fn compares_extensions() {
+        assert!(extension_changed(Path::new("a.docx"), Path::new("a.docx.enc")));
+        assert!(extension_changed(Path::new("a.JPG"), Path::new("a.crypt")));
+        assert!(!extension_changed(Path::new("a.JPG"), Path::new("b.jpg")));
+        assert!(!extension_changed(Path::new("a.docx.tmp"), Path::new("a.docx")));
+        assert!(!extension_changed(Path::new("a.docx"), Path::new("a.docx.bak")));
+        assert!(!extension_changed(Path::new("Makefile"), Path::new("Makefile.old")));
+    }
+
+    #[test]
+    fn reports_alerts_as_ransomware_threats() {
+        let alert = BehaviorAlert {
+            kind: BurstKind::HighEntropyWrites,
+            count: 25,
+            window_secs: 10,
+            paths: vec![document("a.docx")],
+            process: Some(SuspectProcess {
+                pid: 4242,
+                name: "locker".to_string(),
+                exe: Some(PathBuf::from("/home/u/.cache/locker")),
+                open_files: 3,
+            }),
+            suspended: true,
+            timestamp: Local::now(),
+        };
+        let threat = alert.to_threat();
+        assert_eq!(threat.signature.name, "Behavior.Ransomware.HighEntropyWrites");
+        assert_eq!(threat.signature.category, ThreatCategory::Ransomware);
+        assert_eq!(threat.signature.severity, Severity::Critical);
+        assert_eq!(threat.source, DetectionSource::Behavior);
+        assert_eq!(threat.file_path, PathBuf::from("/home/u/.cache/locker"));
+        assert!(threat.context.contains("best guess at the cause: locker (pid 4242)"));
+        assert!(threat.context.ends_with("the process was suspended"));
+
+        let unknown = BehaviorAlert { process: None, ..alert };
+        assert_eq!(unknown.to_threat().file_path, document("a.docx"));
+    }
+
+    #[test]
+    fn spawned_monitor_reports_alerts() {
+        let (alerts_tx, alerts_rx) = crossbeam_channel::unbounded();
+        let sender = spawn(config(), move |alert| {
+            alerts_tx.send(alert).unwrap();
+        })
+        .unwrap();
+        for name in ["a", "b", "c"] {
+            sender.send(FileActivity::Removed(document(name))).unwrap();
+        }
+        let alert = alerts_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(alert.kind, BurstKind::Deletions);
+        drop(sender);
+        assert!(alerts_rx.recv_timeout(Duration::from_secs(5)).is_err());
+    }
+}
@@ -0,0 +1,457 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use crossbeam_channel::Sender;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::behavior::{likely_culprit, SuspectProcess};
+use super::realtime::FileActivity;
+use super::Engine;
+
+// ==================== YEM DOSYALAR ====================
+//
+// Hidden decoy documents nobody has a reason to open. Ransomware working through a
+// directory touches them like any other file, often before it reaches anything of value.
+
+const ACTIVITY_QUEUE: usize = 1024;
+const DECOY_NAMES: &[&str] = &[
+    ".Tax_Return_2023.csv",
+    ".Bank_Accounts.csv",
+    ".Invoices_Archive.csv",
+    ".Passwords_Backup.txt",
+    ".Family_Budget.csv",
+];
+const DECOY_ROWS: usize = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryConfig {
+    pub enabled: bool,
+    // Each directory gets one decoy
+    pub directories: Vec<PathBuf>,
+    // Hidden file names to choose from, first unused one wins
+    pub names: Vec<String>,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        let mut directories = Vec::new();
+        if let Some(home) = dirs::home_dir() {
+            directories.push(dirs::document_dir().unwrap_or_else(|| home.join("Documents")));
+            directories.push(dirs::desktop_dir().unwrap_or_else(|| home.join("Desktop")));
+            directories.push(dirs::picture_dir().unwrap_or_else(|| home.join("Pictures")));
+        }
+        directories.dedup();
+
+        // Planting files in someone's folders is their call to make
+        CanaryConfig {
+            enabled: false,
+            directories,
+            names: DECOY_NAMES.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Canary {
+    pub id: String,
+    pub path: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+    pub created: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CanaryState {
+    Intact,
+    Modified,
+    Missing,
+}
+
+impl Canary {
+    pub fn state(&self) -> CanaryState {
+        match Engine::calculate_file_hash(&self.path) {
+            Ok(sha256) if sha256 == self.sha256 => CanaryState::Intact,
+            Ok(_) => CanaryState::Modified,
+            Err(_) => CanaryState::Missing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CanaryAction {
+    Modified,
+    Renamed(PathBuf),
+    Deleted,
+}
+
+impl fmt::Display for CanaryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanaryAction::Modified => write!(f, "modified"),
+            CanaryAction::Renamed(to) => write!(f, "renamed to {}", to.display()),
+            CanaryAction::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryAlert {
+    pub canary: Canary,
+    pub action: CanaryAction,
+    pub process: Option<SuspectProcess>,
+    pub timestamp: DateTime<Local>,
+}
+
+impl CanaryAlert {
+    pub fn describe(&self) -> String {
+        let culprit = match &self.process {
+            Some(process) => format!(
+                "best guess at the cause: {} (pid {}), which was writing in the same directory",
+                process.name, process.pid
+            ),
+            None => "the process responsible could not be identified".to_string(),
+        };
+        format!("Decoy file {} was {}; {}", self.canary.path.display(), self.action, culprit)
+    }
+}
+
+// The list is read on first use; without a data directory nothing can be planted, since
+// decoys we cannot remember could never be cleaned up
+pub struct CanaryStore {
+    path: Option<PathBuf>,
+    canaries: Option<Vec<Canary>>,
+}
+
+impl CanaryStore {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        CanaryStore { path, canaries: None }
+    }
+
+    fn loaded(&mut self) -> &mut Vec<Canary> {
+        let path = &self.path;
+        self.canaries.get_or_insert_with(|| match path.as_ref().map(fs::read) {
+            Some(Ok(bytes)) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("Ignoring damaged canary list {:?}: {}", path, e);
+                Vec::new()
+            }),
+            _ => Vec::new(),
+        })
+    }
+
+    pub fn canaries(&mut self) -> &[Canary] {
+        self.loaded()
+    }
+
+    pub fn find(&mut self, path: &Path) -> Option<&Canary> {
+        self.loaded().iter().find(|canary| canary.path == path)
+    }
+
+    pub fn in_dir(&mut self, dir: &Path) -> Option<&Canary> {
+        self.loaded().iter().find(|canary| canary.path.parent() == Some(dir))
+    }
+
+    // A directory keeps the decoy it already has; the file is only recorded once fully written
+    pub fn plant(&mut self, dir: &Path, names: &[String]) -> Result<Canary> {
+        if self.path.is_none() {
+            return Err(anyhow!("Could not find data directory"));
+        }
+        if let Some(existing) = self.in_dir(dir) {
+            return Ok(existing.clone());
+        }
+        if !dir.is_dir() {
+            return Err(anyhow!("{:?} is not a directory", dir));
+        }
+        // Rotate through the names so decoys in different directories do not share one
+        let path = names
+            .iter()
+            .cycle()
+            .skip(self.loaded().len() % names.len().max(1))
+            .take(names.len())
+            .map(|name| dir.join(name))
+            .find(|path| !path.exists())
+            .ok_or_else(|| anyhow!("Every decoy name is already taken in {:?}", dir))?;
+
+        let content = decoy_content(&path);
+        let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+        file.write_all(content.as_bytes())?;
+        drop(file);
+
+        let sha256 = Engine::calculate_file_hash(&path)?;
+        let canary = Canary {
+            id: sha256[..16].to_string(),
+            sha256,
+            size: content.len() as u64,
+            path,
+            created: Local::now(),
+        };
+        self.loaded().push(canary.clone());
+        self.save()?;
+        Ok(canary)
+    }
+
+    // Forgets the decoy before deleting it, so the deletion is not reported
+    pub fn remove(&mut self, id: &str) -> Result<Canary> {
+        let canaries = self.loaded();
+        let index = canaries
+            .iter()
+            .position(|canary| canary.id == id)
+            .ok_or_else(|| anyhow!("No canary with id {}", id))?;
+        let canary = canaries.remove(index);
+        self.save()?;
+
+        match fs::remove_file(&canary.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        Ok(canary)
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(self.loaded())?;
+        let path = self.path.as_ref().ok_or_else(|| anyhow!("Could not find data directory"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+// Watches activity on a thread of its own, fed through the returned sender
+pub fn spawn<F>(store: Arc<Mutex<CanaryStore>>, alert: F) -> Result<Sender<FileActivity>>
+where
+    F: Fn(CanaryAlert) + Send + 'static,
+{
+    let (sender, receiver) = crossbeam_channel::bounded::<FileActivity>(ACTIVITY_QUEUE);
+    thread::Builder::new().name("canary-monitor".to_string()).spawn(move || {
+        for activity in receiver {
+            let (path, action) = match activity {
+                FileActivity::Written(path) => (path, CanaryAction::Modified),
+                FileActivity::Renamed { from, to } => (from, CanaryAction::Renamed(to)),
+                FileActivity::Removed(path) => (path, CanaryAction::Deleted),
+            };
+            let canary = match store.lock() {
+                Ok(mut store) => store.find(&path).cloned(),
+                Err(_) => None,
+            };
+            let Some(canary) = canary else {
+                continue;
+            };
+            // Opened for writing but left as it was, which includes our own planting
+            if matches!(action, CanaryAction::Modified) && canary.state() == CanaryState::Intact {
+                continue;
+            }
+
+            alert(CanaryAlert {
+                process: likely_culprit(std::slice::from_ref(&canary.path)),
+                canary,
+                action,
+                timestamp: Local::now(),
+            });
+        }
+    })?;
+    Ok(sender)
+}
+
+// Believable rows for the kind of file the name promises
+fn decoy_content(path: &Path) -> String {
+    let mut rng = rand::thread_rng();
+    let mut content = String::new();
+
+    if path.extension().is_some_and(|ext| ext == "txt") {
+        for site in ["mail", "bank", "shop", "cloud", "work", "router", "wifi", "forum"] {
+            let password: String = (0..14).map(|_| rng.sample(rand::distributions::Alphanumeric) as char).collect();
+            content.push_str(&format!("{}: user{} / {}\n", site, rng.gen_range(10..99), password));
+        }
+        return content;
+    }
+
+    content.push_str("Date,Description,Account,Amount\n");
+    for _ in 0..DECOY_ROWS {
+        content.push_str(&format!(
+            "2023-{:02}-{:02},Payment {},{:08},{}.{:02}\n",
+            rng.gen_range(1..=12),
+            rng.gen_range(1..=28),
+            rng.gen_range(1000..9999),
+            rng.gen_range(10_000_000..99_999_999),
+            rng.gen_range(5..5000),
+            rng.gen_range(0..100)
+        ));
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn names() -> Vec<String> {
+        CanaryConfig::default().names
+    }
+
+    fn store(dir: &TempDir) -> CanaryStore {
+        CanaryStore::new(Some(dir.path().join("data/canaries.json")))
+    }
+
+    #[test]
+    fn is_opt_in() {
+        assert!(!CanaryConfig::default().enabled);
+    }
+
+    #[test]
+    fn plants_one_decoy_per_directory() {
+        let data = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        let mut store = store(&data);
+
+        let canary = store.plant(target.path(), &names()).unwrap();
+        assert_eq!(canary.path.parent(), Some(target.path()));
+        assert!(canary.path.file_name().unwrap().to_string_lossy().starts_with('.'));
+        assert_eq!(fs::metadata(&canary.path).unwrap().len(), canary.size);
+        assert_eq!(canary.state(), CanaryState::Intact);
+        // Planting again keeps the decoy already there
+        assert_eq!(store.plant(target.path(), &names()).unwrap().id, canary.id);
+
+        // The next directory gets the next name
+        let other = TempDir::new().unwrap();
+        let second = store.plant(other.path(), &names()).unwrap();
+        assert_ne!(second.path.file_name(), canary.path.file_name());
+
+        let mut reloaded = CanaryStore::new(Some(data.path().join("data/canaries.json")));
+        assert_eq!(reloaded.canaries().len(), 2);
+        assert_eq!(reloaded.find(&canary.path).unwrap().id, canary.id);
+        assert_eq!(reloaded.in_dir(other.path()).unwrap().id, second.id);
+    }
+
+    #[test]
+    fn skips_names_already_taken() {
+        let data = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        let names = vec![".a.csv".to_string(), ".b.csv".to_string()];
+        fs::write(target.path().join(".a.csv"), b"mine").unwrap();
+
+        let canary = store(&data).plant(target.path(), &names).unwrap();
+        assert_eq!(canary.path, target.path().join(".b.csv"));
+        assert_eq!(fs::read(target.path().join(".a.csv")).unwrap(), b"mine");
+
+        let full = TempDir::new().unwrap();
+        fs::write(full.path().join(".a.csv"), b"").unwrap();
+        fs::write(full.path().join(".b.csv"), b"").unwrap();
+        assert!(store(&data).plant(full.path(), &names).is_err());
+        assert!(store(&data).plant(&full.path().join("missing"), &names).is_err());
+    }
+
+    #[test]
+    fn tells_decoy_state() {
+        let data = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        let canary = store(&data).plant(target.path(), &names()).unwrap();
+
+        fs::write(&canary.path, b"encrypted").unwrap();
+        assert_eq!(canary.state(), CanaryState::Modified);
+        fs::remove_file(&canary.path).unwrap();
+        assert_eq!(canary.state(), CanaryState::Missing);
+    }
+
+    #[test]
+    fn removes_decoys() {
+        let data = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        let mut store = store(&data);
+        let canary = store.plant(target.path(), &names()).unwrap();
+
+        store.remove(&canary.id).unwrap();
+        assert!(!canary.path.exists());
+        assert!(store.canaries().is_empty());
+        assert!(store.remove(&canary.id).is_err());
+
+        // A decoy someone else already deleted is still forgotten
+        let again = store.plant(target.path(), &names()).unwrap();
+        fs::remove_file(&again.path).unwrap();
+        store.remove(&again.id).unwrap();
+    }
+
+    #[test]
+    fn needs_a_data_directory_to_plant() {
+        let target = TempDir::new().unwrap();
+        let mut store = CanaryStore::new(None);
+        assert!(store.canaries().is_empty());
+        assert!(store.plant(target.path(), &names()).is_err());
+        assert_eq!(fs::read_dir(target.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn ignores_a_damaged_list() {
+        let data = TempDir::new().unwrap();
+        let path = data.path().join("canaries.json");
+        fs::write(&path, b"[{\"id\": 5").unwrap();
+        let mut store = CanaryStore::new(Some(path));
+        assert!(store.canaries().is_empty());
+    }
+
+    #[test]
+    fn writes_believable_content() {
+        let csv = decoy_content(Path::new(".Bank_Accounts.csv"));
+        assert!(csv.starts_with("Date,Description,Account,Amount\n"));
+        assert_eq!(csv.lines().count(), DECOY_ROWS + 1);
+        assert!(csv.lines().all(|line| line.split(',').count() == 4));
+        let txt = decoy_content(Path::new(".Passwords_Backup.txt"));
+        assert_eq!(txt.lines().count(), 8);
+        assert!(txt.lines().all(|line| line.contains(" / ")));
+    }
+
+    #[test]
+    fn reports_touched_decoys() {
+        let data = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        let store = Arc::new(Mutex::new(store(&data)));
+        let canary = store.lock().unwrap().plant(target.path(), &names()).unwrap();
+
+        let (alerts_tx, alerts_rx) = crossbeam_channel::unbounded();
+        let sender = spawn(store.clone(), move |alert| {
+            alerts_tx.send(alert).unwrap();
+        })
+        .unwrap();
+        let recv = || alerts_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        // Untouched content and unrelated files are not reported
+        sender.send(FileActivity::Written(canary.path.clone())).unwrap();
+        sender.send(FileActivity::Removed(target.path().join("other"))).unwrap();
+        assert!(alerts_rx.recv_timeout(Duration::from_millis(300)).is_err());
+
+        fs::write(&canary.path, b"encrypted").unwrap();
+        sender.send(FileActivity::Written(canary.path.clone())).unwrap();
+        let alert = recv();
+        assert!(matches!(alert.action, CanaryAction::Modified));
+        assert_eq!(alert.canary.id, canary.id);
+
+        let locked = target.path().join("x.locked");
+        sender
+            .send(FileActivity::Renamed {
+                from: canary.path.clone(),
+                to: locked.clone(),
+            })
+            .unwrap();
+        assert!(matches!(recv().action, CanaryAction::Renamed(to) if to == locked));
+
+        sender.send(FileActivity::Removed(canary.path.clone())).unwrap();
+        let alert = recv();
+        assert!(matches!(alert.action, CanaryAction::Deleted));
+        assert!(alert.describe().ends_with("was deleted; the process responsible could not be identified"));
+
+        drop(sender);
+        assert!(alerts_rx.recv_timeout(Duration::from_secs(5)).is_err());
+    }
+}
@@ -0,0 +1,391 @@
+use std::fs;
+use std::path::Path;
+
+use super::hashes::{HashAlgorithm, HashSignature};
+use super::rules::{BodyOffset, Rule};
+use super::{Severity, ThreatCategory, ThreatSignature, RULE_SAMPLE_LIMIT};
+
+// ==================== CLAMAV İMZA İÇE AKTARIMI ====================
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+// ClamAV target types that can be honoured without a format parser
+const TARGET_ANY: &str = "0";
+const TARGET_ELF: &str = "6";
+
+#[derive(Default)]
+pub struct ClamAvImport {
+    // Plain byte strings with no placement, fed to the multi-pattern matcher
+    pub signatures: Vec<ThreatSignature>,
+    pub rules: Vec<Rule>,
+    pub hashes: Vec<HashSignature>,
+    pub skipped: usize,
+}
+
+pub fn load_clamav_dir(dir: &Path) -> ClamAvImport {
+    let mut import = ClamAvImport::default();
+
+    let mut files: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(_) => return import,
+    };
+    files.sort();
+
+    for path in files {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !matches!(extension.as_str(), "hdb" | "hsb" | "ndb") {
+            continue;
+        }
+
+        let content = match fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                log::warn!("Skipping ClamAV database {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let imported = match extension.as_str() {
+                "hdb" | "hsb" => parse_hash_line(line).map(|hash| import.hashes.push(hash)),
+                _ => parse_ndb_line(line, &mut import),
+            };
+            if imported.is_none() {
+                import.skipped += 1;
+            }
+        }
+    }
+
+    if import.skipped > 0 {
+        log::info!(
+            "Skipped {} unsupported ClamAV signatures in {:?}",
+            import.skipped,
+            dir
+        );
+    }
+
+    import
+}
+
+fn clamav_signature(name: &str) -> ThreatSignature {
+    ThreatSignature {
+        name: name.to_string(),
+        pattern: Vec::new(),
+        category: category_from_name(name),
+        severity: Severity::Critical,
+    }
+}
+
+// ClamAV names look like "Unix.Trojan.Mirai-1234"; the middle part carries the kind
+fn category_from_name(name: &str) -> ThreatCategory {
+    let lower = name.to_lowercase();
+    let kind = lower.split('.').nth(1).unwrap_or("");
+    match kind {
+        "ransomware" => ThreatCategory::Ransomware,
+        "trojan" | "backdoor" | "downloader" | "dropper" | "exploit" | "coinminer" => ThreatCategory::Trojan,
+        "spyware" | "keylogger" => ThreatCategory::Spyware,
+        "adware" => ThreatCategory::Adware,
+        "rootkit" => ThreatCategory::Rootkit,
+        "worm" => ThreatCategory::Worm,
+        "virus" | "malware" => ThreatCategory::Virus,
+        "pua" => ThreatCategory::PUP,
+        _ if lower.contains("test-signature") || lower.contains("eicar") => ThreatCategory::Virus,
+        _ => ThreatCategory::Unknown,
+    }
+}
+
+// .hdb: MD5:Size:Name    .hsb: SHA256:Size:Name (Size may be "*")
+fn parse_hash_line(line: &str) -> Option<HashSignature> {
+    let mut fields = line.split(':');
+    let digest = fields.next()?.trim().to_lowercase();
+    let size = fields.next()?.trim();
+    let name = fields.next()?.trim();
+
+    let algorithm = match digest.len() {
+        32 => HashAlgorithm::Md5,
+        64 => HashAlgorithm::Sha256,
+        // SHA-1 is not indexed
+        _ => return None,
+    };
+    let size = if size == "*" { None } else { Some(size.parse().ok()?) };
+
+    Some(HashSignature {
+        algorithm,
+        digest,
+        size,
+        signature: clamav_signature(name),
+    })
+}
+
+// .ndb: Name:TargetType:Offset:HexSignature[:MinFL[:MaxFL]]
+fn parse_ndb_line(line: &str, import: &mut ClamAvImport) -> Option<()> {
+    let mut fields = line.split(':');
+    let name = fields.next()?.trim();
+    let target = fields.next()?.trim();
+    let offset = parse_offset(fields.next()?.trim())?;
+    let body = fields.next()?.trim();
+
+    let magic = match target {
+        TARGET_ANY => None,
+        TARGET_ELF => Some(ELF_MAGIC),
+        _ => return None,
+    };
+
+    if magic.is_none() && offset == BodyOffset::Any {
+        if let Some(pattern) = decode_literal(body) {
+            let mut signature = clamav_signature(name);
+            signature.pattern = pattern;
+            import.signatures.push(signature);
+            return Some(());
+        }
+    }
+
+    let hex = convert_body(body)?;
+    match Rule::from_hex(clamav_signature(name), &hex, offset, magic) {
+        Ok(rule) => {
+            import.rules.push(rule);
+            Some(())
+        }
+        Err(e) => {
+            log::debug!("Cannot compile ClamAV signature {}: {}", name, e);
+            None
+        }
+    }
+}
+
+// Supports "*", "n" and "n,maxshift" inside the rule sample; "EOF-n" would be resolved
+// against the sample rather than the file, so it is skipped like entry-point and
+// section relative offsets, which need a PE/ELF loader
+fn parse_offset(offset: &str) -> Option<BodyOffset> {
+    if offset == "*" {
+        return Some(BodyOffset::Any);
+    }
+
+    let (base, shift) = match offset.split_once(',') {
+        Some((base, shift)) => (base, Some(shift.parse::<u64>().ok()?)),
+        None => (offset, None),
+    };
+
+    let n: u64 = base.parse().ok()?;
+    if n.checked_add(shift.unwrap_or(0))? >= RULE_SAMPLE_LIMIT as u64 {
+        return None;
+    }
+    Some(match shift {
+        Some(shift) => BodyOffset::Within(n, shift),
+        None => BodyOffset::At(n),
+    })
+}
+
+fn decode_literal(body: &str) -> Option<Vec<u8>> {
+    if body.len() % 2 == 1 || !body.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..body.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&body[i..i + 2], 16).ok())
+        .collect()
+}
+
+// Rewrites ClamAV body syntax into the rule engine's hex string syntax
+fn convert_body(body: &str) -> Option<String> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' => {
+                out.push_str(" [-] ");
+                i += 1;
+            }
+            '{' | '[' => {
+                let close = if chars[i] == '{' { '}' } else { ']' };
+                let end = i + chars[i..].iter().position(|&c| c == close)?;
+                let range: String = chars[i + 1..end].iter().collect();
+                out.push_str(&format!(" [{}] ", convert_range(&range)?));
+                i = end + 1;
+            }
+            '(' => {
+                // "(B)" / "(L)" style anchors have no equivalent
+                if chars.get(i + 2) == Some(&')') && chars.get(i + 1).is_some_and(|c| c.is_ascii_uppercase()) {
+                    return None;
+                }
+                out.push_str(" ( ");
+                i += 1;
+            }
+            '|' | ')' => {
+                out.push_str(&format!(" {} ", chars[i]));
+                i += 1;
+            }
+            c if c.is_ascii_hexdigit() || c == '?' => {
+                let next = *chars.get(i + 1)?;
+                if !(next.is_ascii_hexdigit() || next == '?') {
+                    return None;
+                }
+                out.push(c);
+                out.push(next);
+                out.push(' ');
+                i += 2;
+            }
+            // Negated alternatives ("!(...)") and anything else are unsupported
+            _ => return None,
+        }
+    }
+
+    Some(out)
+}
+
+fn convert_range(range: &str) -> Option<String> {
+    let valid = |s: &str| s.is_empty() || s.parse::<u64>().is_ok();
+    match range.split_once('-') {
+        Some((from, to)) if valid(from) && valid(to) => {
+            let from = if from.is_empty() { "0" } else { from };
+            Some(format!("{}-{}", from, to))
+        }
+        None if range.parse::<u64>().is_ok() => Some(range.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::matcher::SignatureDatabase;
+
+    fn import(ndb: &str) -> ClamAvImport {
+        let mut import = ClamAvImport::default();
+        for line in ndb.lines() {
+            if parse_ndb_line(line, &mut import).is_none() {
+                import.skipped += 1;
+            }
+        }
+        import
+    }
+
+    #[test]
+    fn parses_hash_databases() {
+        let md5 = parse_hash_line("44d88612fea8a8f36de82e1278abb02f:68:Eicar-Test-Signature").unwrap();
+        assert_eq!(md5.algorithm, HashAlgorithm::Md5);
+        assert_eq!(md5.size, Some(68));
+        assert_eq!(md5.signature.category, ThreatCategory::Virus);
+
+        let sha = parse_hash_line(&format!("{}:*:Unix.Trojan.Mirai-1", "AB".repeat(32))).unwrap();
+        assert_eq!(sha.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(sha.digest, "ab".repeat(32));
+        assert_eq!(sha.size, None);
+        assert_eq!(sha.signature.category, ThreatCategory::Trojan);
+    }
+
+    #[test]
+    fn rejects_malformed_hash_lines() {
+        assert!(parse_hash_line("44d88612fea8a8f36de82e1278abb02f:68").is_none());
+        assert!(parse_hash_line("44d88612fea8a8f36de82e1278abb02f:big:Name").is_none());
+        // SHA-1 digests are not indexed
+        assert!(parse_hash_line(&format!("{}:1:Name", "a".repeat(40))).is_none());
+        assert!(parse_hash_line("").is_none());
+    }
+
+    #[test]
+    fn parses_offsets() {
+        assert_eq!(parse_offset("*"), Some(BodyOffset::Any));
+        assert_eq!(parse_offset("12"), Some(BodyOffset::At(12)));
+        assert_eq!(parse_offset("12,4"), Some(BodyOffset::Within(12, 4)));
+        assert_eq!(parse_offset("EOF-8"), None);
+        assert_eq!(parse_offset("EOF-8,2"), None);
+        assert_eq!(parse_offset("8388607"), Some(BodyOffset::At(8388607)));
+        assert_eq!(parse_offset("8388608"), None);
+        assert_eq!(parse_offset("8388600,8"), None);
+        assert_eq!(parse_offset("EP+0"), None);
+        assert_eq!(parse_offset("SE1"), None);
+        assert_eq!(parse_offset("1,x"), None);
+    }
+
+    #[test]
+    fn literal_bodies_go_to_the_pattern_matcher() {
+        let import = import("Unix.Trojan.Lit-1:0:*:414243444546");
+
+        assert_eq!(import.signatures.len(), 1);
+        assert_eq!(import.signatures[0].pattern, b"ABCDEF");
+        assert!(import.rules.is_empty());
+    }
+
+    #[test]
+    fn wildcard_bodies_become_rules() {
+        let import = import(
+            "Unix.Trojan.Wild-1:0:*:4142??4344{2-3}454647*48\n\
+             Unix.Trojan.Elf-1:6:0:7f454c46\n\
+             Unix.Trojan.Alt-1:0:4,8:(4142|4344)4546",
+        );
+        assert_eq!(import.rules.len(), 3);
+        assert_eq!(import.skipped, 0);
+
+        let wild = &import.rules[0];
+        assert_eq!(wild.required_literal(), Some(&b"EFG"[..]));
+        assert!(wild.evaluate(b"..ABxCDyzEFGtailH", 17, None).is_some());
+        assert!(wild.evaluate(b"..ABxCDEFGtailH", 15, None).is_none());
+
+        let elf = &import.rules[1];
+        assert!(elf.evaluate(b"\x7fELF", 4, None).is_some());
+
+        let alt = &import.rules[2];
+        assert!(alt.evaluate(b"xxxxxxCDEF", 10, None).is_some());
+        assert!(alt.evaluate(b"xxxxxxxxxxxxxxCDEF", 18, None).is_none());
+    }
+
+    #[test]
+    fn skips_unsupported_and_malformed_bodies() {
+        let import = import(
+            "Win.Trojan.Pe-1:1:*:4142\n\
+             Unix.Trojan.Ep-1:0:EP+0:4142\n\
+             Unix.Trojan.Neg-1:0:*:41!(42)\n\
+             Unix.Trojan.Anchor-1:0:*:41(B)42\n\
+             Unix.Trojan.Odd-1:0:*:414\n\
+             Unix.Trojan.Range-1:0:*:41{x}42\n\
+             Unix.Trojan.Shift-1:0:18446744073709551615,1:41??42\n\
+             Truncated:0",
+        );
+
+        assert!(import.signatures.is_empty());
+        assert!(import.rules.is_empty());
+        assert_eq!(import.skipped, 8);
+    }
+
+    #[test]
+    fn prefilter_skips_rules_whose_literal_is_absent() {
+        let import = import(
+            "Unix.Trojan.A-1:0:*:616263??646566\n\
+             Unix.Trojan.B-1:0:*:78797a??7a7978\n\
+             Unix.Trojan.Short-1:0:*:61??62",
+        );
+        let mut database = SignatureDatabase::new(Vec::new()).unwrap();
+        database.add_rules(import.rules);
+
+        let names = |data: &[u8]| -> Vec<String> {
+            database.candidate_rules(data).iter().map(|rule| rule.name().to_string()).collect()
+        };
+        // Rules without a usable literal are always evaluated
+        assert_eq!(names(b"nothing"), vec!["Unix.Trojan.Short-1"]);
+        assert_eq!(names(b"..def.."), vec!["Unix.Trojan.A-1", "Unix.Trojan.Short-1"]);
+    }
+
+    #[test]
+    fn loads_only_clamav_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("main.hdb"), "44d88612fea8a8f36de82e1278abb02f:68:Eicar\n# note\nbad\n").unwrap();
+        fs::write(dir.path().join("main.ndb"), "Unix.Trojan.Lit-1:0:*:41424344\n").unwrap();
+        fs::write(dir.path().join("main.cvd"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let import = load_clamav_dir(dir.path());
+        assert_eq!(import.hashes.len(), 1);
+        assert_eq!(import.signatures.len(), 1);
+        assert_eq!(import.skipped, 1);
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use flate2::read::ZlibDecoder;
+use memchr::memmem;
+
+use super::archives::{container_kind, ContainerKind};
+use super::{Severity, ThreatCategory, ThreatSignature};
+
+// ==================== BELGE ETKİN İÇERİK ANALİZİ ====================
+
+const OLE_MAGIC: &[u8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1";
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+// Every VBA project has a "_VBA_PROJECT" stream; OLE stores stream names as UTF-16LE
+const VBA_PROJECT_STREAM: &str = "_VBA_PROJECT";
+const VBA_PROJECT_PART: &str = "vbaproject.bin";
+
+const MAX_VBA_PROJECT_SIZE: u64 = 32 * 1024 * 1024;
+const MAX_PDF_STREAMS: usize = 256;
+const MAX_PDF_STREAM_SIZE: u64 = 4 * 1024 * 1024;
+
+const AUTO_EXEC_MACROS: &[&str] = &[
+    "AutoOpen",
+    "Auto_Open",
+    "AutoExec",
+    "AutoClose",
+    "Document_Open",
+    "Document_Close",
+    "Workbook_Open",
+    "Workbook_BeforeClose",
+];
+
+const SUSPICIOUS_MACRO_CALLS: &[&str] = &[
+    "Shell",
+    "WScript",
+    "CreateObject",
+    "powershell",
+    "URLDownloadToFile",
+    "XMLHTTP",
+    "ADODB.Stream",
+    "CallByName",
+];
+
+#[derive(Debug, Clone)]
+pub struct DocumentFinding {
+    pub name: String,
+    pub severity: Severity,
+    pub confidence: f32,
+    pub offset: u64,
+    pub description: String,
+}
+
+impl DocumentFinding {
+    pub fn to_signature(&self) -> ThreatSignature {
+        ThreatSignature {
+            name: self.name.clone(),
+            pattern: Vec::new(),
+            category: ThreatCategory::ActiveContent,
+            severity: self.severity,
+        }
+    }
+}
+
+// Cheap check on the leading bytes, so the whole document is only loaded when it is one
+pub fn is_document(head: &[u8]) -> bool {
+    head.starts_with(OLE_MAGIC) || is_pdf(head) || container_kind(head) == Some(ContainerKind::Zip)
+}
+
+pub fn analyze(name: &str, data: &[u8]) -> Vec<DocumentFinding> {
+    if data.starts_with(OLE_MAGIC) {
+        // The project part of an OOXML package is already reported through its package
+        if name.to_lowercase().ends_with(VBA_PROJECT_PART) {
+            return Vec::new();
+        }
+        return inspect_ole(data).into_iter().collect();
+    }
+    if is_pdf(data) {
+        return inspect_pdf(data);
+    }
+    if container_kind(data) == Some(ContainerKind::Zip) {
+        return inspect_ooxml(data).into_iter().collect();
+    }
+    Vec::new()
+}
+
+fn is_pdf(head: &[u8]) -> bool {
+    // Readers accept the header anywhere in the first kilobyte
+    memmem::find(&head[..head.len().min(1024)], PDF_MAGIC).is_some()
+}
+
+// Legacy .doc/.xls/.ppt compound files
+fn inspect_ole(data: &[u8]) -> Option<DocumentFinding> {
+    let stream_name: Vec<u8> = VBA_PROJECT_STREAM.bytes().flat_map(|b| [b, 0]).collect();
+    let offset = memmem::find(data, &stream_name)?;
+    Some(macro_finding("Office document", data, offset as u64))
+}
+
+// OOXML packages (.docm, .xlsm, ...) keep their macros in a vbaProject.bin part
+fn inspect_ooxml(data: &[u8]) -> Option<DocumentFinding> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).ok()?;
+    archive.by_name("[Content_Types].xml").ok()?;
+
+    let part = archive
+        .file_names()
+        .find(|name| name.to_lowercase().ends_with(VBA_PROJECT_PART))?
+        .to_string();
+    let mut project = Vec::new();
+    archive
+        .by_name(&part)
+        .ok()?
+        .take(MAX_VBA_PROJECT_SIZE)
+        .read_to_end(&mut project)
+        .ok()?;
+
+    let mut finding = macro_finding("Office Open XML document", &project, 0);
+    finding.description = format!("{} in {}", finding.description, part);
+    Some(finding)
+}
+
+// Compressed VBA source still leaves most identifiers readable in the project streams
+fn macro_finding(kind: &str, project: &[u8], offset: u64) -> DocumentFinding {
+    let present = |words: &[&'static str]| -> Vec<&'static str> {
+        words
+            .iter()
+            .copied()
+            .filter(|word| memmem::find(project, word.as_bytes()).is_some())
+            .collect()
+    };
+    let auto_exec = present(AUTO_EXEC_MACROS);
+    let calls = present(SUSPICIOUS_MACRO_CALLS);
+
+    if auto_exec.is_empty() {
+        return DocumentFinding {
+            name: "Document.Office.Macro".to_string(),
+            severity: Severity::Info,
+            confidence: 0.5,
+            offset,
+            description: format!("{} contains VBA macros", kind),
+        };
+    }
+
+    let mut description = format!("{} runs macros when opened ({})", kind, auto_exec.join(", "));
+    if !calls.is_empty() {
+        description = format!("{} that call {}", description, calls.join(", "));
+    }
+    DocumentFinding {
+        name: "Document.Office.AutoExecMacro".to_string(),
+        severity: Severity::Warning,
+        confidence: if calls.is_empty() { 0.7 } else { 0.9 },
+        offset,
+        description,
+    }
+}
+
+fn inspect_pdf(data: &[u8]) -> Vec<DocumentFinding> {
+    let mut names = pdf_names(data);
+    // Object streams hide actions from a plain text search
+    for (offset, stream) in flate_streams(data) {
+        for name in pdf_names(&stream).into_keys() {
+            names.entry(name).or_insert(offset);
+        }
+    }
+
+    let mut findings = Vec::new();
+    let mut report = |name: &str, severity: Severity, confidence: f32, offset: u64, description: &str| {
+        findings.push(DocumentFinding {
+            name: format!("Document.PDF.{}", name),
+            severity,
+            confidence,
+            offset,
+            description: description.to_string(),
+        });
+    };
+
+    if let Some(&offset) = names.get("Launch") {
+        report("Launch", Severity::Warning, 0.85, offset, "PDF can launch external programs (/Launch)");
+    }
+
+    let javascript = names.get("JavaScript").or_else(|| names.get("JS")).copied();
+    let open_action = names.get("OpenAction").or_else(|| names.get("AA")).copied();
+    match (javascript, open_action) {
+        (Some(offset), Some(_)) => report(
+            "AutoJavaScript",
+            Severity::Warning,
+            0.8,
+            offset,
+            "PDF runs JavaScript when opened (/OpenAction with /JavaScript)",
+        ),
+        (Some(offset), None) => report(
+            "JavaScript",
+            Severity::Warning,
+            0.6,
+            offset,
+            "PDF contains JavaScript (/JavaScript)",
+        ),
+        // Jumping to a page on open is common; alone it is only worth a note
+        (None, Some(offset)) => report(
+            "OpenAction",
+            Severity::Info,
+            0.3,
+            offset,
+            "PDF performs an action when opened (/OpenAction)",
+        ),
+        (None, None) => {}
+    }
+
+    findings
+}
+
+// Name objects with their first offset; "#xx" escapes are decoded so "/J#61vaScript" reads as "JavaScript"
+fn pdf_names(data: &[u8]) -> BTreeMap<String, u64> {
+    let mut names = BTreeMap::new();
+    let mut position = 0;
+
+    while let Some(found) = memchr::memchr(b'/', &data[position..]) {
+        let start = position + found;
+        let mut end = start + 1;
+        let mut name = Vec::new();
+
+        while end < data.len() && name.len() <= 64 {
+            let c = data[end];
+            if c == b'#' {
+                let hex = data
+                    .get(end + 1..end + 3)
+                    .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok());
+                if let Some(byte) = hex {
+                    name.push(byte);
+                    end += 3;
+                    continue;
+                }
+            }
+            if !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'-' | b'+')) {
+                break;
+            }
+            name.push(c);
+            end += 1;
+        }
+
+        if !name.is_empty() && name.len() <= 64 {
+            names
+                .entry(String::from_utf8_lossy(&name).to_string())
+                .or_insert(start as u64);
+        }
+        position = end;
+    }
+
+    names
+}
+
+// Inflates every stream that decodes as zlib; others (images, fonts) are skipped
+fn flate_streams(data: &[u8]) -> Vec<(u64, Vec<u8>)> {
+    let mut streams = Vec::new();
+    let mut position = 0;
+
+    while streams.len() < MAX_PDF_STREAMS {
+        let Some(found) = memmem::find(&data[position..], b"stream") else {
+            break;
+        };
+        let keyword = position + found;
+        position = keyword + b"stream".len();
+        if data[..keyword].ends_with(b"end") {
+            continue;
+        }
+
+        let mut body = position;
+        while body < data.len() && matches!(data[body], b'\r' | b'\n') {
+            body += 1;
+        }
+        let end = memmem::find(&data[body..], b"endstream").map_or(data.len(), |end| body + end);
+
+        let mut decoded = Vec::new();
+        // A truncated stream still yields whatever inflated before the damage
+        let _ = ZlibDecoder::new(&data[body..end])
+            .take(MAX_PDF_STREAM_SIZE)
+            .read_to_end(&mut decoded);
+        if !decoded.is_empty() {
+            streams.push((body as u64, decoded));
+        }
+        position = end;
+    }
+
+    streams
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    fn names(findings: &[DocumentFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    fn ole(project: &[u8]) -> Vec<u8> {
+        let mut data = OLE_MAGIC.to_vec();
+        data.extend_from_slice(&[0; 64]);
+        data.extend(VBA_PROJECT_STREAM.bytes().flat_map(|b| [b, 0]));
+        data.extend_from_slice(project);
+        data
+    }
+
+    fn ooxml(parts: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in parts {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn recognises_documents() {
+        assert!(is_document(OLE_MAGIC));
+        assert!(is_document(b"\r\n\r\n%PDF-1.4"));
+        assert!(is_document(&ooxml(&[("a", b"b")])));
+        assert!(!is_document(b"plain text"));
+        assert!(analyze("x", b"plain text").is_empty());
+        assert!(analyze("x", b"").is_empty());
+    }
+
+    #[test]
+    fn grades_office_macros() {
+        let plain = analyze("a.doc", &ole(b"Sub Format()"));
+        assert_eq!(names(&plain), vec!["Document.Office.Macro"]);
+        assert_eq!(plain[0].severity, Severity::Info);
+
+        let auto = analyze("a.doc", &ole(b"Sub AutoOpen()"));
+        assert_eq!(names(&auto), vec!["Document.Office.AutoExecMacro"]);
+        assert_eq!(auto[0].confidence, 0.7);
+
+        let dropper = analyze("a.doc", &ole(b"Sub Document_Open() Shell URLDownloadToFile"));
+        assert_eq!(dropper[0].confidence, 0.9);
+        assert!(dropper[0].description.contains("Shell, URLDownloadToFile"));
+
+        // Compound files without a VBA project, and the project part inside a package
+        assert!(analyze("a.doc", &[OLE_MAGIC, &[0u8; 100]].concat()).is_empty());
+        assert!(analyze("x.docm!word/vbaProject.bin", &ole(b"Sub AutoOpen()")).is_empty());
+    }
+
+    #[test]
+    fn finds_macros_in_ooxml_packages() {
+        let docm = ooxml(&[
+            ("[Content_Types].xml", b"<Types/>"),
+            ("word/vbaProject.bin", b"Workbook_Open CreateObject"),
+        ]);
+        let findings = analyze("a.docm", &docm);
+        assert_eq!(names(&findings), vec!["Document.Office.AutoExecMacro"]);
+        assert!(findings[0].description.ends_with("in word/vbaProject.bin"));
+
+        let docx = ooxml(&[("[Content_Types].xml", b"<Types/>"), ("word/document.xml", b"<w/>")]);
+        assert!(analyze("a.docx", &docx).is_empty());
+        // A plain zip that happens to hold a vbaProject.bin is not an Office package
+        assert!(analyze("a.zip", &ooxml(&[("vbaProject.bin", b"AutoOpen")])).is_empty());
+    }
+
+    #[test]
+    fn grades_pdf_actions() {
+        let page = analyze("a.pdf", b"%PDF-1.7 << /OpenAction [3 0 R /Fit] >>");
+        assert_eq!(names(&page), vec!["Document.PDF.OpenAction"]);
+        assert_eq!(page[0].severity, Severity::Info);
+
+        let js = analyze("a.pdf", b"%PDF-1.7 << /S /JavaScript /JS (x) >>");
+        assert_eq!(names(&js), vec!["Document.PDF.JavaScript"]);
+
+        let auto = analyze("a.pdf", b"%PDF-1.7 << /AA << /O << /S /JavaScript >> >> >>");
+        assert_eq!(names(&auto), vec!["Document.PDF.AutoJavaScript"]);
+        assert_eq!(auto[0].offset, 28);
+
+        let launch = analyze("a.pdf", b"%PDF-1.7 << /S /Launch /F (cmd.exe) >>");
+        assert_eq!(names(&launch), vec!["Document.PDF.Launch"]);
+
+        assert!(analyze("a.pdf", b"%PDF-1.7 << /Type /Page >>").is_empty());
+    }
+
+    #[test]
+    fn decodes_escaped_and_compressed_names() {
+        let escaped = analyze("a.pdf", b"%PDF-1.7 << /OpenAction << /S /J#61vaScript >> >>");
+        assert_eq!(names(&escaped), vec!["Document.PDF.AutoJavaScript"]);
+
+        let mut hidden = b"%PDF-1.7\n1 0 obj << /OpenAction 2 0 R >>\n2 0 obj << /Filter /FlateDecode >>\nstream\r\n".to_vec();
+        hidden.extend(deflate(b"<< /S /JavaScript /JS (app.alert(1)) >>"));
+        hidden.extend_from_slice(b"\r\nendstream\nendobj\n");
+        assert_eq!(names(&analyze("a.pdf", &hidden)), vec!["Document.PDF.AutoJavaScript"]);
+
+        // Malformed escapes and streams are read as far as they go
+        let names_found = pdf_names(b"/A#zz /#4 /Ok /");
+        assert_eq!(names_found.keys().collect::<Vec<_>>(), vec!["A", "Ok"]);
+        assert!(flate_streams(b"stream\r\nnot zlib endstream").is_empty());
+        let mut truncated = b"stream\n".to_vec();
+        // Pseudo-random bytes, so half the stream still holds whole deflate blocks
+        let noise: Vec<u8> = (0..200_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let compressed = deflate(&noise);
+        truncated.extend_from_slice(&compressed[..compressed.len() / 2]);
+        assert!(!flate_streams(&truncated).is_empty());
+    }
+}
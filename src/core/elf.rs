@@ -0,0 +1,687 @@
+use anyhow::{anyhow, bail, Result};
+
+use super::heuristics::shannon_entropy;
+
+// ==================== ELF STATİK ANALİZİ ====================
+
+pub const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNAMIC: u32 = 6;
+const SHT_NOBITS: u32 = 8;
+const SHT_DYNSYM: u32 = 11;
+
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_RPATH: u64 = 15;
+const DT_RUNPATH: u64 = 29;
+
+const STT_FUNC: u8 = 2;
+
+// Bounds on what a hostile header can make us walk
+const MAX_SECTIONS: usize = 4096;
+const MAX_SEGMENTS: usize = 512;
+const MAX_SYMBOLS: usize = 200_000;
+const ENTROPY_MIN_SECTION: u64 = 4096;
+// Bytes spanned by the fields read from each table entry
+const PHDR_SPAN: (u64, u64) = (28, 40);
+const SHDR_SPAN: (u64, u64) = (28, 44);
+
+#[derive(Debug, Clone)]
+pub struct ElfSection {
+    pub name: String,
+    pub kind: u32,
+    pub flags: u64,
+    pub offset: u64,
+    pub size: u64,
+    // None for sections without file contents or too small to judge
+    pub entropy: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ElfSegment {
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub filesz: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ElfInfo {
+    pub is_64: bool,
+    pub big_endian: bool,
+    pub file_type: u16,
+    pub machine: u16,
+    pub entry: u64,
+    pub segments: Vec<ElfSegment>,
+    pub sections: Vec<ElfSection>,
+    pub interpreter: Option<String>,
+    pub needed: Vec<String>,
+    // Undefined dynamic symbols, plus linked-in functions of static binaries that kept .symtab
+    pub imports: Vec<String>,
+    pub rpath: Option<String>,
+    pub runpath: Option<String>,
+    pub is_static: bool,
+    pub stripped: bool,
+    pub packer: Option<String>,
+}
+
+struct ElfReader<'a> {
+    data: &'a [u8],
+    is_64: bool,
+    big_endian: bool,
+}
+
+impl<'a> ElfReader<'a> {
+    fn bytes<const N: usize>(&self, offset: u64) -> Option<[u8; N]> {
+        let start = usize::try_from(offset).ok()?;
+        self.data.get(start..start.checked_add(N)?)?.try_into().ok()
+    }
+
+    fn u8(&self, offset: u64) -> Option<u8> {
+        self.data.get(usize::try_from(offset).ok()?).copied()
+    }
+
+    fn u16(&self, offset: u64) -> Option<u16> {
+        let b = self.bytes::<2>(offset)?;
+        Some(if self.big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    }
+
+    fn u32(&self, offset: u64) -> Option<u32> {
+        let b = self.bytes::<4>(offset)?;
+        Some(if self.big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    }
+
+    fn u64(&self, offset: u64) -> Option<u64> {
+        let b = self.bytes::<8>(offset)?;
+        Some(if self.big_endian { u64::from_be_bytes(b) } else { u64::from_le_bytes(b) })
+    }
+
+    // Address-sized field
+    fn word(&self, offset: u64) -> Option<u64> {
+        if self.is_64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+
+    fn range(&self, offset: u64, size: u64) -> Option<&'a [u8]> {
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        self.data.get(start..end)
+    }
+
+    fn c_string(&self, offset: u64) -> Option<String> {
+        let start = usize::try_from(offset).ok()?;
+        let rest = self.data.get(start..)?;
+        let end = rest.iter().position(|&b| b == 0)?;
+        Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+    }
+}
+
+impl ElfInfo {
+    // Parses as much as the data allows; a truncated image yields partial section and symbol data
+    pub fn parse(data: &[u8]) -> Result<ElfInfo> {
+        if !data.starts_with(ELF_MAGIC) || data.len() < 52 {
+            bail!("Not an ELF image");
+        }
+        let is_64 = match data[4] {
+            1 => false,
+            2 => true,
+            class => bail!("Unknown ELF class {}", class),
+        };
+        let big_endian = match data[5] {
+            1 => false,
+            2 => true,
+            encoding => bail!("Unknown ELF data encoding {}", encoding),
+        };
+        let r = ElfReader { data, is_64, big_endian };
+        let truncated = || anyhow!("Truncated ELF header");
+
+        let (phoff, shoff, sizes) = if is_64 {
+            (r.u64(32), r.u64(40), 54)
+        } else {
+            (r.u32(28).map(u64::from), r.u32(32).map(u64::from), 42)
+        };
+        let phoff = phoff.ok_or_else(truncated)?;
+        let shoff = shoff.ok_or_else(truncated)?;
+        let phentsize = r.u16(sizes).ok_or_else(truncated)? as u64;
+        let phnum = r.u16(sizes + 2).ok_or_else(truncated)? as usize;
+        let shentsize = r.u16(sizes + 4).ok_or_else(truncated)? as u64;
+        let shnum = r.u16(sizes + 6).ok_or_else(truncated)? as usize;
+        let shstrndx = r.u16(sizes + 8).ok_or_else(truncated)? as usize;
+
+        let mut info = ElfInfo {
+            is_64,
+            big_endian,
+            file_type: r.u16(16).ok_or_else(truncated)?,
+            machine: r.u16(18).ok_or_else(truncated)?,
+            entry: r.word(24).ok_or_else(truncated)?,
+            segments: Vec::new(),
+            sections: Vec::new(),
+            interpreter: None,
+            needed: Vec::new(),
+            imports: Vec::new(),
+            rpath: None,
+            runpath: None,
+            is_static: false,
+            stripped: true,
+            packer: None,
+        };
+
+        for i in 0..phnum.min(MAX_SEGMENTS) {
+            let base = entry_base(phoff, i, phentsize, if is_64 { PHDR_SPAN.1 } else { PHDR_SPAN.0 })
+                .ok_or_else(|| anyhow!("Malformed ELF program header table"))?;
+            let segment = if is_64 {
+                (|| {
+                    Some(ElfSegment {
+                        kind: r.u32(base)?,
+                        flags: r.u32(base + 4)?,
+                        offset: r.u64(base + 8)?,
+                        vaddr: r.u64(base + 16)?,
+                        filesz: r.u64(base + 32)?,
+                    })
+                })()
+            } else {
+                (|| {
+                    Some(ElfSegment {
+                        kind: r.u32(base)?,
+                        offset: r.u32(base + 4)?.into(),
+                        vaddr: r.u32(base + 8)?.into(),
+                        filesz: r.u32(base + 16)?.into(),
+                        flags: r.u32(base + 24)?,
+                    })
+                })()
+            };
+            match segment {
+                Some(segment) => info.segments.push(segment),
+                None => break,
+            }
+        }
+
+        if let Some(interp) = info.segments.iter().find(|s| s.kind == PT_INTERP) {
+            info.interpreter = r.c_string(interp.offset);
+        }
+
+        let headers = info.read_section_headers(&r, shoff, shentsize, shnum, shstrndx)?;
+        info.sections = headers.iter().map(|h| h.section.clone()).collect();
+        info.stripped = !headers.iter().any(|h| h.section.kind == SHT_SYMTAB);
+
+        // Dynamic section through section headers, or through PT_DYNAMIC when they were removed
+        let dynamic = headers
+            .iter()
+            .find(|h| h.section.kind == SHT_DYNAMIC)
+            .map(|h| (h.section.offset, h.section.size, headers.get(h.link).map(|s| s.section.offset)));
+        let dynamic = dynamic.or_else(|| {
+            info.segments
+                .iter()
+                .find(|s| s.kind == PT_DYNAMIC)
+                .map(|s| (s.offset, s.filesz, None))
+        });
+        if let Some((offset, size, strtab)) = dynamic {
+            info.read_dynamic(&r, offset, size, strtab)?;
+        }
+
+        for header in &headers {
+            let wanted = match header.section.kind {
+                SHT_DYNSYM => true,
+                // Static binaries carry their libc functions in .symtab
+                SHT_SYMTAB => info.interpreter.is_none() && info.needed.is_empty(),
+                _ => false,
+            };
+            if wanted {
+                if let Some(strtab) = headers.get(header.link) {
+                    info.read_symbols(&r, &header.section, strtab.section.offset)?;
+                }
+            }
+        }
+        info.imports.sort();
+        info.imports.dedup();
+
+        info.is_static = info.interpreter.is_none()
+            && info.needed.is_empty()
+            && !info.segments.is_empty();
+        info.packer = detect_packer(data, &info);
+
+        Ok(info)
+    }
+
+    fn read_section_headers(
+        &self,
+        r: &ElfReader,
+        shoff: u64,
+        shentsize: u64,
+        shnum: usize,
+        shstrndx: usize,
+    ) -> Result<Vec<SectionHeader>> {
+        let mut headers = Vec::new();
+        if shoff == 0 {
+            return Ok(headers);
+        }
+
+        for i in 0..shnum.min(MAX_SECTIONS) {
+            let base = entry_base(shoff, i, shentsize, if self.is_64 { SHDR_SPAN.1 } else { SHDR_SPAN.0 })
+                .ok_or_else(|| anyhow!("Malformed ELF section header table"))?;
+            let header = if self.is_64 {
+                (|| {
+                    Some((
+                        r.u32(base)?,
+                        r.u32(base + 4)?,
+                        r.u64(base + 8)?,
+                        r.u64(base + 24)?,
+                        r.u64(base + 32)?,
+                        r.u32(base + 40)?,
+                    ))
+                })()
+            } else {
+                (|| {
+                    Some((
+                        r.u32(base)?,
+                        r.u32(base + 4)?,
+                        r.u32(base + 8)?.into(),
+                        r.u32(base + 16)?.into(),
+                        r.u32(base + 20)?.into(),
+                        r.u32(base + 24)?,
+                    ))
+                })()
+            };
+            let Some((name, kind, flags, offset, size, link)) = header else {
+                break;
+            };
+
+            let entropy = if kind != SHT_NOBITS && size >= ENTROPY_MIN_SECTION {
+                r.range(offset, size).map(shannon_entropy)
+            } else {
+                None
+            };
+
+            headers.push(SectionHeader {
+                name_offset: name,
+                link: link as usize,
+                section: ElfSection {
+                    name: String::new(),
+                    kind,
+                    flags,
+                    offset,
+                    size,
+                    entropy,
+                },
+            });
+        }
+
+        if let Some(names) = headers.get(shstrndx).map(|h| h.section.offset) {
+            for header in &mut headers {
+                let offset = names
+                    .checked_add(header.name_offset as u64)
+                    .ok_or_else(|| anyhow!("Malformed ELF section name offset"))?;
+                header.section.name = r.c_string(offset).unwrap_or_default();
+            }
+        }
+
+        Ok(headers)
+    }
+
+    fn read_dynamic(&mut self, r: &ElfReader, offset: u64, size: u64, strtab: Option<u64>) -> Result<()> {
+        let entry_size = if self.is_64 { 16 } else { 8 };
+        let mut entries = Vec::new();
+        offset
+            .checked_add(size)
+            .ok_or_else(|| anyhow!("Malformed ELF dynamic section"))?;
+
+        for i in 0..size / entry_size {
+            let base = offset + i * entry_size;
+            let (Some(tag), Some(value)) = (r.word(base), r.word(base + entry_size / 2)) else {
+                break;
+            };
+            if tag == DT_NULL {
+                break;
+            }
+            entries.push((tag, value));
+        }
+
+        // Without section headers the string table is found by mapping DT_STRTAB to a file offset
+        let strtab = strtab.or_else(|| {
+            let address = entries.iter().find(|(tag, _)| *tag == DT_STRTAB)?.1;
+            self.segments
+                .iter()
+                .filter(|s| s.kind == PT_LOAD)
+                .find(|s| address >= s.vaddr && s.vaddr.checked_add(s.filesz).is_some_and(|end| address < end))
+                .and_then(|s| s.offset.checked_add(address - s.vaddr))
+        });
+        let Some(strtab) = strtab else {
+            return Ok(());
+        };
+
+        for (tag, value) in entries {
+            let offset = strtab
+                .checked_add(value)
+                .ok_or_else(|| anyhow!("Malformed ELF dynamic string offset"))?;
+            let text = r.c_string(offset);
+            match tag {
+                DT_NEEDED => self.needed.extend(text),
+                DT_RPATH => self.rpath = text,
+                DT_RUNPATH => self.runpath = text,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_symbols(&mut self, r: &ElfReader, symtab: &ElfSection, strtab: u64) -> Result<()> {
+        let entry_size = if self.is_64 { 24 } else { 16 };
+        let count = (symtab.size / entry_size).min(MAX_SYMBOLS as u64);
+        symtab
+            .offset
+            .checked_add(count * entry_size)
+            .ok_or_else(|| anyhow!("Malformed ELF symbol table"))?;
+
+        for i in 1..count {
+            let base = symtab.offset + i * entry_size;
+            let (info, shndx) = if self.is_64 {
+                (r.u8(base + 4), r.u16(base + 6))
+            } else {
+                (r.u8(base + 12), r.u16(base + 14))
+            };
+            let (Some(name), Some(info), Some(shndx)) = (r.u32(base), info, shndx) else {
+                break;
+            };
+
+            let undefined = shndx == 0;
+            let function = info & 0x0f == STT_FUNC;
+            let wanted = if symtab.kind == SHT_DYNSYM { undefined } else { function };
+            if !wanted || name == 0 {
+                continue;
+            }
+
+            let offset = strtab
+                .checked_add(name as u64)
+                .ok_or_else(|| anyhow!("Malformed ELF symbol name offset"))?;
+            if let Some(name) = r.c_string(offset) {
+                // Versioned names look like "memcpy@GLIBC_2.14"
+                let name = name.split('@').next().unwrap_or_default().to_string();
+                if !name.is_empty() {
+                    self.imports.push(name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn imports_symbol(&self, name: &str) -> bool {
+        self.imports.binary_search_by(|import| import.as_str().cmp(name)).is_ok()
+    }
+
+    pub fn needs_library(&self, name: &str) -> bool {
+        self.needed.iter().any(|library| library == name)
+    }
+
+    pub fn is_packed(&self) -> bool {
+        self.packer.is_some()
+    }
+
+    pub fn section(&self, name: &str) -> Option<&ElfSection> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+}
+
+// Offset of entry `index`, provided every field read from it stays addressable
+fn entry_base(table: u64, index: usize, entry_size: u64, span: u64) -> Option<u64> {
+    let base = table.checked_add((index as u64).checked_mul(entry_size)?)?;
+    base.checked_add(span)?;
+    Some(base)
+}
+
+struct SectionHeader {
+    name_offset: u32,
+    link: usize,
+    section: ElfSection,
+}
+
+fn detect_packer(data: &[u8], info: &ElfInfo) -> Option<String> {
+    let head = &data[..data.len().min(4096)];
+    let upx_sections = info.sections.iter().any(|s| s.name.starts_with("UPX"));
+    if upx_sections
+        || memchr::memmem::find(head, b"UPX!").is_some()
+        || memchr::memmem::find(data, b"This file is packed with the UPX").is_some()
+    {
+        return Some("UPX".to_string());
+    }
+
+    if info.sections.iter().any(|s| s.name.starts_with(".MPRESS")) {
+        return Some("MPRESS".to_string());
+    }
+
+    // Self-unpacking stubs drop section headers and map one writable, executable segment
+    let wx_load = info
+        .segments
+        .iter()
+        .any(|s| s.kind == PT_LOAD && s.flags & (PF_W | PF_X) == (PF_W | PF_X));
+    if info.sections.is_empty() && wx_load {
+        return Some("Unknown".to_string());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERP: &[u8] = b"/lib64/ld-linux-x86-64.so.2\0";
+    const DYNSTR: &[u8] = b"\0libc.so.6\0ptrace\0memcpy@GLIBC_2.14\0";
+    const SHSTRTAB: &[u8] = b"\0.shstrtab\0.dynstr\0.dynsym\0.dynamic\0";
+
+    // Field offsets inside the image built by `sample`
+    const PHOFF_FIELD: usize = 32;
+    const SHOFF_FIELD: usize = 40;
+
+    fn put(image: &mut [u8], at: usize, bytes: &[u8]) {
+        image[at..at + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn put_u64(image: &mut [u8], at: usize, value: u64) {
+        put(image, at, &value.to_le_bytes());
+    }
+
+    fn section_header(image: &[u8], index: usize) -> usize {
+        let shoff = u64::from_le_bytes(image[SHOFF_FIELD..SHOFF_FIELD + 8].try_into().unwrap()) as usize;
+        shoff + index * 64
+    }
+
+    // A dynamically linked x86-64 executable: PT_INTERP and PT_LOAD, .dynstr, .dynsym,
+    // .dynamic and .shstrtab, importing ptrace and a versioned memcpy from libc
+    fn sample() -> Vec<u8> {
+        let interp = 176;
+        let dynstr = interp + INTERP.len();
+        let dynsym = dynstr + DYNSTR.len();
+        let dynamic = dynsym + 3 * 24;
+        let shstrtab = dynamic + 2 * 16;
+        let shoff = shstrtab + SHSTRTAB.len();
+        let mut image = vec![0u8; shoff + 5 * 64];
+
+        put(&mut image, 0, b"\x7fELF\x02\x01\x01");
+        put(&mut image, 16, &2u16.to_le_bytes());
+        put(&mut image, 18, &62u16.to_le_bytes());
+        put_u64(&mut image, 24, 0x401000);
+        put_u64(&mut image, PHOFF_FIELD, 64);
+        put_u64(&mut image, SHOFF_FIELD, shoff as u64);
+        for (at, value) in [(52, 64u16), (54, 56), (56, 2), (58, 64), (60, 5), (62, 4)] {
+            put(&mut image, at, &value.to_le_bytes());
+        }
+
+        for (i, (kind, flags, offset, vaddr, size)) in [
+            (PT_INTERP, 4, interp, 0, INTERP.len()),
+            (PT_LOAD, 5, 0, 0x400000, image.len()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let base = 64 + i * 56;
+            put(&mut image, base, &kind.to_le_bytes());
+            put(&mut image, base + 4, &(flags as u32).to_le_bytes());
+            put_u64(&mut image, base + 8, offset as u64);
+            put_u64(&mut image, base + 16, vaddr);
+            put_u64(&mut image, base + 32, size as u64);
+        }
+
+        put(&mut image, interp, INTERP);
+        put(&mut image, dynstr, DYNSTR);
+        // Undefined function symbols; entry 0 is the reserved null symbol
+        for (i, name) in [11u32, 18].into_iter().enumerate() {
+            let base = dynsym + (i + 1) * 24;
+            put(&mut image, base, &name.to_le_bytes());
+            image[base + 4] = 0x10 | STT_FUNC;
+        }
+        put_u64(&mut image, dynamic, DT_NEEDED);
+        put_u64(&mut image, dynamic + 8, 1);
+        put(&mut image, shstrtab, SHSTRTAB);
+
+        for (i, (name, kind, offset, size, link)) in [
+            (11u32, 3u32, dynstr, DYNSTR.len(), 0u32),
+            (19, SHT_DYNSYM, dynsym, 3 * 24, 1),
+            (27, SHT_DYNAMIC, dynamic, 2 * 16, 1),
+            (1, 3, shstrtab, SHSTRTAB.len(), 0),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let base = section_header(&image, i + 1);
+            put(&mut image, base, &name.to_le_bytes());
+            put(&mut image, base + 4, &kind.to_le_bytes());
+            put_u64(&mut image, base + 24, offset as u64);
+            put_u64(&mut image, base + 32, size as u64);
+            put(&mut image, base + 40, &link.to_le_bytes());
+        }
+
+        image
+    }
+
+    #[test]
+    fn parses_dynamic_executable() {
+        let info = ElfInfo::parse(&sample()).unwrap();
+        assert!(info.is_64 && !info.big_endian);
+        assert_eq!((info.file_type, info.machine, info.entry), (2, 62, 0x401000));
+        assert_eq!(info.interpreter.as_deref(), Some("/lib64/ld-linux-x86-64.so.2"));
+        assert!(info.needs_library("libc.so.6"));
+        assert_eq!(info.imports, vec!["memcpy", "ptrace"]);
+        assert!(info.imports_symbol("ptrace") && !info.imports_symbol("execve"));
+        assert_eq!(info.section(".dynsym").map(|s| s.kind), Some(SHT_DYNSYM));
+        assert_eq!(info.section(".dynamic").map(|s| s.kind), Some(SHT_DYNAMIC));
+        assert!(info.stripped);
+        assert!(!info.is_static);
+        assert!(!info.is_packed());
+    }
+
+    #[test]
+    fn finds_dynamic_data_without_section_headers() {
+        let mut image = sample();
+        put_u64(&mut image, SHOFF_FIELD, 0);
+        // PT_DYNAMIC replaces the .dynamic section header
+        let dynamic = 176 + INTERP.len() + DYNSTR.len() + 3 * 24;
+        let base = 64 + 56;
+        put(&mut image, base, &PT_DYNAMIC.to_le_bytes());
+        put_u64(&mut image, base + 8, dynamic as u64);
+        put_u64(&mut image, base + 32, 32);
+        // Dropping the PT_LOAD leaves DT_STRTAB unmappable, so only the fallback path runs
+        let info = ElfInfo::parse(&image).unwrap();
+        assert!(info.sections.is_empty());
+        assert!(info.needed.is_empty());
+        assert!(!info.is_static);
+    }
+
+    #[test]
+    fn rejects_non_elf_input() {
+        assert!(ElfInfo::parse(b"").is_err());
+        assert!(ElfInfo::parse(b"\x7fELF\x02\x01").is_err());
+        assert!(ElfInfo::parse(&[b'M', b'Z'].repeat(40)).is_err());
+
+        let mut image = sample();
+        image[4] = 3;
+        assert!(ElfInfo::parse(&image).unwrap_err().to_string().contains("class"));
+        let mut image = sample();
+        image[5] = 0;
+        assert!(ElfInfo::parse(&image).unwrap_err().to_string().contains("encoding"));
+    }
+
+    #[test]
+    fn truncated_images_parse_partially() {
+        let image = sample();
+        assert!(ElfInfo::parse(&image[..60]).is_err());
+        for len in [64, 100, 176, 200, image.len() - 1] {
+            let info = ElfInfo::parse(&image[..len]).unwrap();
+            assert!(info.imports.len() <= 2, "{}", len);
+        }
+        let info = ElfInfo::parse(&image[..120]).unwrap();
+        assert_eq!(info.segments.len(), 1);
+        assert!(info.sections.is_empty());
+    }
+
+    #[test]
+    fn overflowing_header_offsets_are_malformed() {
+        let mut image = sample();
+        put_u64(&mut image, PHOFF_FIELD, u64::MAX - 10);
+        assert!(ElfInfo::parse(&image).unwrap_err().to_string().contains("program header"));
+
+        let mut image = sample();
+        put_u64(&mut image, SHOFF_FIELD, u64::MAX - 10);
+        assert!(ElfInfo::parse(&image).unwrap_err().to_string().contains("section header"));
+
+        // .shstrtab placed so that adding a name offset wraps
+        let mut image = sample();
+        let base = section_header(&image, 4);
+        put_u64(&mut image, base + 24, u64::MAX);
+        assert!(ElfInfo::parse(&image).unwrap_err().to_string().contains("section name"));
+
+        let mut image = sample();
+        let base = section_header(&image, 3);
+        put_u64(&mut image, base + 24, u64::MAX - 8);
+        assert!(ElfInfo::parse(&image).unwrap_err().to_string().contains("dynamic section"));
+
+        let mut image = sample();
+        let base = section_header(&image, 2);
+        put_u64(&mut image, base + 24, u64::MAX - 24);
+        assert!(ElfInfo::parse(&image).unwrap_err().to_string().contains("symbol table"));
+
+        // .dynstr at the top of the address space: both string lookups wrap
+        let mut image = sample();
+        let base = section_header(&image, 1);
+        put_u64(&mut image, base + 24, u64::MAX - 4);
+        assert!(ElfInfo::parse(&image).is_err());
+    }
+
+    #[test]
+    fn detects_packers() {
+        let mut image = sample();
+        put(&mut image, 100, b"UPX!");
+        assert_eq!(ElfInfo::parse(&image).unwrap().packer.as_deref(), Some("UPX"));
+
+        // No section headers and a writable, executable load segment
+        let mut image = sample();
+        put_u64(&mut image, SHOFF_FIELD, 0);
+        put(&mut image, 64 + 56 + 4, &(PF_W | PF_X).to_le_bytes());
+        assert_eq!(ElfInfo::parse(&image).unwrap().packer.as_deref(), Some("Unknown"));
+    }
+
+    #[test]
+    fn parses_bare_32_bit_header() {
+        let mut image = vec![0u8; 52];
+        put(&mut image, 0, b"\x7fELF\x01\x02\x01");
+        put(&mut image, 16, &3u16.to_be_bytes());
+        put(&mut image, 24, &0x8048000u32.to_be_bytes());
+        let info = ElfInfo::parse(&image).unwrap();
+        assert!(!info.is_64 && info.big_endian);
+        assert_eq!((info.file_type, info.entry), (3, 0x8048000));
+        assert!(info.segments.is_empty() && !info.is_static);
+    }
+}
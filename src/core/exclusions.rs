@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+// ==================== TARAMA HARİÇ TUTMALARI ====================
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+enum PathRule {
+    // Plain path, excludes itself and everything below it
+    Prefix(PathBuf),
+    // Glob without a separator, tested against the file name only (e.g. "*.iso")
+    Name(Pattern),
+    // Glob tested against the full path (e.g. "**/node_modules/**")
+    Full(Pattern),
+}
+
+pub struct ScanExclusions {
+    extensions: Vec<String>,
+    paths: Vec<PathRule>,
+}
+
+impl ScanExclusions {
+    pub fn new(excluded_extensions: &[String], excluded_paths: &[PathBuf]) -> Self {
+        let extensions = excluded_extensions
+            .iter()
+            .map(|ext| ext.trim().trim_start_matches('*').trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| format!(".{}", ext))
+            .collect();
+
+        let mut paths = Vec::new();
+        for path in excluded_paths {
+            let raw = path.to_string_lossy();
+            if !raw.contains(['*', '?', '[']) {
+                paths.push(PathRule::Prefix(path.clone()));
+                continue;
+            }
+
+            match Pattern::new(&raw) {
+                Ok(pattern) if raw.contains('/') => paths.push(PathRule::Full(pattern)),
+                Ok(pattern) => paths.push(PathRule::Name(pattern)),
+                Err(e) => log::warn!("Ignoring invalid exclusion pattern {:?}: {}", raw, e),
+            }
+        }
+
+        ScanExclusions { extensions, paths }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty() && self.paths.is_empty()
+    }
+
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if !is_dir && self.extensions.iter().any(|ext| name.ends_with(ext.as_str())) {
+            return true;
+        }
+
+        let full = path.to_string_lossy();
+        // "dir/**" only matches below the directory, so test the directory itself
+        // with a trailing separator to prune it before descending
+        let dir_form = if is_dir { Some(format!("{}/", full)) } else { None };
+
+        self.paths.iter().any(|rule| match rule {
+            PathRule::Prefix(prefix) => path.starts_with(prefix),
+            PathRule::Name(pattern) => path
+                .file_name()
+                .map(|name| pattern.matches_with(&name.to_string_lossy(), MATCH_OPTIONS))
+                .unwrap_or(false),
+            PathRule::Full(pattern) => {
+                pattern.matches_with(&full, MATCH_OPTIONS)
+                    || dir_form
+                        .as_deref()
+                        .map(|dir| pattern.matches_with(dir, MATCH_OPTIONS))
+                        .unwrap_or(false)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exclusions(extensions: &[&str], paths: &[&str]) -> ScanExclusions {
+        let extensions: Vec<String> = extensions.iter().map(|ext| ext.to_string()).collect();
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        ScanExclusions::new(&extensions, &paths)
+    }
+
+    #[test]
+    fn extensions_match_in_any_spelling() {
+        let rules = exclusions(&[".ISO", "*.tmp", "log", "  "], &[]);
+
+        assert!(rules.is_excluded(Path::new("/data/disk.iso"), false));
+        assert!(rules.is_excluded(Path::new("/data/a.TMP"), false));
+        assert!(rules.is_excluded(Path::new("/var/x.log"), false));
+        assert!(!rules.is_excluded(Path::new("/var/catalog"), false));
+        // Directories are never excluded by extension
+        assert!(!rules.is_excluded(Path::new("/data/backup.iso"), true));
+    }
+
+    #[test]
+    fn plain_paths_exclude_their_subtree() {
+        let rules = exclusions(&[], &["/home/user/vm"]);
+
+        assert!(rules.is_excluded(Path::new("/home/user/vm"), true));
+        assert!(rules.is_excluded(Path::new("/home/user/vm/disk.qcow2"), false));
+        assert!(!rules.is_excluded(Path::new("/home/user/vmware"), true));
+    }
+
+    #[test]
+    fn globs_without_separator_match_the_name() {
+        let rules = exclusions(&[], &["*.qcow2"]);
+
+        assert!(rules.is_excluded(Path::new("/srv/images/win.qcow2"), false));
+        assert!(!rules.is_excluded(Path::new("/srv/images/win.qcow2.txt"), false));
+    }
+
+    #[test]
+    fn full_globs_prune_directories_before_descending() {
+        let rules = exclusions(&[], &["**/node_modules/**"]);
+
+        assert!(rules.is_excluded(Path::new("/home/u/app/node_modules"), true));
+        assert!(rules.is_excluded(Path::new("/home/u/app/node_modules/x/index.js"), false));
+        assert!(!rules.is_excluded(Path::new("/home/u/app/src/node_modules.js"), false));
+    }
+
+    #[test]
+    fn invalid_globs_are_ignored() {
+        let rules = exclusions(&[], &["/data/[unclosed"]);
+
+        assert!(rules.is_empty());
+        assert!(!rules.is_excluded(Path::new("/data/[unclosed"), false));
+    }
+}
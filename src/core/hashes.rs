@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{Severity, ThreatCategory, ThreatSignature};
+
+// ==================== HASH İMZALARI ====================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Md5,
+}
+
+#[derive(Debug, Clone)]
+pub struct HashSignature {
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+    pub size: Option<u64>,
+    pub signature: ThreatSignature,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FileDigests {
+    pub sha256: [u8; 32],
+    pub md5: Option<[u8; 16]>,
+}
+
+impl FileDigests {
+    pub fn sha256_hex(&self) -> String {
+        to_hex(&self.sha256)
+    }
+}
+
+struct HashEntry {
+    size: Option<u64>,
+    signature: ThreatSignature,
+}
+
+// Digests are stored as fixed-size keys, so probing never parses or compares hex strings.
+// One digest may be listed with several sizes; each is kept.
+#[derive(Default)]
+pub struct HashSignatureSet {
+    sha256: HashMap<[u8; 32], Vec<HashEntry>>,
+    md5: HashMap<[u8; 16], Vec<HashEntry>>,
+}
+
+impl HashSignatureSet {
+    // False for malformed digests and for a digest and size already present, whose entry is replaced
+    pub fn insert(&mut self, hash: HashSignature) -> bool {
+        let entry = HashEntry {
+            size: hash.size,
+            signature: hash.signature,
+        };
+        match hash.algorithm {
+            HashAlgorithm::Sha256 => match parse_digest::<32>(&hash.digest) {
+                Some(digest) => add_entry(self.sha256.entry(digest).or_default(), entry),
+                None => false,
+            },
+            HashAlgorithm::Md5 => match parse_digest::<16>(&hash.digest) {
+                Some(digest) => add_entry(self.md5.entry(digest).or_default(), entry),
+                None => false,
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sha256.values().map(Vec::len).sum::<usize>() + self.md5.values().map(Vec::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sha256.is_empty() && self.md5.is_empty()
+    }
+
+    pub fn has_md5(&self) -> bool {
+        !self.md5.is_empty()
+    }
+
+    pub fn lookup(&self, digests: &FileDigests, size: u64) -> Option<&ThreatSignature> {
+        self.sha256
+            .get(&digests.sha256)
+            .and_then(|entries| matching(entries, size))
+            .or_else(|| {
+                digests
+                    .md5
+                    .and_then(|md5| self.md5.get(&md5))
+                    .and_then(|entries| matching(entries, size))
+            })
+            .map(|entry| &entry.signature)
+    }
+}
+
+// Entries without a recorded size match any file
+fn matching(entries: &[HashEntry], size: u64) -> Option<&HashEntry> {
+    entries.iter().find(|entry| entry.size.is_none_or(|s| s == size))
+}
+
+fn add_entry(entries: &mut Vec<HashEntry>, entry: HashEntry) -> bool {
+    match entries.iter_mut().find(|existing| existing.size == entry.size) {
+        Some(existing) => {
+            *existing = entry;
+            false
+        }
+        None => {
+            entries.push(entry);
+            true
+        }
+    }
+}
+
+pub fn parse_digest<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    let hex = hex.trim();
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut digest = [0u8; N];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(digest)
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Plain blocklists: one "<md5 or sha256 hex> <name>" entry per line
+pub fn load_hash_lists_from_dir(dir: &Path) -> Vec<HashSignature> {
+    let mut files: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|ext| ext == "hashlist").unwrap_or(false))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    let mut hashes = Vec::new();
+    for path in files {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("Skipping hash list {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (digest, name) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            if !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+                continue;
+            }
+            let algorithm = match digest.len() {
+                64 => HashAlgorithm::Sha256,
+                32 => HashAlgorithm::Md5,
+                _ => continue,
+            };
+            let name = name.trim();
+
+            hashes.push(HashSignature {
+                algorithm,
+                digest: digest.to_lowercase(),
+                size: None,
+                signature: ThreatSignature {
+                    name: if name.is_empty() {
+                        format!("Blocklisted.{}", &digest[..12])
+                    } else {
+                        name.to_string()
+                    },
+                    pattern: Vec::new(),
+                    category: ThreatCategory::Unknown,
+                    severity: Severity::Critical,
+                },
+            });
+        }
+    }
+
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(name: &str) -> ThreatSignature {
+        ThreatSignature {
+            name: name.to_string(),
+            pattern: Vec::new(),
+            category: ThreatCategory::Trojan,
+            severity: Severity::Critical,
+        }
+    }
+
+    fn hash(algorithm: HashAlgorithm, digest: &str, size: Option<u64>, name: &str) -> HashSignature {
+        HashSignature {
+            algorithm,
+            digest: digest.to_string(),
+            size,
+            signature: signature(name),
+        }
+    }
+
+    const SHA: &str = "275a021bbfb6489e54d471899f7db9d1663fc695ec2fe2a2c4538aabf651fd0f";
+    const MD5: &str = "44d88612fea8a8f36de82e1278abb02f";
+
+    fn digests() -> FileDigests {
+        FileDigests {
+            sha256: parse_digest::<32>(SHA).unwrap(),
+            md5: parse_digest::<16>(MD5),
+        }
+    }
+
+    #[test]
+    fn parses_and_prints_digests() {
+        let digest = parse_digest::<16>(&format!("  {}  ", MD5.to_uppercase())).unwrap();
+
+        assert_eq!(to_hex(&digest), MD5);
+        assert!(parse_digest::<16>(&MD5[..30]).is_none());
+        assert!(parse_digest::<16>(&format!("{}zz", &MD5[..30])).is_none());
+        assert!(parse_digest::<2>("é€").is_none());
+    }
+
+    #[test]
+    fn looks_up_sha256_then_md5() {
+        let mut set = HashSignatureSet::default();
+        assert!(set.insert(hash(HashAlgorithm::Md5, MD5, None, "ByMd5")));
+        assert!(set.has_md5());
+        assert_eq!(set.lookup(&digests(), 68).unwrap().name, "ByMd5");
+
+        assert!(set.insert(hash(HashAlgorithm::Sha256, SHA, None, "BySha")));
+        assert_eq!(set.lookup(&digests(), 68).unwrap().name, "BySha");
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn size_must_match_when_recorded() {
+        let mut set = HashSignatureSet::default();
+        set.insert(hash(HashAlgorithm::Sha256, SHA, Some(68), "Sized"));
+
+        assert!(set.lookup(&digests(), 68).is_some());
+        assert!(set.lookup(&digests(), 69).is_none());
+    }
+
+    #[test]
+    fn keeps_every_size_listed_for_a_digest() {
+        let mut set = HashSignatureSet::default();
+        assert!(set.insert(hash(HashAlgorithm::Sha256, SHA, Some(68), "Small")));
+        assert!(set.insert(hash(HashAlgorithm::Sha256, SHA, Some(4096), "Large")));
+        assert_eq!(set.len(), 2);
+
+        assert_eq!(set.lookup(&digests(), 68).unwrap().name, "Small");
+        assert_eq!(set.lookup(&digests(), 4096).unwrap().name, "Large");
+        assert!(set.lookup(&digests(), 69).is_none());
+
+        // The same digest and size again replaces the entry
+        assert!(!set.insert(hash(HashAlgorithm::Sha256, SHA, Some(68), "Renamed")));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.lookup(&digests(), 68).unwrap().name, "Renamed");
+    }
+
+    #[test]
+    fn rejects_malformed_digests() {
+        let mut set = HashSignatureSet::default();
+
+        assert!(!set.insert(hash(HashAlgorithm::Sha256, MD5, None, "WrongLength")));
+        assert!(!set.insert(hash(HashAlgorithm::Md5, "g4d88612fea8a8f36de82e1278abb02f", None, "NotHex")));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn loads_blocklists_and_skips_bad_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let non_ascii = format!("a{}", "€".repeat(21));
+        assert_eq!(non_ascii.len(), 64);
+        let list = format!(
+            "# comment\n\n{} Known.Bad\n{}\n{} Not.Hex\nshort Too.Short\n",
+            SHA.to_uppercase(),
+            MD5,
+            non_ascii
+        );
+        fs::write(dir.path().join("a.hashlist"), list).unwrap();
+        fs::write(dir.path().join("ignored.txt"), SHA).unwrap();
+
+        let hashes = load_hash_lists_from_dir(dir.path());
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[0].algorithm, HashAlgorithm::Sha256);
+        assert_eq!(hashes[0].digest, SHA);
+        assert_eq!(hashes[0].signature.name, "Known.Bad");
+        assert_eq!(hashes[1].algorithm, HashAlgorithm::Md5);
+        assert_eq!(hashes[1].signature.name, "Blocklisted.44d88612fea8");
+    }
+}
@@ -0,0 +1,448 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+
+use super::elf::ElfInfo;
+use super::{Severity, ThreatCategory, ThreatSignature};
+
+// ==================== SEZGİSEL ANALİZ ====================
+
+const SHF_ALLOC: u64 = 2;
+
+const ENTROPY_BLOCK: usize = 64 * 1024;
+// Compressed or encrypted data sits close to 8 bits per byte; compiled code rarely exceeds 6.5
+const PACKED_ENTROPY: f64 = 7.2;
+
+const BASE64_BLOB_MIN: usize = 4096;
+
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "sh", "bash", "zsh", "run", "bin", "elf", "appimage", "desktop", "py", "pl", "exe", "scr", "bat",
+    "cmd", "js", "vbs", "jar",
+];
+
+const DECOY_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "txt", "rtf", "jpg", "jpeg", "png",
+    "gif", "mp3", "mp4", "avi", "zip",
+];
+
+const WRITABLE_LOCATIONS: &[&str] = &["/tmp/", "/var/tmp/", "/dev/shm/", "/run/user/"];
+
+// Imports that ordinary user-installed tools seldom combine
+const SUSPICIOUS_IMPORTS: &[&str] = &[
+    "ptrace",
+    "prctl",
+    "memfd_create",
+    "fexecve",
+    "execve",
+    "setuid",
+    "setgid",
+    "inotify_add_watch",
+    "kill",
+    "connect",
+];
+
+static PIPE_TO_SHELL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(curl|wget)\b[^\n|;]*\|\s*(sudo\s+)?(ba|da|z)?sh\b").expect("valid regex")
+});
+
+static BASE64_RUN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"[A-Za-z0-9+/]{{{},}}={{0,2}}", BASE64_BLOB_MIN)).expect("valid regex")
+});
+
+#[derive(Debug, Clone)]
+pub struct HeuristicFinding {
+    pub name: String,
+    pub category: ThreatCategory,
+    pub confidence: f32,
+    pub offset: u64,
+    pub description: String,
+}
+
+impl HeuristicFinding {
+    pub fn to_signature(&self) -> ThreatSignature {
+        ThreatSignature {
+            name: self.name.clone(),
+            pattern: Vec::new(),
+            category: self.category.clone(),
+            severity: Severity::Warning,
+        }
+    }
+}
+
+// Inspects the leading sample of a file; every finding carries its own confidence
+pub fn analyze(path: &Path, sample: &[u8], filesize: u64, elf: Option<&ElfInfo>) -> Vec<HeuristicFinding> {
+    let mut findings = Vec::new();
+
+    if let Some(finding) = double_extension(path) {
+        findings.push(finding);
+    }
+
+    if let Some(elf) = elf {
+        if let Some(finding) = high_entropy(sample, filesize, elf) {
+            findings.push(finding);
+        }
+        // Plenty of legitimate tools ship packed, so a packer alone stays below the default threshold
+        if let Some(packer) = &elf.packer {
+            findings.push(HeuristicFinding {
+                name: format!("Heuristic.Packed.{}", packer),
+                category: ThreatCategory::Unknown,
+                confidence: 0.45,
+                offset: 0,
+                description: format!("Executable is compressed with the {} packer", packer),
+            });
+        }
+        if let Some(finding) = writable_location_elf(path, elf) {
+            findings.push(finding);
+        }
+    } else if is_text(sample) {
+        let blob = base64_blob(sample);
+        if let Some(captures) = PIPE_TO_SHELL.captures(sample) {
+            let m = captures.get(0).expect("whole match");
+            // Installers commonly pipe to sh, so this alone stays below the default threshold
+            let mut confidence = 0.45;
+            if captures.get(2).is_some() {
+                confidence += 0.2;
+            }
+            if blob.is_some() {
+                confidence += 0.2;
+            }
+            findings.push(HeuristicFinding {
+                name: "Heuristic.Script.PipeToShell".to_string(),
+                category: ThreatCategory::PUP,
+                confidence,
+                offset: m.start() as u64,
+                description: format!(
+                    "Downloads and runs a script: {}",
+                    String::from_utf8_lossy(m.as_bytes())
+                ),
+            });
+        }
+        findings.extend(blob);
+    }
+
+    findings
+}
+
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn is_text(sample: &[u8]) -> bool {
+    let head = &sample[..sample.len().min(8192)];
+    !head.is_empty() && !head.contains(&0)
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+fn double_extension(path: &Path) -> Option<HeuristicFinding> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let mut parts = name.rsplit('.');
+    let last = parts.next()?;
+    let decoy = parts.next()?;
+    // "name.pdf.sh" needs a stem before the decoy extension
+    parts.next().filter(|stem| !stem.is_empty())?;
+
+    if !EXECUTABLE_EXTENSIONS.contains(&last) || !DECOY_EXTENSIONS.contains(&decoy) {
+        return None;
+    }
+
+    Some(HeuristicFinding {
+        name: "Heuristic.DoubleExtension".to_string(),
+        category: ThreatCategory::Unknown,
+        confidence: if is_executable(path) { 0.8 } else { 0.65 },
+        offset: 0,
+        description: format!("Executable disguised as .{} file", decoy),
+    })
+}
+
+fn high_entropy(sample: &[u8], filesize: u64, elf: &ElfInfo) -> Option<HeuristicFinding> {
+    // Tiny binaries do not give a meaningful distribution
+    if filesize < ENTROPY_BLOCK as u64 / 4 {
+        return None;
+    }
+
+    // Judge by loaded section where the headers survived, by fixed blocks otherwise.
+    // Non-loaded sections such as .gnu_debugdata are legitimately compressed.
+    let densest_section = elf
+        .sections
+        .iter()
+        .filter(|section| section.flags & SHF_ALLOC != 0)
+        .filter_map(|section| section.entropy.map(|entropy| (section, entropy)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    let (offset, entropy, region) = match densest_section {
+        Some((section, entropy)) => (section.offset, entropy, format!("section {}", section.name)),
+        None => sample
+            .chunks(ENTROPY_BLOCK)
+            .enumerate()
+            .filter(|(_, block)| block.len() >= ENTROPY_BLOCK / 4)
+            .map(|(i, block)| ((i * ENTROPY_BLOCK) as u64, shannon_entropy(block), "data".to_string()))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?,
+    };
+
+    if entropy < PACKED_ENTROPY {
+        return None;
+    }
+
+    Some(HeuristicFinding {
+        name: "Heuristic.Packed.HighEntropy".to_string(),
+        category: ThreatCategory::Unknown,
+        // 7.2 bits maps to 0.5, a perfectly random block to 0.9
+        confidence: (0.5 + (entropy - PACKED_ENTROPY) / (8.0 - PACKED_ENTROPY) * 0.4) as f32,
+        offset,
+        description: format!("Executable {} with {:.2} bits/byte entropy suggests packing or encryption", region, entropy),
+    })
+}
+
+fn base64_blob(sample: &[u8]) -> Option<HeuristicFinding> {
+    let m = BASE64_RUN.find(sample)?;
+    let blob = m.as_bytes();
+
+    // Encoded executables keep a recognisable prefix: "\x7fELF" -> "f0VMR", "MZ" -> "TV"
+    let (payload, confidence) = if blob.starts_with(b"f0VMR") {
+        ("an ELF executable", 0.9)
+    } else if blob.starts_with(b"TVqQ") || blob.starts_with(b"TVoA") {
+        ("a Windows executable", 0.85)
+    } else {
+        ("data", 0.45 + (blob.len() as f32 / (256.0 * 1024.0)).min(0.2))
+    };
+
+    Some(HeuristicFinding {
+        name: "Heuristic.Base64Payload".to_string(),
+        category: ThreatCategory::PUP,
+        confidence,
+        offset: m.start() as u64,
+        description: format!("{} byte base64 blob encoding {}", blob.len(), payload),
+    })
+}
+
+fn writable_location_elf(path: &Path, elf: &ElfInfo) -> Option<HeuristicFinding> {
+    let location = path.to_string_lossy();
+    let in_user_area = dirs::home_dir()
+        .and_then(|home| path.strip_prefix(home).ok().map(|rel| rel.to_path_buf()))
+        .map(|rel| {
+            rel.components()
+                .next()
+                .map(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+                .unwrap_or(false)
+                || rel.starts_with("Downloads")
+        })
+        .unwrap_or(false);
+
+    if !in_user_area && !WRITABLE_LOCATIONS.iter().any(|dir| location.starts_with(dir)) {
+        return None;
+    }
+
+    let suspicious: Vec<&str> = SUSPICIOUS_IMPORTS
+        .iter()
+        .copied()
+        .filter(|name| elf.imports_symbol(name))
+        .collect();
+    if suspicious.is_empty() {
+        return None;
+    }
+
+    // Static, stripped droppers are a common shape for miners and bots
+    let hardened = if elf.is_static && elf.stripped { 0.1 } else { 0.0 };
+
+    Some(HeuristicFinding {
+        name: "Heuristic.ELF.WritableLocation".to_string(),
+        category: ThreatCategory::Unknown,
+        confidence: (0.4 + 0.15 * suspicious.len() as f32 + hardened).min(0.95),
+        offset: 0,
+        description: format!("Executable in a user-writable location imports {}", suspicious.join(", ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::elf::ElfSection;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const DEFAULT_THRESHOLD: f32 = 0.6;
+
+    fn elf_info(sections: Vec<ElfSection>, imports: &[&str]) -> ElfInfo {
+        let mut imports: Vec<String> = imports.iter().map(|name| name.to_string()).collect();
+        imports.sort();
+        ElfInfo {
+            is_64: true,
+            big_endian: false,
+            file_type: 2,
+            machine: 62,
+            entry: 0,
+            segments: Vec::new(),
+            sections,
+            interpreter: None,
+            needed: Vec::new(),
+            imports,
+            rpath: None,
+            runpath: None,
+            is_static: true,
+            stripped: true,
+            packer: None,
+        }
+    }
+
+    fn section(name: &str, flags: u64, entropy: f64) -> ElfSection {
+        ElfSection {
+            name: name.to_string(),
+            kind: 1,
+            flags,
+            offset: 0x1000,
+            size: 0x10000,
+            entropy: Some(entropy),
+        }
+    }
+
+    fn names(findings: &[HeuristicFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    fn pipe_confidence(script: &[u8]) -> f32 {
+        analyze(Path::new("/tmp/install.sh"), script, script.len() as u64, None)
+            .into_iter()
+            .find(|f| f.name == "Heuristic.Script.PipeToShell")
+            .map(|f| f.confidence)
+            .unwrap_or(0.0)
+    }
+
+    #[test]
+    fn entropy_bounds() {
+        assert_eq!(shannon_entropy(b""), 0.0);
+        assert_eq!(shannon_entropy(&[7u8; 1000]), 0.0);
+        let uniform: Vec<u8> = (0..=255u8).cycle().take(256 * 64).collect();
+        assert!((shannon_entropy(&uniform) - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pipe_to_shell_alone_stays_below_default_threshold() {
+        let plain = b"#!/bin/sh\ncurl -fsSL https://example.com/install.sh | sh\n";
+        let with_sudo = b"wget -qO- http://example.com/x | sudo bash\n";
+        assert!(pipe_confidence(plain) > 0.0);
+        assert!(pipe_confidence(plain) < DEFAULT_THRESHOLD);
+        assert!(pipe_confidence(with_sudo) >= DEFAULT_THRESHOLD);
+
+        let mut with_payload = plain.to_vec();
+        with_payload.extend(std::iter::repeat_n(b'A', BASE64_BLOB_MIN));
+        assert!(pipe_confidence(&with_payload) >= DEFAULT_THRESHOLD);
+
+        // A mention without a pipe or into another program is not a download-and-run
+        assert_eq!(pipe_confidence(b"curl https://example.com > file\n"), 0.0);
+        assert_eq!(pipe_confidence(b"curl https://example.com | shellcheck -\n"), 0.0);
+    }
+
+    #[test]
+    fn binary_samples_skip_script_checks() {
+        let sample = b"curl http://x | sh\0\0";
+        assert!(analyze(Path::new("/tmp/blob"), sample, sample.len() as u64, None).is_empty());
+        assert!(analyze(Path::new("/tmp/empty"), b"", 0, None).is_empty());
+    }
+
+    #[test]
+    fn base64_payload_identifies_encoded_executables() {
+        let blob = |prefix: &str| {
+            let mut data = prefix.as_bytes().to_vec();
+            data.resize(BASE64_BLOB_MIN + 10, b'Q');
+            data
+        };
+        let elf = base64_blob(&blob("f0VMRgIBAQ")).unwrap();
+        assert_eq!(elf.confidence, 0.9);
+        assert!(base64_blob(&blob("TVqQAAMAAAAEAAAA")).unwrap().description.contains("Windows"));
+        assert!(base64_blob(&blob("")).unwrap().confidence < DEFAULT_THRESHOLD);
+
+        let short = vec![b'Q'; BASE64_BLOB_MIN - 1];
+        assert!(base64_blob(&short).is_none());
+    }
+
+    #[test]
+    fn double_extension_needs_stem_and_known_extensions() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("invoice.pdf.sh");
+        fs::write(&path, "echo").unwrap();
+        let finding = double_extension(&path).unwrap();
+        assert_eq!(finding.confidence, 0.65);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(double_extension(&path).unwrap().confidence, 0.8);
+
+        for name in [".pdf.sh", "invoice.sh", "invoice.pdf", "archive.tar.gz", "report.PDF.tar"] {
+            assert!(double_extension(Path::new(name)).is_none(), "{}", name);
+        }
+        assert!(double_extension(Path::new("Invoice.PDF.EXE")).is_some());
+    }
+
+    #[test]
+    fn high_entropy_judges_loaded_sections_only() {
+        let sample = vec![0u8; ENTROPY_BLOCK];
+        let packed = elf_info(vec![section(".text", SHF_ALLOC, 7.9)], &[]);
+        let finding = high_entropy(&sample, sample.len() as u64, &packed).unwrap();
+        assert_eq!(finding.offset, 0x1000);
+        assert!(finding.confidence > 0.8);
+
+        // Compressed debug data is not loaded and not suspicious
+        let debug = elf_info(vec![section(".gnu_debugdata", 0, 7.9), section(".text", SHF_ALLOC, 6.0)], &[]);
+        assert!(high_entropy(&sample, sample.len() as u64, &debug).is_none());
+
+        // Too small to judge
+        assert!(high_entropy(&sample, 100, &packed).is_none());
+
+        // Without usable sections the raw blocks are measured
+        let random: Vec<u8> = (0..ENTROPY_BLOCK).map(|i| (i * 7919 % 251) as u8 ^ (i >> 8) as u8).collect();
+        let headerless = elf_info(Vec::new(), &[]);
+        assert!(high_entropy(&random, random.len() as u64, &headerless).is_some());
+        assert!(high_entropy(&sample, sample.len() as u64, &headerless).is_none());
+    }
+
+    #[test]
+    fn writable_location_elf_needs_suspicious_imports() {
+        let dropper = elf_info(Vec::new(), &["ptrace", "memfd_create", "connect"]);
+        let finding = writable_location_elf(Path::new("/dev/shm/x"), &dropper).unwrap();
+        assert!((finding.confidence - 0.95).abs() < 1e-6);
+
+        let harmless = elf_info(Vec::new(), &["printf"]);
+        assert!(writable_location_elf(Path::new("/tmp/x"), &harmless).is_none());
+        assert!(writable_location_elf(Path::new("/usr/bin/x"), &dropper).is_none());
+    }
+
+    #[test]
+    fn packer_is_reported_for_elf_samples() {
+        let mut upx = elf_info(Vec::new(), &[]);
+        upx.packer = Some("UPX".to_string());
+        let findings = analyze(Path::new("/opt/tool"), b"\x7fELF", 4, Some(&upx));
+        assert_eq!(names(&findings), vec!["Heuristic.Packed.UPX"]);
+    }
+
+    #[test]
+    fn clean_packed_binary_alone_stays_below_default_threshold() {
+        let mut upx = elf_info(vec![section(".text", SHF_ALLOC, 6.0)], &["printf"]);
+        upx.packer = Some("UPX".to_string());
+        let sample = vec![0u8; ENTROPY_BLOCK];
+        let findings = analyze(Path::new("/usr/local/bin/tool"), &sample, sample.len() as u64, Some(&upx));
+
+        assert_eq!(names(&findings), vec!["Heuristic.Packed.UPX"]);
+        assert!(findings.iter().all(|f| f.confidence < DEFAULT_THRESHOLD));
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use super::scancache::path_keys;
+use super::{AuditItem, AuditStatus, Engine, ScanExclusions, Severity};
+
+// ==================== DOSYA BÜTÜNLÜĞÜ ====================
+//
+// A snapshot of hash, permissions, owner and modification time for every file under the
+// configured roots. Later snapshots are compared against it; intentional changes are
+// folded back into the baseline.
+
+const FILE_TYPE_BITS: u32 = 0o170000;
+const PERMISSION_BITS: u32 = 0o7777;
+const SETID_BITS: u32 = 0o6000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityConfig {
+    pub paths: Vec<PathBuf>,
+    // Files rewritten by the system on its own
+    pub excluded_paths: Vec<PathBuf>,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        IntegrityConfig {
+            paths: vec![PathBuf::from("/usr/bin"), PathBuf::from("/usr/sbin"), PathBuf::from("/etc")],
+            excluded_paths: vec![
+                PathBuf::from("/etc/ld.so.cache"),
+                PathBuf::from("/etc/adjtime"),
+                PathBuf::from("/etc/resolv.conf"),
+                PathBuf::from("/etc/mtab"),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    // None for symlinks and files we may not read
+    pub sha256: Option<String>,
+    pub link_target: Option<PathBuf>,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub mtime: i64,
+}
+
+impl FileRecord {
+    // None once the file is gone
+    pub fn capture(path: &Path) -> Option<Self> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        let file_type = metadata.file_type();
+        Some(FileRecord {
+            sha256: if file_type.is_file() { Engine::calculate_file_hash(path).ok() } else { None },
+            // Lossy so an odd target cannot keep the baseline from being saved as JSON
+            link_target: if file_type.is_symlink() {
+                fs::read_link(path).ok().map(|target| PathBuf::from(target.to_string_lossy().into_owned()))
+            } else {
+                None
+            },
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            size: metadata.size(),
+            mtime: metadata.mtime(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityBaseline {
+    pub created: DateTime<Local>,
+    pub updated: DateTime<Local>,
+    pub roots: Vec<PathBuf>,
+    pub excluded_paths: Vec<PathBuf>,
+    #[serde(with = "path_keys")]
+    pub files: BTreeMap<PathBuf, FileRecord>,
+}
+
+impl IntegrityBaseline {
+    pub fn build(roots: &[PathBuf], excluded_paths: &[PathBuf]) -> Self {
+        let now = Local::now();
+        IntegrityBaseline {
+            created: now,
+            updated: now,
+            roots: roots.to_vec(),
+            excluded_paths: excluded_paths.to_vec(),
+            files: snapshot(roots, excluded_paths),
+        }
+    }
+
+    // None when no baseline has been built yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn diff(&self) -> Vec<IntegrityChange> {
+        let current = snapshot(&self.roots, &self.excluded_paths);
+        let mut changes = Vec::new();
+
+        for (path, before) in &self.files {
+            match current.get(path) {
+                None => changes.push(IntegrityChange {
+                    path: path.clone(),
+                    kind: ChangeKind::Removed,
+                    details: Vec::new(),
+                    record: before.clone(),
+                }),
+                Some(after) => {
+                    let details = differences(before, after);
+                    if !details.is_empty() {
+                        changes.push(IntegrityChange {
+                            path: path.clone(),
+                            kind: ChangeKind::Modified,
+                            details,
+                            record: after.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for (path, after) in current {
+            if !self.files.contains_key(&path) {
+                changes.push(IntegrityChange {
+                    path,
+                    kind: ChangeKind::Added,
+                    details: Vec::new(),
+                    record: after,
+                });
+            }
+        }
+
+        changes
+    }
+
+    // Accepts the current state of the given files, including their removal
+    pub fn refresh(&mut self, paths: &[PathBuf]) {
+        for path in paths {
+            match FileRecord::capture(path) {
+                Some(record) => {
+                    self.files.insert(path.clone(), record);
+                }
+                None => {
+                    self.files.remove(path);
+                }
+            }
+        }
+        self.updated = Local::now();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityChange {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    // What changed, e.g. "content" or "permissions 755 -> 4755"
+    pub details: Vec<String>,
+    // The file as it is now, or as it was for removed files
+    pub record: FileRecord,
+}
+
+impl IntegrityChange {
+    // Rewritten executables and newly set-id files are what a rootkit leaves behind
+    pub fn severity(&self) -> Severity {
+        let record = &self.record;
+        let executable = record.mode & 0o111 != 0;
+        let content_changed = self.details.iter().any(|detail| detail == "content");
+        let became_setid = record.mode & SETID_BITS != 0
+            && (self.kind == ChangeKind::Added || self.details.iter().any(|detail| detail.starts_with("permissions")));
+        if (executable && content_changed) || became_setid {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        }
+    }
+
+    pub fn to_audit_item(&self) -> AuditItem {
+        let (title, status, recommendation) = match self.kind {
+            ChangeKind::Modified => (
+                "File modified since baseline",
+                AuditStatus::Fail,
+                "If the change was intentional, update the integrity baseline; otherwise reinstall the package that owns the file",
+            ),
+            ChangeKind::Removed => (
+                "File removed since baseline",
+                AuditStatus::Fail,
+                "If the removal was intentional, update the integrity baseline; otherwise restore the file from its package",
+            ),
+            ChangeKind::Added => (
+                "File added since baseline",
+                AuditStatus::Warning,
+                "If the file was installed intentionally, update the integrity baseline; otherwise investigate and remove it",
+            ),
+        };
+        let description = if self.details.is_empty() {
+            self.path.display().to_string()
+        } else {
+            format!("{}: {}", self.path.display(), self.details.join(", "))
+        };
+
+        AuditItem {
+            id: format!("integrity:{}", self.path.display()),
+            title: title.to_string(),
+            description,
+            status,
+            severity: self.severity(),
+            recommendation: recommendation.to_string(),
+        }
+    }
+}
+
+fn snapshot(roots: &[PathBuf], excluded_paths: &[PathBuf]) -> BTreeMap<PathBuf, FileRecord> {
+    let exclusions = ScanExclusions::new(&[], excluded_paths);
+    let mut paths = Vec::new();
+    for root in roots {
+        for entry in WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|entry| !exclusions.is_excluded(entry.path(), entry.file_type().is_dir()))
+            .filter_map(|entry| entry.ok())
+        {
+            if !entry.file_type().is_dir() {
+                paths.push(entry.into_path());
+            }
+        }
+    }
+
+    paths
+        .into_par_iter()
+        .filter_map(|path| FileRecord::capture(&path).map(|record| (path, record)))
+        .collect()
+}
+
+fn differences(before: &FileRecord, after: &FileRecord) -> Vec<String> {
+    let mut details = Vec::new();
+    // Files we could not read then or now are compared by metadata alone
+    let content_changed = match (&before.sha256, &after.sha256) {
+        (Some(old), Some(new)) => old != new,
+        _ => before.link_target != after.link_target,
+    };
+    if content_changed {
+        details.push("content".to_string());
+    }
+    if before.mode & FILE_TYPE_BITS != after.mode & FILE_TYPE_BITS {
+        details.push("file type".to_string());
+    }
+    if before.mode & PERMISSION_BITS != after.mode & PERMISSION_BITS {
+        details.push(format!("permissions {:o} -> {:o}", before.mode & PERMISSION_BITS, after.mode & PERMISSION_BITS));
+    }
+    if (before.uid, before.gid) != (after.uid, after.gid) {
+        details.push(format!("owner {}:{} -> {}:{}", before.uid, before.gid, after.uid, after.gid));
+    }
+    if before.mtime != after.mtime {
+        details.push("modification time".to_string());
+    }
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use tempfile::TempDir;
+
+    fn tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("bin")).unwrap();
+        fs::write(dir.path().join("bin/tool"), b"#!/bin/sh\necho tool\n").unwrap();
+        fs::set_permissions(dir.path().join("bin/tool"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.path().join("config"), b"key=value\n").unwrap();
+        fs::write(dir.path().join("cache"), b"volatile").unwrap();
+        symlink("bin/tool", dir.path().join("link")).unwrap();
+        dir
+    }
+
+    fn baseline(dir: &TempDir) -> IntegrityBaseline {
+        IntegrityBaseline::build(&[dir.path().to_path_buf()], &[dir.path().join("cache")])
+    }
+
+    fn change<'a>(changes: &'a [IntegrityChange], path: &Path) -> &'a IntegrityChange {
+        changes.iter().find(|change| change.path == path).unwrap()
+    }
+
+    #[test]
+    fn records_files_below_the_roots() {
+        let dir = tree();
+        let baseline = baseline(&dir);
+        assert_eq!(baseline.files.len(), 3);
+        let tool = &baseline.files[&dir.path().join("bin/tool")];
+        assert!(tool.sha256.is_some());
+        assert_eq!(tool.mode & PERMISSION_BITS, 0o755);
+        let link = &baseline.files[&dir.path().join("link")];
+        assert_eq!(link.link_target, Some(PathBuf::from("bin/tool")));
+        assert!(link.sha256.is_none());
+        assert!(!baseline.files.contains_key(&dir.path().join("cache")));
+        assert!(baseline.diff().is_empty());
+    }
+
+    #[test]
+    fn reports_added_removed_and_modified_files() {
+        let dir = tree();
+        let baseline = baseline(&dir);
+
+        fs::write(dir.path().join("bin/tool"), b"#!/bin/sh\nevil\n").unwrap();
+        fs::remove_file(dir.path().join("config")).unwrap();
+        fs::write(dir.path().join("bin/dropped"), b"new").unwrap();
+        fs::remove_file(dir.path().join("link")).unwrap();
+        symlink("/tmp/evil", dir.path().join("link")).unwrap();
+        fs::write(dir.path().join("cache"), b"changed").unwrap();
+
+        let changes = baseline.diff();
+        assert_eq!(changes.len(), 4);
+        let tool = change(&changes, &dir.path().join("bin/tool"));
+        assert_eq!(tool.kind, ChangeKind::Modified);
+        assert!(tool.details.contains(&"content".to_string()));
+        assert_eq!(tool.severity(), Severity::Critical);
+        let config = change(&changes, &dir.path().join("config"));
+        assert_eq!(config.kind, ChangeKind::Removed);
+        assert_eq!(config.severity(), Severity::Warning);
+        assert_eq!(change(&changes, &dir.path().join("bin/dropped")).kind, ChangeKind::Added);
+        assert!(change(&changes, &dir.path().join("link")).details.contains(&"content".to_string()));
+    }
+
+    #[test]
+    fn reports_permission_changes() {
+        let dir = tree();
+        let baseline = baseline(&dir);
+        let config = dir.path().join("config");
+        fs::set_permissions(&config, fs::Permissions::from_mode(0o4755)).unwrap();
+
+        let changes = baseline.diff();
+        let change = change(&changes, &config);
+        assert_eq!(change.details, vec!["permissions 644 -> 4755".to_string()]);
+        // Newly set-id is what a rootkit leaves behind
+        assert_eq!(change.severity(), Severity::Critical);
+
+        let item = change.to_audit_item();
+        assert_eq!(item.id, format!("integrity:{}", config.display()));
+        assert_eq!(item.status, AuditStatus::Fail);
+        assert!(item.description.ends_with("permissions 644 -> 4755"));
+    }
+
+    #[test]
+    fn accepts_intentional_changes() {
+        let dir = tree();
+        let mut baseline = baseline(&dir);
+        fs::write(dir.path().join("config"), b"key=other\n").unwrap();
+        fs::remove_file(dir.path().join("link")).unwrap();
+        fs::write(dir.path().join("new"), b"new").unwrap();
+        assert_eq!(baseline.diff().len(), 3);
+
+        baseline.refresh(&[dir.path().join("config"), dir.path().join("link")]);
+        let changes = baseline.diff();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, dir.path().join("new"));
+        assert!(!baseline.files.contains_key(&dir.path().join("link")));
+    }
+
+    #[test]
+    fn round_trips_non_utf8_paths() {
+        let dir = tree();
+        let odd = dir.path().join(OsStr::from_bytes(b"caf\xe9"));
+        fs::write(&odd, b"latin-1 name").unwrap();
+        let baseline = baseline(&dir);
+        assert!(baseline.files.contains_key(&odd));
+
+        let state = TempDir::new().unwrap();
+        let path = state.path().join("state/baseline.json");
+        baseline.save(&path).unwrap();
+        let loaded = IntegrityBaseline::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.files, baseline.files);
+        assert!(loaded.diff().is_empty());
+    }
+
+    #[test]
+    fn loads_nothing_or_fails_on_damage() {
+        let dir = TempDir::new().unwrap();
+        assert!(IntegrityBaseline::load(&dir.path().join("missing.json")).unwrap().is_none());
+        let damaged = dir.path().join("damaged.json");
+        fs::write(&damaged, b"{\"files\": {").unwrap();
+        assert!(IntegrityBaseline::load(&damaged).is_err());
+        // Keys are hex-encoded path bytes
+        fs::write(&damaged, br#"{"created":"2024-01-01T00:00:00+00:00","updated":"2024-01-01T00:00:00+00:00","roots":[],"excluded_paths":[],"files":{"/etc/passwd":{"sha256":null,"link_target":null,"mode":0,"uid":0,"gid":0,"size":0,"mtime":0}}}"#).unwrap();
+        assert!(IntegrityBaseline::load(&damaged).is_err());
+    }
+}
@@ -0,0 +1,537 @@
+use std::io::{self, BufRead};
+
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine as _;
+
+// ==================== E-POSTA AYRIŞTIRMA ====================
+
+// Multipart bodies nested deeper than this are not followed
+const MAX_MIME_DEPTH: usize = 8;
+const MAX_HEADER_LINES: usize = 256;
+
+// Mail clients wrap, pad and truncate base64 loosely
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+// An mbox starts with a "From " separator line followed by headers
+pub fn is_mbox(head: &[u8]) -> bool {
+    let Some(rest) = head.strip_prefix(b"From ") else {
+        return false;
+    };
+    let mut lines = rest.split(|&b| b == b'\n').skip(1);
+    lines.next().map(is_header_line).unwrap_or(false)
+}
+
+// A single RFC 822 message, as stored in maildir or saved as .eml; only MIME mail can carry attachments
+pub fn is_message(head: &[u8]) -> bool {
+    let mut mime = false;
+    for (i, line) in head.split(|&b| b == b'\n').take(MAX_HEADER_LINES).enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            return i > 0 && mime;
+        }
+        if line[0] == b' ' || line[0] == b'\t' {
+            if i == 0 {
+                return false;
+            }
+            continue;
+        }
+        if !is_header_line(line) {
+            return false;
+        }
+        let lower = line.to_ascii_lowercase();
+        mime |= lower.starts_with(b"mime-version:") || lower.starts_with(b"content-type:");
+    }
+    false
+}
+
+fn is_header_line(line: &[u8]) -> bool {
+    match line.iter().position(|&b| b == b':') {
+        Some(colon) => colon > 0 && line[..colon].iter().all(|&b| b.is_ascii_graphic()),
+        None => false,
+    }
+}
+
+// Line without its "\n" or "\r\n" ending
+fn trim_line(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+// Yields raw messages one at a time, either every message of an mbox or a single message file
+pub struct Mailbox<R: BufRead> {
+    reader: R,
+    mbox: bool,
+    max_message: u64,
+    pending: Option<Vec<u8>>,
+    done: bool,
+}
+
+impl<R: BufRead> Mailbox<R> {
+    pub fn new(reader: R, mbox: bool, max_message: u64) -> Self {
+        Mailbox {
+            reader,
+            mbox,
+            max_message,
+            pending: None,
+            done: false,
+        }
+    }
+
+    // Messages past max_message are cut short; the rest of their lines are dropped
+    pub fn next_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut message = Vec::new();
+        let mut line = self.pending.take().unwrap_or_default();
+        let mut previous_blank = true;
+        let mut started = false;
+
+        loop {
+            if line.is_empty() && self.reader.read_until(b'\n', &mut line)? == 0 {
+                self.done = true;
+                break;
+            }
+
+            if self.mbox && previous_blank && line.starts_with(b"From ") {
+                if started {
+                    self.pending = Some(line);
+                    break;
+                }
+                // The separator itself is not part of the message
+                started = true;
+                line.clear();
+                continue;
+            }
+            started = true;
+
+            previous_blank = trim_line(&line).is_empty();
+            if (message.len() as u64) < self.max_message {
+                message.extend_from_slice(&line);
+            }
+            line.clear();
+        }
+
+        Ok((!message.is_empty()).then_some(message))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+pub struct ParsedMessage {
+    // Message-ID without angle brackets, when the message has one
+    pub message_id: Option<String>,
+    pub attachments: Vec<Attachment>,
+}
+
+pub fn parse_message(raw: &[u8]) -> ParsedMessage {
+    let (headers, body) = split_headers(raw);
+    let message_id = header(&headers, "message-id")
+        .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+        .filter(|id| !id.is_empty());
+
+    let mut attachments = Vec::new();
+    collect_parts(&headers, body, 0, &mut attachments);
+
+    ParsedMessage {
+        message_id,
+        attachments,
+    }
+}
+
+// Unfolded headers with lowercase names, and the body that follows the blank line
+fn split_headers(raw: &[u8]) -> (Vec<(String, String)>, &[u8]) {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut position = 0;
+
+    while position < raw.len() {
+        let end = memchr::memchr(b'\n', &raw[position..]).map_or(raw.len(), |i| position + i + 1);
+        let line = trim_line(&raw[position..end]);
+        position = end;
+
+        if line.is_empty() {
+            break;
+        }
+        let text = String::from_utf8_lossy(line);
+        if line[0] == b' ' || line[0] == b'\t' {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(text.trim());
+            }
+        } else if let Some((name, value)) = text.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    (headers, &raw[position..])
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+}
+
+// "text/plain; charset=utf-8; name=\"a.txt\"" -> ("text/plain", [("charset", "utf-8"), ("name", "a.txt")])
+fn parse_parameters(value: &str) -> (String, Vec<(String, String)>) {
+    let mut parts = value.split(';');
+    let main = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let parameters = parts
+        .filter_map(|part| {
+            let (key, value) = part.split_once('=')?;
+            Some((key.trim().to_ascii_lowercase(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect();
+    (main, parameters)
+}
+
+fn parameter<'a>(parameters: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    parameters.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+}
+
+fn collect_parts(headers: &[(String, String)], body: &[u8], depth: usize, attachments: &mut Vec<Attachment>) {
+    if depth > MAX_MIME_DEPTH {
+        return;
+    }
+
+    let (content_type, type_parameters) = parse_parameters(header(headers, "content-type").unwrap_or("text/plain"));
+    if content_type.starts_with("multipart/") {
+        if let Some(boundary) = parameter(&type_parameters, "boundary") {
+            for part in split_multipart(body, boundary.as_bytes()) {
+                let (headers, body) = split_headers(part);
+                collect_parts(&headers, body, depth + 1, attachments);
+            }
+        }
+        return;
+    }
+
+    let (disposition, disposition_parameters) = parse_parameters(header(headers, "content-disposition").unwrap_or(""));
+    let name = parameter(&disposition_parameters, "filename")
+        .or_else(|| parameter(&type_parameters, "name"))
+        .map(decode_encoded_words)
+        .or_else(|| {
+            parameter(&disposition_parameters, "filename*")
+                .or_else(|| parameter(&type_parameters, "name*"))
+                .map(decode_extended_value)
+        })
+        // Names end up inside "message!name" paths, so they cannot carry separators
+        .map(|name| name.replace(['/', '\\', '!'], "_").trim().to_string())
+        .filter(|name| !name.is_empty());
+
+    // Message text is already covered by scanning the mailbox itself; only attachments are unpacked
+    if name.is_none() && disposition != "attachment" && content_type.starts_with("text/") {
+        return;
+    }
+
+    let encoding = header(headers, "content-transfer-encoding").unwrap_or("").trim().to_ascii_lowercase();
+    let data = match encoding.as_str() {
+        "base64" => decode_base64(body),
+        "quoted-printable" => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    };
+
+    let name = name.unwrap_or_else(|| match content_type.as_str() {
+        "message/rfc822" => "message.eml".to_string(),
+        _ => format!("part-{}", attachments.len() + 1),
+    });
+    attachments.push(Attachment { name, data });
+}
+
+fn split_multipart<'a>(body: &'a [u8], boundary: &[u8]) -> Vec<&'a [u8]> {
+    let mut delimiter = b"--".to_vec();
+    delimiter.extend_from_slice(boundary);
+
+    let mut parts = Vec::new();
+    let mut current: Option<usize> = None;
+    let mut position = 0;
+
+    while position < body.len() {
+        let end = memchr::memchr(b'\n', &body[position..]).map_or(body.len(), |i| position + i + 1);
+        let line = trim_line(&body[position..end]);
+
+        if line.starts_with(&delimiter) {
+            if let Some(start) = current {
+                // The line break before a delimiter belongs to the delimiter
+                let part = &body[start..position];
+                let part = part.strip_suffix(b"\n").unwrap_or(part);
+                parts.push(part.strip_suffix(b"\r").unwrap_or(part));
+            }
+            if line[delimiter.len()..].starts_with(b"--") {
+                return parts;
+            }
+            current = Some(end);
+        }
+        position = end;
+    }
+
+    // An unterminated last part still counts
+    if let Some(start) = current {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+fn decode_base64(body: &[u8]) -> Vec<u8> {
+    let mut encoded: Vec<u8> = body
+        .iter()
+        .copied()
+        .take_while(|&b| b != b'=')
+        .filter(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/'))
+        .collect();
+    // A lone trailing character cannot encode a byte
+    if encoded.len() % 4 == 1 {
+        encoded.pop();
+    }
+    LENIENT_BASE64.decode(&encoded).unwrap_or_default()
+}
+
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut i = 0;
+
+    while i < body.len() {
+        if body[i] == b'=' {
+            // Soft line break
+            if body[i + 1..].starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if body[i + 1..].starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            if let Some(byte) = body.get(i + 1..i + 3).and_then(hex_byte) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(body[i]);
+        i += 1;
+    }
+
+    decoded
+}
+
+fn hex_byte(digits: &[u8]) -> Option<u8> {
+    if !digits.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()
+}
+
+// RFC 2047 encoded words: "=?utf-8?B?aW52b2ljZS5wZGY=?="
+fn decode_encoded_words(value: &str) -> String {
+    let mut name = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("=?") {
+        let mut fields = rest[start + 2..].splitn(3, '?');
+        let (Some(_charset), Some(encoding), Some(tail)) = (fields.next(), fields.next(), fields.next()) else {
+            break;
+        };
+        let Some(end) = tail.find("?=") else {
+            break;
+        };
+
+        name.push_str(&rest[..start]);
+        let text = &tail[..end];
+        let bytes = match encoding.to_ascii_uppercase().as_str() {
+            "B" => LENIENT_BASE64.decode(text.trim_end_matches('=')).unwrap_or_default(),
+            _ => decode_quoted_printable(text.replace('_', " ").as_bytes()),
+        };
+        name.push_str(&String::from_utf8_lossy(&bytes));
+        rest = tail[end + 2..].trim_start();
+    }
+
+    name.push_str(rest);
+    name
+}
+
+// RFC 2231 extended values: "utf-8''invoice%20march.pdf"
+fn decode_extended_value(value: &str) -> String {
+    let encoded = value.splitn(3, '\'').nth(2).unwrap_or(value).as_bytes();
+    let mut bytes = Vec::new();
+    let mut i = 0;
+
+    while i < encoded.len() {
+        let escaped = (encoded[i] == b'%').then(|| encoded.get(i + 1..i + 3).and_then(hex_byte)).flatten();
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            None => {
+                bytes.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPART: &str = "From: a@example.com\r\n\
+Message-ID: <abc@example.com>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed;\r\n boundary=\"XYZ\"\r\n\
+\r\n\
+preamble\r\n\
+--XYZ\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Hello\r\n\
+--XYZ\r\n\
+Content-Type: application/octet-stream; name=\"ignored.bin\"\r\n\
+Content-Disposition: attachment; filename=\"=?utf-8?B?aW52b2ljZS5wZGY=?=\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+ZXZp\r\nbC1t\r\nYXJr\r\nZXI=\r\n\
+--XYZ\r\n\
+Content-Type: text/x-sh\r\n\
+Content-Disposition: attachment; filename*=utf-8''run%20me.sh\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+echo =3D=\r\ndone\r\n\
+--XYZ--\r\n\
+epilogue\r\n";
+
+    fn attachments(raw: &str) -> Vec<(String, Vec<u8>)> {
+        parse_message(raw.as_bytes())
+            .attachments
+            .into_iter()
+            .map(|a| (a.name, a.data))
+            .collect()
+    }
+
+    fn messages(raw: &[u8], mbox: bool, max_message: u64) -> Vec<Vec<u8>> {
+        let mut mailbox = Mailbox::new(raw, mbox, max_message);
+        let mut messages = Vec::new();
+        while let Some(message) = mailbox.next_message().unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[test]
+    fn recognises_mailboxes_and_messages() {
+        assert!(is_mbox(b"From a@b Mon Jan  1 00:00:00 2024\nSubject: x\n"));
+        assert!(!is_mbox(b"From a@b Mon Jan  1 00:00:00 2024\nnot a header\n"));
+        assert!(!is_mbox(b"From: a@b\n"));
+
+        assert!(is_message(MULTIPART.as_bytes()));
+        assert!(is_message(b"Subject: x\r\n folded\r\nContent-Type: text/plain\r\n\r\nbody"));
+        // Plain RFC 822 text cannot carry attachments
+        assert!(!is_message(b"Subject: x\nFrom: a@b\n\nbody"));
+        assert!(!is_message(b" Content-Type: text/plain\n\n"));
+        assert!(!is_message(b"\nContent-Type: text/plain\n"));
+        assert!(!is_message(b"Content-Type: text/plain\nno colon here\n\n"));
+        assert!(!is_message(b"Content-Type: text/plain"));
+    }
+
+    #[test]
+    fn splits_mbox_messages() {
+        let mbox = b"From a Mon\nSubject: 1\n\nbody one\n>From quoted\nFrom inline is not a separator\n\nFrom b Tue\nSubject: 2\n\nbody two\n";
+        let found = messages(mbox, true, 1 << 20);
+        assert_eq!(found.len(), 2);
+        assert!(found[0].ends_with(b"From inline is not a separator\n\n"));
+        assert_eq!(found[1], b"Subject: 2\n\nbody two\n");
+
+        // A single message file is read whole, "From " lines included
+        assert_eq!(messages(mbox, false, 1 << 20).len(), 1);
+        assert!(messages(b"", true, 1 << 20).is_empty());
+    }
+
+    #[test]
+    fn caps_message_size() {
+        let mbox = b"From a\nSubject: 1\n\nline\nline\nline\n\nFrom b\nSubject: 2\n";
+        let found = messages(mbox, true, 15);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], b"Subject: 1\n\nline\n");
+        assert_eq!(found[1], b"Subject: 2\n");
+    }
+
+    #[test]
+    fn extracts_attachments() {
+        let message = parse_message(MULTIPART.as_bytes());
+        assert_eq!(message.message_id.as_deref(), Some("abc@example.com"));
+        let found: Vec<_> = message.attachments.into_iter().map(|a| (a.name, a.data)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("invoice.pdf".to_string(), b"evil-marker".to_vec()),
+                ("run me.sh".to_string(), b"echo =done".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_unnamed_and_nested_parts() {
+        let raw = "Content-Type: multipart/mixed; boundary=outer\n\n\
+--outer\n\
+Content-Type: multipart/alternative; boundary=inner\n\n\
+--inner\n\
+Content-Type: application/pdf\n\nPDF\n\
+--inner--\n\
+--outer\n\
+Content-Type: message/rfc822\n\nSubject: fwd\n\n\
+--outer\n\
+Content-Type: text/plain; name=\"../../etc/passwd!x\"\n\ntext\n";
+        let found = attachments(raw);
+        let names: Vec<&str> = found.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["part-1", "message.eml", ".._.._etc_passwd_x"]);
+        // The unterminated last part runs to the end
+        assert_eq!(found[2].1, b"text\n");
+        assert_eq!(parse_message(raw.as_bytes()).message_id, None);
+    }
+
+    #[test]
+    fn tolerates_malformed_structure() {
+        // Multipart without a boundary, and an empty message
+        assert!(attachments("Content-Type: multipart/mixed\n\n--x\n\nbody\n").is_empty());
+        assert!(attachments("").is_empty());
+        assert!(attachments("Message-ID: <>\n").is_empty());
+
+        // Nesting past the limit is not followed
+        let mut raw = String::new();
+        for depth in 0..=MAX_MIME_DEPTH + 1 {
+            raw.push_str(&format!("Content-Type: multipart/mixed; boundary=b{}\n\n--b{}\n", depth, depth));
+        }
+        raw.push_str("Content-Type: application/zip\n\nPK\n");
+        assert!(attachments(&raw).is_empty());
+    }
+
+    #[test]
+    fn decodes_sloppy_encodings() {
+        assert_eq!(decode_base64(b"ZXZp bC1t\r\nYXJrZXI"), b"evil-marker");
+        assert_eq!(decode_base64(b"ZXZpbA=garbage"), b"evil");
+        assert_eq!(decode_base64(b"ZXZpbAZ"), b"evil\x06");
+        assert_eq!(decode_base64(b"Z"), b"");
+        assert!(decode_base64(b"!!!!").is_empty());
+
+        assert_eq!(decode_quoted_printable(b"a=3Db=\nc=ZZ="), b"a=bc=ZZ=");
+        assert_eq!(decode_quoted_printable(b"=4"), b"=4");
+
+        assert_eq!(decode_encoded_words("=?utf-8?Q?caf=C3=A9_menu?=.pdf"), "café menu.pdf");
+        assert_eq!(decode_encoded_words("=?utf-8?B?YQ==?= =?utf-8?B?Yg==?="), "ab");
+        assert_eq!(decode_encoded_words("=?broken"), "=?broken");
+        assert_eq!(decode_encoded_words("=?utf-8?B?unterminated"), "=?utf-8?B?unterminated");
+
+        assert_eq!(decode_extended_value("utf-8'en'a%2Fb%zz.txt"), "a/b%zz.txt");
+        assert_eq!(decode_extended_value("plain.txt"), "plain.txt");
+    }
+}
@@ -0,0 +1,475 @@
+use std::io::{self, Read};
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use super::hashes::{FileDigests, HashSignature, HashSignatureSet};
+use super::rules::Rule;
+use super::ThreatSignature;
+
+// ==================== İMZA VERİTABANI ====================
+
+const SCAN_CHUNK_SIZE: usize = 1024 * 1024;
+const MAX_HITS_PER_SIGNATURE: usize = 32;
+const CONTEXT_BEFORE: usize = 16;
+const CONTEXT_MATCH_MAX: usize = 32;
+const CONTEXT_AFTER: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureMatch<'a> {
+    pub signature: &'a ThreatSignature,
+    pub offset: u64,
+}
+
+#[derive(Default)]
+pub struct SignatureDatabase {
+    signatures: Vec<ThreatSignature>,
+    automaton: Option<AhoCorasick>,
+    max_pattern_len: usize,
+    rules: Vec<Rule>,
+    // Required literals of the rules that have one, so a single pass picks the rules worth evaluating
+    rule_filter: Option<AhoCorasick>,
+    // Rule index for each pattern of rule_filter
+    filtered_rules: Vec<usize>,
+    hashes: HashSignatureSet,
+    version: u64,
+    built: Option<DateTime<Local>>,
+}
+
+impl SignatureDatabase {
+    pub fn new(signatures: Vec<ThreatSignature>) -> Result<Self> {
+        // An empty pattern would match at every position of every file
+        let signatures: Vec<ThreatSignature> = signatures
+            .into_iter()
+            .filter(|signature| !signature.pattern.is_empty())
+            .collect();
+
+        let automaton = if signatures.is_empty() {
+            None
+        } else {
+            Some(
+                AhoCorasickBuilder::new()
+                    .match_kind(MatchKind::Standard)
+                    .build(signatures.iter().map(|signature| &signature.pattern))?,
+            )
+        };
+
+        let max_pattern_len = signatures
+            .iter()
+            .map(|signature| signature.pattern.len())
+            .max()
+            .unwrap_or(0);
+
+        Ok(SignatureDatabase {
+            signatures,
+            automaton,
+            max_pattern_len,
+            rules: Vec::new(),
+            rule_filter: None,
+            filtered_rules: Vec::new(),
+            hashes: HashSignatureSet::default(),
+            version: 0,
+            built: None,
+        })
+    }
+
+    pub fn set_version(&mut self, version: u64, built: Option<DateTime<Local>>) {
+        self.version = version;
+        self.built = built;
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn built(&self) -> Option<DateTime<Local>> {
+        self.built
+    }
+
+    pub fn add_rules(&mut self, rules: Vec<Rule>) {
+        if rules.is_empty() {
+            return;
+        }
+        self.rules.extend(rules);
+
+        self.filtered_rules = (0..self.rules.len())
+            .filter(|&i| self.rules[i].required_literal().is_some())
+            .collect();
+        let literals = self
+            .filtered_rules
+            .iter()
+            .filter_map(|&i| self.rules[i].required_literal());
+        self.rule_filter = match AhoCorasickBuilder::new().match_kind(MatchKind::Standard).build(literals) {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                // Without the filter every rule is simply evaluated
+                log::warn!("Cannot build rule prefilter: {}", e);
+                self.filtered_rules.clear();
+                None
+            }
+        };
+    }
+
+    pub fn add_hash_signatures(&mut self, hashes: Vec<HashSignature>) {
+        for hash in hashes {
+            self.hashes.insert(hash);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len() + self.rules.len() + self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty() && self.rules.is_empty() && self.hashes.is_empty()
+    }
+
+    pub fn needs_md5(&self) -> bool {
+        self.hashes.has_md5()
+    }
+
+    pub fn lookup_hash(&self, digests: &FileDigests, size: u64) -> Option<&ThreatSignature> {
+        self.hashes.lookup(digests, size)
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    // Rules that can match `data`: those without a required literal and those whose literal occurs in it
+    pub fn candidate_rules(&self, data: &[u8]) -> Vec<&Rule> {
+        let mut candidate = vec![true; self.rules.len()];
+        for &i in &self.filtered_rules {
+            candidate[i] = false;
+        }
+        if let Some(filter) = &self.rule_filter {
+            for m in filter.find_overlapping_iter(data) {
+                candidate[self.filtered_rules[m.pattern().as_usize()]] = true;
+            }
+        }
+
+        self.rules
+            .iter()
+            .zip(candidate)
+            .filter_map(|(rule, candidate)| candidate.then_some(rule))
+            .collect()
+    }
+
+    pub fn signatures(&self) -> &[ThreatSignature] {
+        &self.signatures
+    }
+
+    pub fn max_pattern_len(&self) -> usize {
+        self.max_pattern_len
+    }
+
+    pub fn find_matches(&self, data: &[u8]) -> Vec<SignatureMatch<'_>> {
+        let automaton = match &self.automaton {
+            Some(automaton) => automaton,
+            None => return Vec::new(),
+        };
+
+        let mut hits = vec![0usize; self.signatures.len()];
+        let mut matches = Vec::new();
+
+        for m in automaton.find_overlapping_iter(data) {
+            self.record_hit(&mut hits, &mut matches, m.pattern().as_usize(), m.start() as u64);
+        }
+
+        matches
+    }
+
+    pub fn scan_reader<R: Read>(&self, mut reader: R) -> io::Result<Vec<SignatureMatch<'_>>> {
+        let automaton = match &self.automaton {
+            Some(automaton) => automaton,
+            None => return Ok(Vec::new()),
+        };
+
+        // The tail of each chunk is carried into the next one so that a pattern
+        // split across a chunk boundary is still seen in one piece
+        let overlap = self.max_pattern_len.saturating_sub(1);
+        let mut buffer = vec![0u8; overlap + SCAN_CHUNK_SIZE];
+        let mut carried = 0;
+        // File offset of buffer[0]
+        let mut base: u64 = 0;
+
+        let mut hits = vec![0usize; self.signatures.len()];
+        let mut matches = Vec::new();
+
+        loop {
+            let bytes_read = match reader.read(&mut buffer[carried..]) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let filled = carried + bytes_read;
+
+            for m in automaton.find_overlapping_iter(&buffer[..filled]) {
+                // Entirely inside the carried tail, so already reported last round
+                if m.end() <= carried {
+                    continue;
+                }
+                self.record_hit(&mut hits, &mut matches, m.pattern().as_usize(), base + m.start() as u64);
+            }
+
+            let keep = overlap.min(filled);
+            buffer.copy_within(filled - keep..filled, 0);
+            base += (filled - keep) as u64;
+            carried = keep;
+        }
+
+        matches.sort_by_key(|m| m.offset);
+        Ok(matches)
+    }
+
+    fn record_hit<'a>(
+        &'a self,
+        hits: &mut [usize],
+        matches: &mut Vec<SignatureMatch<'a>>,
+        index: usize,
+        offset: u64,
+    ) {
+        // A file made of a repeated pattern should not flood the report
+        if hits[index] < MAX_HITS_PER_SIGNATURE {
+            hits[index] += 1;
+            matches.push(SignatureMatch {
+                signature: &self.signatures[index],
+                offset,
+            });
+        }
+    }
+}
+
+// Passes bytes through unchanged while hashing them and keeping the leading part
+// of the stream for checks that need random access (rules)
+pub struct ContentTap<R> {
+    inner: R,
+    sample: Vec<u8>,
+    sample_limit: usize,
+    total: u64,
+    sha256: Sha256,
+    md5: Option<Md5>,
+}
+
+impl<R: Read> ContentTap<R> {
+    pub fn new(inner: R, sample_limit: usize, with_md5: bool) -> Self {
+        ContentTap {
+            inner,
+            sample: Vec::new(),
+            sample_limit,
+            total: 0,
+            sha256: Sha256::new(),
+            md5: if with_md5 { Some(Md5::new()) } else { None },
+        }
+    }
+
+    pub fn sample(&self) -> &[u8] {
+        &self.sample
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    // Only meaningful once the stream has been read to the end
+    pub fn digests(&self) -> FileDigests {
+        FileDigests {
+            sha256: self.sha256.clone().finalize().into(),
+            md5: self.md5.as_ref().map(|md5| md5.clone().finalize().into()),
+        }
+    }
+}
+
+impl<R: Read> Read for ContentTap<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let room = self.sample_limit.saturating_sub(self.sample.len());
+        self.sample.extend_from_slice(&buf[..n.min(room)]);
+        self.total += n as u64;
+        self.sha256.update(&buf[..n]);
+        if let Some(md5) = self.md5.as_mut() {
+            md5.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+pub fn context_window(offset: u64, pattern_len: usize) -> (u64, usize) {
+    let start = offset.saturating_sub(CONTEXT_BEFORE as u64);
+    let len = (offset - start) as usize + pattern_len.min(CONTEXT_MATCH_MAX) + CONTEXT_AFTER;
+    (start, len)
+}
+
+// Renders bytes as "58 35 4f 21 | X5O!" for triage output
+pub fn format_context(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let ascii: String = bytes
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect();
+    format!("{} | {}", hex.join(" "), ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Severity, ThreatCategory};
+
+    fn signature(name: &str, pattern: &[u8]) -> ThreatSignature {
+        ThreatSignature {
+            name: name.to_string(),
+            pattern: pattern.to_vec(),
+            category: ThreatCategory::Virus,
+            severity: Severity::Critical,
+        }
+    }
+
+    fn names(matches: &[SignatureMatch<'_>]) -> Vec<String> {
+        matches.iter().map(|m| m.signature.name.clone()).collect()
+    }
+
+    #[test]
+    fn finds_every_signature_in_one_pass() {
+        let db = SignatureDatabase::new(vec![
+            signature("Alpha", b"alpha"),
+            signature("Beta", b"beta"),
+            signature("Gamma", b"gamma"),
+        ])
+        .unwrap();
+
+        let matches = db.find_matches(b"--beta--alpha--");
+        let mut found = names(&matches);
+        found.sort();
+        assert_eq!(found, vec!["Alpha", "Beta"]);
+    }
+
+    #[test]
+    fn reports_overlapping_patterns() {
+        let db = SignatureDatabase::new(vec![signature("Outer", b"malware"), signature("Inner", b"ware")]).unwrap();
+
+        let mut found = names(&db.find_matches(b"xxmalwarexx"));
+        found.sort();
+        assert_eq!(found, vec!["Inner", "Outer"]);
+    }
+
+    #[test]
+    fn drops_empty_patterns() {
+        let db = SignatureDatabase::new(vec![signature("Empty", b""), signature("Real", b"real")]).unwrap();
+
+        assert_eq!(db.signatures().len(), 1);
+        assert_eq!(db.max_pattern_len(), 4);
+        assert_eq!(names(&db.find_matches(b"real")), vec!["Real"]);
+    }
+
+    #[test]
+    fn empty_database_matches_nothing() {
+        let db = SignatureDatabase::new(Vec::new()).unwrap();
+
+        assert!(db.is_empty());
+        assert!(db.find_matches(b"anything").is_empty());
+        assert!(db.scan_reader(&b"anything"[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn binary_patterns_match_exactly() {
+        let db = SignatureDatabase::new(vec![signature("Bytes", &[0x00, 0xff, 0x7f, 0x80])]).unwrap();
+
+        assert_eq!(db.find_matches(&[0x01, 0x00, 0xff, 0x7f, 0x80]).len(), 1);
+        assert!(db.find_matches(&[0x00, 0xff, 0x7f, 0x81]).is_empty());
+    }
+
+    // Hands out at most `step` bytes per read, like a pipe or a slow disk
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn finds_patterns_split_across_chunks() {
+        let db = SignatureDatabase::new(vec![signature("Split", b"boundary")]).unwrap();
+        let mut data = vec![b'.'; SCAN_CHUNK_SIZE + 64];
+        let offset = SCAN_CHUNK_SIZE - 3;
+        data[offset..offset + 8].copy_from_slice(b"boundary");
+
+        let matches = db.scan_reader(&data[..]).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].offset, offset as u64);
+    }
+
+    #[test]
+    fn short_reads_do_not_duplicate_or_lose_matches() {
+        let db = SignatureDatabase::new(vec![signature("Word", b"needle")]).unwrap();
+        let data = b"needle..needle....needle";
+
+        let matches = db.scan_reader(Trickle { data, step: 5 }).unwrap();
+        let offsets: Vec<u64> = matches.iter().map(|m| m.offset).collect();
+        assert_eq!(offsets, vec![0, 8, 18]);
+    }
+
+    #[test]
+    fn tap_hashes_everything_but_samples_only_the_head() {
+        let data = vec![b'a'; 100];
+        let mut tap = ContentTap::new(&data[..], 10, true);
+        io::copy(&mut tap, &mut io::sink()).unwrap();
+
+        assert_eq!(tap.total(), 100);
+        assert_eq!(tap.sample(), &data[..10]);
+        let digests = tap.digests();
+        assert_eq!(digests.sha256, <[u8; 32]>::from(Sha256::digest(&data)));
+        assert_eq!(digests.md5, Some(<[u8; 16]>::from(Md5::digest(&data))));
+    }
+
+    #[test]
+    fn tap_skips_md5_unless_asked() {
+        let mut tap = ContentTap::new(&b"abc"[..], 10, false);
+        io::copy(&mut tap, &mut io::sink()).unwrap();
+
+        assert!(tap.digests().md5.is_none());
+    }
+
+    #[test]
+    fn reports_every_hit_with_its_offset() {
+        let db = SignatureDatabase::new(vec![signature("Word", b"bad")]).unwrap();
+
+        let offsets: Vec<u64> = db.find_matches(b"bad..bad.bad").iter().map(|m| m.offset).collect();
+        assert_eq!(offsets, vec![0, 5, 9]);
+    }
+
+    #[test]
+    fn caps_hits_per_signature() {
+        let db = SignatureDatabase::new(vec![signature("Byte", b"x"), signature("Other", b"y")]).unwrap();
+        let mut data = vec![b'x'; MAX_HITS_PER_SIGNATURE * 4];
+        data.push(b'y');
+
+        let matches = db.scan_reader(&data[..]).unwrap();
+        assert_eq!(matches.iter().filter(|m| m.signature.name == "Byte").count(), MAX_HITS_PER_SIGNATURE);
+        assert_eq!(matches.iter().filter(|m| m.signature.name == "Other").count(), 1);
+    }
+
+    #[test]
+    fn context_window_is_clamped_at_file_start() {
+        assert_eq!(context_window(4, 8), (0, 4 + 8 + CONTEXT_AFTER));
+        assert_eq!(context_window(100, 8), (100 - CONTEXT_BEFORE as u64, CONTEXT_BEFORE + 8 + CONTEXT_AFTER));
+        // Long patterns are shortened so the snippet stays readable
+        assert_eq!(context_window(100, 1000).1, CONTEXT_BEFORE + CONTEXT_MATCH_MAX + CONTEXT_AFTER);
+    }
+
+    #[test]
+    fn context_is_rendered_as_hex_and_ascii() {
+        assert_eq!(format_context(b"X5O!\x00\n"), "58 35 4f 21 00 0a | X5O!..");
+        assert_eq!(format_context(b""), " | ");
+    }
+}
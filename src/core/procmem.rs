@@ -0,0 +1,443 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+// ==================== SÜREÇ BELLEĞİ TARAMA ====================
+
+// Huge anonymous reservations (JIT heaps, sanitizer shadows) are only scanned this far
+pub const MAX_REGION_SCAN: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessMatch {
+    pub pid: u32,
+    pub name: String,
+    // Mapping that holds the match, as in /proc/<pid>/maps; the match address is the threat offset
+    pub region: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunningProcess {
+    pub pid: u32,
+    pub name: String,
+    // None for kernel threads and processes whose executable we may not resolve
+    pub exe: Option<PathBuf>,
+    // Clock ticks after boot; tells a reused pid apart from the process that was scanned
+    pub start_time: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryRegion {
+    pub start: u64,
+    pub end: u64,
+    pub perms: String,
+    // Backing file, "[heap]"/"[stack]" and similar, or empty for anonymous memory
+    pub path: String,
+}
+
+impl MemoryRegion {
+    pub fn len(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn readable(&self) -> bool {
+        self.perms.starts_with('r')
+    }
+
+    pub fn executable(&self) -> bool {
+        self.perms.as_bytes().get(2) == Some(&b'x')
+    }
+
+    // Memory with no file behind it, including memfd and deleted files that only live in memory now
+    pub fn anonymous(&self) -> bool {
+        self.path.is_empty()
+            || self.path == "[heap]"
+            || self.path.starts_with("[stack")
+            || self.path.starts_with("[anon")
+            || self.path.starts_with("/memfd:")
+            || self.path.ends_with(" (deleted)")
+    }
+
+    // Injected code and unpacked payloads live in anonymous or executable memory;
+    // [vvar] and [vsyscall] cannot be read through /proc/<pid>/mem
+    pub fn worth_scanning(&self) -> bool {
+        self.readable()
+            && (self.anonymous() || self.executable())
+            && !matches!(self.path.as_str(), "[vvar]" | "[vvar_vclock]" | "[vsyscall]")
+    }
+
+    pub fn describe(&self) -> String {
+        let path = if self.path.is_empty() { "anonymous" } else { self.path.as_str() };
+        format!("{:#x}-{:#x} {} {}", self.start, self.end, self.perms, path)
+    }
+}
+
+// Every process except ourselves; processes that exit while we look are skipped
+pub fn processes() -> Vec<RunningProcess> {
+    let own = std::process::id();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut processes: Vec<RunningProcess> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|&pid| pid != own)
+        .filter_map(read_process)
+        .collect();
+    processes.sort_by_key(|process| process.pid);
+    processes
+}
+
+fn read_process(pid: u32) -> Option<RunningProcess> {
+    let name = fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(RunningProcess {
+        pid,
+        name: name.trim_end().to_string(),
+        exe: fs::read_link(format!("/proc/{}/exe", pid)).ok(),
+        start_time: fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| parse_start_time(&stat)),
+    })
+}
+
+// Field 22 of /proc/<pid>/stat; the command name before it may itself hold spaces and ")"
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let fields = &stat[stat.rfind(')')? + 1..];
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+pub fn regions(pid: u32) -> io::Result<Vec<MemoryRegion>> {
+    let maps = fs::read_to_string(format!("/proc/{}/maps", pid))?;
+    Ok(maps.lines().filter_map(parse_maps_line).collect())
+}
+
+// "7f2c1a000000-7f2c1a021000 rw-p 00000000 00:00 0    [heap]"
+fn parse_maps_line(line: &str) -> Option<MemoryRegion> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let perms = fields.next()?.to_string();
+    // Offset, device and inode
+    for _ in 0..3 {
+        fields.next()?;
+    }
+    let path = fields.next().unwrap_or_default().trim_start().to_string();
+
+    Some(MemoryRegion {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        perms,
+        path,
+    })
+}
+
+// Reading another process needs ptrace access: same user without Yama restrictions, or root
+pub fn open_memory(pid: u32) -> io::Result<File> {
+    File::open(format!("/proc/{}/mem", pid))
+}
+
+// Streams one mapping; a page that turns unreadable mid-way ends the region instead of failing it
+pub struct RegionReader<'a> {
+    mem: &'a File,
+    position: u64,
+    end: u64,
+}
+
+impl<'a> RegionReader<'a> {
+    pub fn new(mem: &'a File, region: &MemoryRegion) -> Self {
+        RegionReader {
+            mem,
+            position: region.start,
+            end: region.start.saturating_add(region.len().min(MAX_REGION_SCAN)),
+        }
+    }
+}
+
+impl Read for RegionReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let wanted = (self.end - self.position).min(buf.len() as u64) as usize;
+        if wanted == 0 {
+            return Ok(0);
+        }
+        match self.mem.read_at(&mut buf[..wanted], self.position) {
+            Ok(n) => {
+                self.position += n as u64;
+                Ok(n)
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => Err(e),
+            Err(_) => {
+                self.position = self.end;
+                Ok(0)
+            }
+        }
+    }
+}
+
+pub fn read_at(mem: &File, address: u64, len: usize) -> Vec<u8> {
+    let mut buffer = vec![0u8; len];
+    match mem.read_at(&mut buffer, address) {
+        Ok(n) => buffer.truncate(n),
+        Err(_) => buffer.clear(),
+    }
+    buffer
+}
+
+pub fn terminate(pid: u32) -> Result<()> {
+    send_signal(pid, libc::SIGKILL, "terminate")
+}
+
+// Kills the process only while its pid still belongs to the executable and start time seen by the scan
+pub fn terminate_if_unchanged(process: &RunningProcess) -> Result<()> {
+    let unchanged = read_process(process.pid).is_some_and(|current| {
+        current.start_time.is_some() && current.start_time == process.start_time && current.exe == process.exe
+    });
+    if !unchanged {
+        return Err(anyhow!(
+            "Process {} ({}) exited or was replaced since it was scanned; not terminating",
+            process.pid,
+            process.name
+        ));
+    }
+    terminate(process.pid)
+}
+
+// A stopped process keeps its state, so a false alarm can be undone with resume
+pub fn suspend(pid: u32) -> Result<()> {
+    send_signal(pid, libc::SIGSTOP, "suspend")
+}
+
+pub fn resume(pid: u32) -> Result<()> {
+    send_signal(pid, libc::SIGCONT, "resume")
+}
+
+fn send_signal(pid: u32, signal: libc::c_int, action: &str) -> Result<()> {
+    if pid <= 1 || pid == std::process::id() {
+        return Err(anyhow!("Refusing to {} process {}", action, pid));
+    }
+    // SAFETY: kill(2) has no memory-safety preconditions
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+        return Err(anyhow!("Failed to {} process {}: {}", action, pid, io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+// Processes with files open for writing in any of the given directories, busiest first.
+// Only descriptors we may inspect are seen: our own user's processes, or all of them as root.
+pub fn open_file_writers(dirs: &[PathBuf]) -> Vec<(RunningProcess, usize)> {
+    let mut writers: Vec<(RunningProcess, usize)> = processes()
+        .into_iter()
+        .filter_map(|process| {
+            let fds = fs::read_dir(format!("/proc/{}/fd", process.pid)).ok()?;
+            let count = fds
+                .filter_map(|fd| fd.ok())
+                .filter(|fd| {
+                    fs::read_link(fd.path()).is_ok_and(|target| {
+                        target.parent().is_some_and(|parent| dirs.iter().any(|dir| dir == parent))
+                    })
+                })
+                .filter(|fd| open_for_writing(process.pid, &fd.file_name().to_string_lossy()))
+                .count();
+            (count > 0).then_some((process, count))
+        })
+        .collect();
+    writers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.pid.cmp(&b.0.pid)));
+    writers
+}
+
+fn open_for_writing(pid: u32, fd: &str) -> bool {
+    fs::read_to_string(format!("/proc/{}/fdinfo/{}", pid, fd))
+        .ok()
+        .and_then(|info| parse_fd_flags(&info))
+        .is_some_and(|flags| {
+            let mode = flags & libc::O_ACCMODE as u32;
+            mode == libc::O_WRONLY as u32 || mode == libc::O_RDWR as u32
+        })
+}
+
+// The "flags:" line of /proc/<pid>/fdinfo/<fd>, in octal
+fn parse_fd_flags(info: &str) -> Option<u32> {
+    let flags = info.lines().find_map(|line| line.strip_prefix("flags:"))?;
+    u32::from_str_radix(flags.trim(), 8).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::process::{Child, Command};
+    use std::thread;
+    use std::time::Duration;
+
+    fn region(line: &str) -> MemoryRegion {
+        parse_maps_line(line).unwrap()
+    }
+
+    fn sleeper() -> Child {
+        Command::new("sleep").arg("30").spawn().unwrap()
+    }
+
+    fn exited(child: &mut Child) -> bool {
+        for _ in 0..50 {
+            if child.try_wait().unwrap().is_some() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn parses_maps_lines() {
+        let heap = region("7f2c1a000000-7f2c1a021000 rw-p 00000000 00:00 0                          [heap]");
+        assert_eq!((heap.start, heap.end, heap.len()), (0x7f2c1a000000, 0x7f2c1a021000, 0x21000));
+        assert!(heap.readable() && !heap.executable() && heap.anonymous() && heap.worth_scanning());
+
+        let library = region("7f00-8f00 r-xp 00001000 08:01 1234 /usr/lib/libc.so.6");
+        assert!(library.executable() && !library.anonymous() && library.worth_scanning());
+        assert_eq!(library.describe(), "0x7f00-0x8f00 r-xp /usr/lib/libc.so.6");
+
+        let data = region("1000-2000 rw-p 00000000 08:01 1234 /usr/lib/libc.so.6");
+        assert!(!data.worth_scanning());
+        let deleted = region("1000-2000 r--p 00000000 08:01 1234 /tmp/x (deleted)");
+        assert!(deleted.anonymous() && deleted.worth_scanning());
+        let spaced = region("1000-2000 r-xp 00000000 08:01 1234 /opt/My App/bin");
+        assert_eq!(spaced.path, "/opt/My App/bin");
+
+        assert!(!region("1000-2000 r--p 00000000 00:00 0 [vvar]").worth_scanning());
+        assert!(!region("1000-2000 ---p 00000000 00:00 0").worth_scanning());
+        assert_eq!(region("1000-2000 rw-p 00000000 00:00 0").describe(), "0x1000-0x2000 rw-p anonymous");
+    }
+
+    #[test]
+    fn rejects_malformed_maps_lines() {
+        for line in ["", "garbage", "zz-10 rw-p 0 0 0", "10-20", "10-20 rw-p 0 00:00", "1020 rw-p 0 0 0 x"] {
+            assert!(parse_maps_line(line).is_none(), "{:?}", line);
+        }
+        // A reversed range does not underflow
+        assert_eq!(region("20-10 rw-p 0 0 0").len(), 0);
+    }
+
+    #[test]
+    fn parses_start_time_past_odd_command_names() {
+        let fields = "S 1 1 1 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 1 0 987654 1000 50";
+        assert_eq!(parse_start_time(&format!("42 (bash) {}", fields)), Some(987654));
+        assert_eq!(parse_start_time(&format!("42 (evil) (x y) {}", fields)), Some(987654));
+        assert_eq!(parse_start_time("42 (bash) S 1 1"), None);
+        assert_eq!(parse_start_time("42 bash S"), None);
+    }
+
+    #[test]
+    fn reads_own_memory() {
+        let marker = b"procmem-test-marker".to_vec();
+        let mem = open_memory(std::process::id()).unwrap();
+        let address = marker.as_ptr() as u64;
+        assert_eq!(read_at(&mem, address, marker.len()), marker);
+        assert!(read_at(&mem, 0, 16).is_empty());
+
+        let region = MemoryRegion {
+            start: address,
+            end: address + marker.len() as u64,
+            perms: "rw-p".to_string(),
+            path: String::new(),
+        };
+        let mut read = Vec::new();
+        RegionReader::new(&mem, &region).read_to_end(&mut read).unwrap();
+        assert_eq!(read, marker);
+
+        // An unmapped page ends the region quietly
+        let unmapped = MemoryRegion { start: 0, end: 4096, ..region };
+        let mut read = Vec::new();
+        RegionReader::new(&mem, &unmapped).read_to_end(&mut read).unwrap();
+        assert!(read.is_empty());
+    }
+
+    #[test]
+    fn lists_processes_with_identity() {
+        let mut child = sleeper();
+        let found = processes().into_iter().find(|p| p.pid == child.id()).unwrap();
+        assert_eq!(found.name, "sleep");
+        assert!(found.exe.is_some() && found.start_time.is_some());
+        assert!(processes().iter().all(|p| p.pid != std::process::id()));
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn terminate_checks_the_pid_still_names_the_scanned_process() {
+        let mut child = sleeper();
+        let scanned = read_process(child.id()).unwrap();
+
+        // Same pid, different start time: the pid was reused
+        let reused = RunningProcess {
+            start_time: scanned.start_time.map(|t| t + 1),
+            ..scanned.clone()
+        };
+        assert!(terminate_if_unchanged(&reused).is_err());
+        let replaced = RunningProcess {
+            exe: Some(PathBuf::from("/usr/bin/other")),
+            ..scanned.clone()
+        };
+        assert!(terminate_if_unchanged(&replaced).is_err());
+        assert!(child.try_wait().unwrap().is_none());
+
+        terminate_if_unchanged(&scanned).unwrap();
+        assert!(exited(&mut child));
+        // Gone now, so nothing is signalled
+        assert!(terminate_if_unchanged(&scanned).is_err());
+    }
+
+    #[test]
+    fn parses_fd_flags() {
+        assert_eq!(parse_fd_flags("pos:\t0\nflags:\t0100002\nmnt_id:\t25\n"), Some(0o100002));
+        assert_eq!(parse_fd_flags("pos:\t0\nflags:\t02000000\n"), Some(0o2000000));
+        assert_eq!(parse_fd_flags("pos:\t0\n"), None);
+        assert_eq!(parse_fd_flags("flags:\t0109\n"), None);
+        assert_eq!(parse_fd_flags(""), None);
+    }
+
+    fn holding(file: &Path, redirect: &str) -> Child {
+        let script = format!("exec 3{}\"$0\"; exec sleep 30", redirect);
+        Command::new("sh").arg("-c").arg(script).arg(file).spawn().unwrap()
+    }
+
+    fn writers(dir: &Path, pid: u32) -> Option<usize> {
+        // The shell needs a moment to open the file
+        for _ in 0..50 {
+            if let Some((_, count)) = open_file_writers(&[dir.to_path_buf()]).into_iter().find(|(p, _)| p.pid == pid) {
+                return Some(count);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        None
+    }
+
+    #[test]
+    fn finds_only_processes_writing_in_a_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("victim.docx");
+        fs::write(&file, b"data").unwrap();
+
+        let mut writer = holding(&file, ">>");
+        let mut reader = holding(&file, "<");
+        assert_eq!(writers(dir.path(), writer.id()), Some(1));
+        assert_eq!(writers(dir.path(), reader.id()), None);
+        // Other directories see nothing
+        let other = tempfile::TempDir::new().unwrap();
+        assert!(open_file_writers(&[other.path().to_path_buf()]).iter().all(|(p, _)| p.pid != writer.id()));
+
+        for child in [&mut writer, &mut reader] {
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+    }
+
+    #[test]
+    fn refuses_to_signal_init_or_itself() {
+        assert!(terminate(1).is_err());
+        assert!(suspend(0).is_err());
+        assert!(resume(std::process::id()).is_err());
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
+use notify::event::{AccessKind, AccessMode, CreateKind, ModifyKind, RemoveKind, RenameMode};
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use super::ScanExclusions;
+
+// ==================== GERÇEK ZAMANLI KORUMA ====================
+//
+// Watcher events are coalesced per path until the file has been quiet for the debounce
+// interval, then handed to a bounded queue that a few scanner threads drain. Listeners
+// such as the behavior monitor get the undebounced activity alongside.
+
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+// Paths waiting for their debounce interval; beyond this new paths are dropped
+const MAX_PENDING: usize = 65536;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedRoot {
+    pub path: PathBuf,
+    pub recursive: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealtimeConfig {
+    pub enabled: bool,
+    pub roots: Vec<WatchedRoot>,
+    // A file is scanned once it has seen no events for this long
+    pub debounce_ms: u64,
+    pub queue_capacity: usize,
+    pub scanner_threads: usize,
+    pub max_file_size: u64,
+    pub heuristic_enabled: bool,
+    pub heuristic_threshold: f32,
+    pub excluded_extensions: Vec<String>,
+    pub excluded_paths: Vec<PathBuf>,
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        let home = dirs::home_dir();
+        let mut roots = Vec::new();
+        if let Some(home) = &home {
+            // The home directory itself only catches files dropped straight into it
+            roots.push(WatchedRoot {
+                path: home.clone(),
+                recursive: false,
+            });
+            roots.push(WatchedRoot {
+                path: dirs::download_dir().unwrap_or_else(|| home.join("Downloads")),
+                recursive: true,
+            });
+        }
+        // Where ransomware goes looking for files worth encrypting; user-dirs.dirs may point these at home itself
+        let user_dirs = [dirs::document_dir(), dirs::desktop_dir(), dirs::picture_dir()];
+        for dir in user_dirs.into_iter().flatten().filter(|dir| Some(dir) != home.as_ref()) {
+            roots.push(WatchedRoot {
+                path: dir,
+                recursive: true,
+            });
+        }
+        roots.push(WatchedRoot {
+            path: PathBuf::from("/tmp"),
+            recursive: true,
+        });
+
+        RealtimeConfig {
+            enabled: true,
+            roots,
+            debounce_ms: 500,
+            queue_capacity: 1024,
+            scanner_threads: 2,
+            max_file_size: 100 * 1024 * 1024,
+            heuristic_enabled: true,
+            heuristic_threshold: 0.6,
+            excluded_extensions: vec![".part".to_string(), ".crdownload".to_string(), ".swp".to_string()],
+            excluded_paths: Vec::new(),
+        }
+    }
+}
+
+// What listeners see of the event stream, before debouncing
+#[derive(Debug, Clone)]
+pub enum FileActivity {
+    // A writer closed the file
+    Written(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+    Removed(PathBuf),
+}
+
+pub struct RealtimeMonitor {
+    // Dropping the watcher closes the event stream, which winds down every thread below
+    watcher: RecommendedWatcher,
+    running: Arc<AtomicBool>,
+    watched: Vec<WatchedRoot>,
+}
+
+impl RealtimeMonitor {
+    // Watches the configured roots and calls `scan` for every regular file written below them.
+    // Listeners that fall behind lose activity rather than stall scanning.
+    pub fn start<F>(config: &RealtimeConfig, listeners: Vec<Sender<FileActivity>>, scan: F) -> Result<Self>
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        let (event_tx, event_rx) = crossbeam_channel::unbounded::<notify::Result<Event>>();
+        let mut watcher = RecommendedWatcher::new(
+            move |event| {
+                let _ = event_tx.send(event);
+            },
+            Config::default(),
+        )?;
+
+        let mut watched = Vec::new();
+        for root in &config.roots {
+            let mode = if root.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+            match watcher.watch(&root.path, mode) {
+                Ok(()) => watched.push(root.clone()),
+                Err(e) => log::warn!("Not watching {:?}: {}", root.path, e),
+            }
+        }
+        if watched.is_empty() {
+            return Err(anyhow!("None of the real-time protection roots could be watched"));
+        }
+
+        let (queue_tx, queue_rx) = crossbeam_channel::bounded::<PathBuf>(config.queue_capacity.max(1));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let debounce = Duration::from_millis(config.debounce_ms);
+        let exclusions = ScanExclusions::new(&config.excluded_extensions, &config.excluded_paths);
+        let max_file_size = config.max_file_size;
+        let debouncer_running = running.clone();
+        thread::Builder::new().name("realtime-events".to_string()).spawn(move || {
+            let mut debouncer = Debouncer::new(debounce);
+            let mut last_flush = Instant::now();
+            'events: loop {
+                match event_rx.recv_timeout(FLUSH_INTERVAL) {
+                    Ok(Ok(event)) => {
+                        for (path, complete) in interesting_paths(&event) {
+                            if !exclusions.is_excluded(&path, false) {
+                                debouncer.touch(path, complete);
+                            }
+                        }
+                        if let Some(activity) = file_activity(&event) {
+                            for listener in &listeners {
+                                let _ = listener.try_send(activity.clone());
+                            }
+                        }
+                    }
+                    Ok(Err(e)) => log::warn!("Real-time watcher error: {}", e),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break 'events,
+                }
+
+                if last_flush.elapsed() >= FLUSH_INTERVAL {
+                    last_flush = Instant::now();
+                    for path in debouncer.ready() {
+                        if !is_scannable(&path, max_file_size) {
+                            continue;
+                        }
+                        match queue_tx.try_send(path) {
+                            Ok(()) => {}
+                            // Scanners are behind; keep the path and offer it again on the next flush
+                            Err(TrySendError::Full(path)) => debouncer.defer(path),
+                            Err(TrySendError::Disconnected(_)) => break 'events,
+                        }
+                    }
+                }
+            }
+            debouncer_running.store(false, Ordering::SeqCst);
+            log::info!("Real-time protection stopped");
+        })?;
+
+        let scan = Arc::new(scan);
+        for index in 0..config.scanner_threads.max(1) {
+            let queue_rx: Receiver<PathBuf> = queue_rx.clone();
+            let scan = scan.clone();
+            thread::Builder::new().name(format!("realtime-scan-{}", index)).spawn(move || {
+                for path in queue_rx {
+                    scan(&path);
+                }
+            })?;
+        }
+
+        log::info!("Real-time protection watching {:?}", watched.iter().map(|root| &root.path).collect::<Vec<_>>());
+        Ok(RealtimeMonitor {
+            watcher,
+            running,
+            watched,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    pub fn watched_roots(&self) -> &[WatchedRoot] {
+        &self.watched
+    }
+
+    // Adds a directory on its own unless a watched root already covers it
+    pub fn ensure_watched(&mut self, dir: &Path) -> Result<()> {
+        let covered = self
+            .watched
+            .iter()
+            .any(|root| root.path == dir || (root.recursive && dir.starts_with(&root.path)));
+        if !covered {
+            self.watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            self.watched.push(WatchedRoot {
+                path: dir.to_path_buf(),
+                recursive: false,
+            });
+        }
+        Ok(())
+    }
+}
+
+// Files written to, with whether the writer is known to be done with them
+fn interesting_paths(event: &Event) -> Vec<(PathBuf, bool)> {
+    match event.kind {
+        EventKind::Create(CreateKind::File | CreateKind::Any)
+        | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any | ModifyKind::Metadata(_)) => {
+            event.paths.iter().map(|path| (path.clone(), false)).collect()
+        }
+        // The destination of a rename is the last path; files moved in are complete already
+        EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both)) => {
+            event.paths.last().map(|path| (path.clone(), true)).into_iter().collect()
+        }
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => {
+            event.paths.iter().map(|path| (path.clone(), true)).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn file_activity(event: &Event) -> Option<FileActivity> {
+    match (&event.kind, event.paths.as_slice()) {
+        (EventKind::Access(AccessKind::Close(AccessMode::Write)), [path, ..]) => Some(FileActivity::Written(path.clone())),
+        (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to, ..]) => Some(FileActivity::Renamed {
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        (EventKind::Remove(RemoveKind::File | RemoveKind::Any), [path, ..]) => Some(FileActivity::Removed(path.clone())),
+        _ => None,
+    }
+}
+
+// Regular files only: symlinks are scanned where they point, if that is watched at all
+fn is_scannable(path: &Path, max_file_size: u64) -> bool {
+    fs::symlink_metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.len() > 0 && metadata.len() <= max_file_size)
+        .unwrap_or(false)
+}
+
+struct Debouncer {
+    interval: Duration,
+    // When each path becomes due
+    pending: HashMap<PathBuf, Instant>,
+    dropped: u64,
+}
+
+impl Debouncer {
+    fn new(interval: Duration) -> Self {
+        Debouncer {
+            interval,
+            pending: HashMap::new(),
+            dropped: 0,
+        }
+    }
+
+    // Every event pushes the path back; a closed writer makes it due right away
+    fn touch(&mut self, path: PathBuf, complete: bool) {
+        if self.pending.len() >= MAX_PENDING && !self.pending.contains_key(&path) {
+            self.dropped += 1;
+            if self.dropped.is_power_of_two() {
+                log::warn!("Real-time protection is overloaded, {} file events dropped", self.dropped);
+            }
+            return;
+        }
+        let now = Instant::now();
+        let due = if complete { now } else { now + self.interval };
+        self.pending.insert(path, due);
+    }
+
+    fn ready(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, due)| **due <= now)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready
+    }
+
+    fn defer(&mut self, path: PathBuf) {
+        self.pending.entry(path).or_insert_with(Instant::now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::DataChange;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| event.add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn picks_written_files_from_events() {
+        let created = event(EventKind::Create(CreateKind::File), &["/w/a"]);
+        assert_eq!(interesting_paths(&created), vec![(PathBuf::from("/w/a"), false)]);
+        let written = event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &["/w/a"]);
+        assert_eq!(interesting_paths(&written), vec![(PathBuf::from("/w/a"), false)]);
+        let closed = event(EventKind::Access(AccessKind::Close(AccessMode::Write)), &["/w/a"]);
+        assert_eq!(interesting_paths(&closed), vec![(PathBuf::from("/w/a"), true)]);
+        let renamed = event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/w/a.part", "/w/a"]);
+        assert_eq!(interesting_paths(&renamed), vec![(PathBuf::from("/w/a"), true)]);
+
+        assert!(interesting_paths(&event(EventKind::Create(CreateKind::Folder), &["/w/d"])).is_empty());
+        assert!(interesting_paths(&event(EventKind::Remove(RemoveKind::File), &["/w/a"])).is_empty());
+        assert!(interesting_paths(&event(EventKind::Access(AccessKind::Read), &["/w/a"])).is_empty());
+        assert!(interesting_paths(&event(EventKind::Modify(ModifyKind::Name(RenameMode::To)), &[])).is_empty());
+    }
+
+    #[test]
+    fn reports_activity_to_listeners() {
+        let closed = event(EventKind::Access(AccessKind::Close(AccessMode::Write)), &["/w/a"]);
+        assert!(matches!(file_activity(&closed), Some(FileActivity::Written(path)) if path == Path::new("/w/a")));
+        let renamed = event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/w/a", "/w/a.locked"]);
+        assert!(matches!(
+            file_activity(&renamed),
+            Some(FileActivity::Renamed { from, to }) if from == Path::new("/w/a") && to == Path::new("/w/a.locked")
+        ));
+        let removed = event(EventKind::Remove(RemoveKind::File), &["/w/a"]);
+        assert!(matches!(file_activity(&removed), Some(FileActivity::Removed(_))));
+
+        // Half a rename or an event without paths says nothing useful
+        assert!(file_activity(&event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/w/a"])).is_none());
+        assert!(file_activity(&event(EventKind::Remove(RemoveKind::File), &[])).is_none());
+        assert!(file_activity(&event(EventKind::Create(CreateKind::File), &["/w/a"])).is_none());
+    }
+
+    #[test]
+    fn debounces_until_quiet() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(50));
+        debouncer.touch(PathBuf::from("/w/slow"), false);
+        debouncer.touch(PathBuf::from("/w/done"), true);
+        assert_eq!(debouncer.ready(), vec![PathBuf::from("/w/done")]);
+        assert!(debouncer.ready().is_empty());
+
+        thread::sleep(Duration::from_millis(30));
+        // Another write pushes the deadline back
+        debouncer.touch(PathBuf::from("/w/slow"), false);
+        thread::sleep(Duration::from_millis(30));
+        assert!(debouncer.ready().is_empty());
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(debouncer.ready(), vec![PathBuf::from("/w/slow")]);
+
+        debouncer.defer(PathBuf::from("/w/full"));
+        assert_eq!(debouncer.ready(), vec![PathBuf::from("/w/full")]);
+    }
+
+    #[test]
+    fn drops_paths_beyond_the_pending_limit() {
+        let mut debouncer = Debouncer::new(Duration::from_secs(60));
+        for index in 0..MAX_PENDING {
+            debouncer.touch(PathBuf::from(format!("/w/{}", index)), false);
+        }
+        debouncer.touch(PathBuf::from("/w/extra"), true);
+        assert_eq!(debouncer.dropped, 1);
+        // Paths already pending are still updated
+        debouncer.touch(PathBuf::from("/w/0"), true);
+        assert_eq!(debouncer.dropped, 1);
+        assert_eq!(debouncer.ready(), vec![PathBuf::from("/w/0")]);
+    }
+
+    #[test]
+    fn scans_only_regular_nonempty_files() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("file");
+        fs::write(&file, b"data").unwrap();
+        let empty = dir.path().join("empty");
+        fs::write(&empty, b"").unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        assert!(is_scannable(&file, 4));
+        assert!(!is_scannable(&file, 3));
+        assert!(!is_scannable(&empty, 100));
+        assert!(!is_scannable(&link, 100));
+        assert!(!is_scannable(dir.path(), 100));
+        assert!(!is_scannable(&dir.path().join("missing"), 100));
+    }
+
+    fn watch(dir: &Path, recursive: bool) -> RealtimeConfig {
+        RealtimeConfig {
+            roots: vec![WatchedRoot {
+                path: dir.to_path_buf(),
+                recursive,
+            }],
+            debounce_ms: 50,
+            ..RealtimeConfig::default()
+        }
+    }
+
+    fn wait_for<T>(mut check: impl FnMut() -> Option<T>) -> Option<T> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(value) = check() {
+                return Some(value);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        None
+    }
+
+    #[test]
+    fn scans_files_written_under_a_root() {
+        let dir = TempDir::new().unwrap();
+        let scanned = Arc::new(Mutex::new(Vec::new()));
+        let (activity_tx, activity_rx) = crossbeam_channel::bounded(16);
+        let sink = scanned.clone();
+        let mut monitor = RealtimeMonitor::start(&watch(dir.path(), true), vec![activity_tx], move |path| {
+            sink.lock().unwrap().push(path.to_path_buf());
+        })
+        .unwrap();
+        assert!(monitor.is_running());
+        assert_eq!(monitor.watched_roots().len(), 1);
+
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        // Give the recursive watch time to pick up the new directory
+        thread::sleep(Duration::from_millis(100));
+        let nested = dir.path().join("sub/payload.sh");
+        fs::write(&nested, b"#!/bin/sh\n").unwrap();
+        fs::write(dir.path().join("download.part"), b"partial").unwrap();
+
+        let found = wait_for(|| scanned.lock().unwrap().contains(&nested).then_some(()));
+        assert!(found.is_some(), "{:?}", scanned.lock().unwrap());
+        assert!(matches!(activity_rx.recv_timeout(Duration::from_secs(5)), Ok(FileActivity::Written(_))));
+
+        // Excluded extensions never reach the scanners
+        thread::sleep(Duration::from_millis(200));
+        assert!(scanned.lock().unwrap().iter().all(|path| path.extension().is_none_or(|ext| ext != "part")));
+
+        // A covered subdirectory is not watched twice
+        monitor.ensure_watched(&dir.path().join("sub")).unwrap();
+        assert_eq!(monitor.watched_roots().len(), 1);
+        drop(monitor);
+    }
+
+    #[test]
+    fn refuses_when_no_root_can_be_watched() {
+        let dir = TempDir::new().unwrap();
+        let config = watch(&dir.path().join("missing"), true);
+        assert!(RealtimeMonitor::start(&config, Vec::new(), |_| {}).is_err());
+    }
+
+    #[test]
+    fn adds_directories_outside_the_roots() {
+        let watched = TempDir::new().unwrap();
+        let other = TempDir::new().unwrap();
+        let mut monitor = RealtimeMonitor::start(&watch(watched.path(), false), Vec::new(), |_| {}).unwrap();
+        monitor.ensure_watched(other.path()).unwrap();
+        monitor.ensure_watched(other.path()).unwrap();
+        assert_eq!(monitor.watched_roots().len(), 2);
+        assert!(monitor.ensure_watched(&other.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::rules::parse_category;
+use super::{Severity, ThreatSignature};
+
+// ==================== HASH İTİBAR SORGUSU ====================
+//
+// POST <endpoint>  {"hashes": ["<sha256>", ...]}
+//   -> {"verdicts": [{"sha256": "...", "verdict": "malicious", "name": "...", "category": "Trojan"}]}
+// Hashes missing from the response are treated as unknown.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationConfig {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub batch_size: usize,
+    pub cache_ttl_secs: i64,
+    // The service may learn about an unknown file soon, so those answers expire early
+    pub unknown_ttl_secs: i64,
+    pub timeout_secs: u64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        // No hash leaves the machine until the user configures a service
+        ReputationConfig {
+            enabled: false,
+            endpoint: String::new(),
+            batch_size: 100,
+            cache_ttl_secs: 24 * 60 * 60,
+            unknown_ttl_secs: 60 * 60,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl ReputationConfig {
+    pub fn is_configured(&self) -> bool {
+        self.enabled && !self.endpoint.trim().is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ReputationOutcome<T> {
+    Disabled,
+    Answered(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Clean,
+    Malicious,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationVerdict {
+    pub sha256: String,
+    pub verdict: Verdict,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+impl ReputationVerdict {
+    pub fn is_malicious(&self) -> bool {
+        self.verdict == Verdict::Malicious
+    }
+
+    pub fn to_signature(&self) -> ThreatSignature {
+        ThreatSignature {
+            name: self
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Reputation.Malicious.{}", &self.sha256[..self.sha256.len().min(12)])),
+            pattern: Vec::new(),
+            category: parse_category(self.category.as_deref().unwrap_or("")),
+            severity: Severity::Critical,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LookupRequest<'a> {
+    hashes: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct LookupResponse {
+    verdicts: Vec<ReputationVerdict>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedVerdict {
+    verdict: ReputationVerdict,
+    fetched: DateTime<Local>,
+}
+
+pub struct ReputationClient {
+    config: ReputationConfig,
+    client: reqwest::blocking::Client,
+    cache_path: PathBuf,
+    cache: HashMap<String, CachedVerdict>,
+    offline: bool,
+}
+
+impl ReputationClient {
+    pub fn new(config: ReputationConfig, cache_path: &Path) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+
+        // A damaged cache is simply rebuilt
+        let cache = fs::read(cache_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        Ok(ReputationClient {
+            config,
+            client,
+            cache_path: cache_path.to_path_buf(),
+            cache,
+            offline: false,
+        })
+    }
+
+    fn is_fresh(config: &ReputationConfig, entry: &CachedVerdict, now: DateTime<Local>) -> bool {
+        let ttl = match entry.verdict.verdict {
+            Verdict::Unknown => config.unknown_ttl_secs.min(config.cache_ttl_secs),
+            _ => config.cache_ttl_secs,
+        };
+        now.signed_duration_since(entry.fetched).num_seconds() < ttl
+    }
+
+    fn cached(&self, sha256: &str) -> Option<&ReputationVerdict> {
+        let entry = self.cache.get(sha256)?;
+        Self::is_fresh(&self.config, entry, Local::now()).then_some(&entry.verdict)
+    }
+
+    // Answers from the cache where possible and queries the rest in batches.
+    // Once the service is unreachable the remaining hashes stay unanswered.
+    pub fn lookup(&mut self, hashes: &[String]) -> ReputationOutcome<HashMap<String, ReputationVerdict>> {
+        if !self.config.is_configured() {
+            return ReputationOutcome::Disabled;
+        }
+
+        let mut verdicts = HashMap::new();
+        let mut pending = Vec::new();
+        let mut queued = HashSet::new();
+
+        for sha256 in hashes {
+            let sha256 = sha256.to_lowercase();
+            match self.cached(&sha256) {
+                Some(verdict) => {
+                    verdicts.insert(sha256, verdict.clone());
+                }
+                None if queued.insert(sha256.clone()) => pending.push(sha256),
+                None => {}
+            }
+        }
+
+        for batch in pending.chunks(self.config.batch_size.max(1)) {
+            if self.offline {
+                break;
+            }
+            match self.query(batch) {
+                Ok(answers) => {
+                    let fetched = Local::now();
+                    for sha256 in batch {
+                        let verdict = answers.get(sha256).cloned().unwrap_or_else(|| ReputationVerdict {
+                            sha256: sha256.clone(),
+                            verdict: Verdict::Unknown,
+                            name: None,
+                            category: None,
+                        });
+                        self.cache.insert(
+                            sha256.clone(),
+                            CachedVerdict {
+                                verdict: verdict.clone(),
+                                fetched,
+                            },
+                        );
+                        verdicts.insert(sha256.clone(), verdict);
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Reputation service unavailable, continuing offline: {}", e);
+                    self.offline = true;
+                }
+            }
+        }
+
+        if let Err(e) = self.save() {
+            log::warn!("Failed to save reputation cache: {}", e);
+        }
+
+        ReputationOutcome::Answered(verdicts)
+    }
+
+    fn query(&self, batch: &[String]) -> Result<HashMap<String, ReputationVerdict>> {
+        let response: LookupResponse = self
+            .client
+            .post(&self.config.endpoint)
+            .json(&LookupRequest { hashes: batch })
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        Ok(response
+            .verdicts
+            .into_iter()
+            .map(|mut verdict| {
+                verdict.sha256 = verdict.sha256.to_lowercase();
+                (verdict.sha256.clone(), verdict)
+            })
+            .collect())
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let config = &self.config;
+        let now = Local::now();
+        self.cache.retain(|_, entry| Self::is_fresh(config, entry, now));
+
+        if let Some(parent) = self.cache_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.cache_path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.cache)?)?;
+        fs::rename(&tmp, &self.cache_path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ThreatCategory;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use tempfile::TempDir;
+
+    const BAD: &str = "aaaa000000000000000000000000000000000000000000000000000000000000";
+    const GOOD: &str = "bbbb000000000000000000000000000000000000000000000000000000000000";
+    const NEW: &str = "cccc000000000000000000000000000000000000000000000000000000000000";
+
+    // Stand-in lookup service: BAD is malicious, GOOD is clean, anything else is left out.
+    // Every request's hash list is recorded.
+    fn mock_service(reply: &'static str) -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/lookup", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut length = 0;
+                let mut line = String::new();
+                while matches!(reader.read_line(&mut line), Ok(n) if n > 0) && line != "\r\n" {
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap_or(0);
+                    }
+                    line.clear();
+                }
+                let mut body = vec![0; length];
+                if reader.read_exact(&mut body).is_err() {
+                    continue;
+                }
+
+                let hashes: Vec<String> = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["hashes"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|h| h.as_str().unwrap().to_string())
+                    .collect();
+                let verdicts: Vec<serde_json::Value> = hashes
+                    .iter()
+                    .filter_map(|h| match h.as_str() {
+                        BAD => Some(serde_json::json!({"sha256": BAD.to_uppercase(), "verdict": "malicious", "category": "Ransomware"})),
+                        GOOD => Some(serde_json::json!({"sha256": GOOD, "verdict": "clean"})),
+                        _ => None,
+                    })
+                    .collect();
+                seen.lock().unwrap().push(hashes);
+
+                let body = if reply.is_empty() {
+                    serde_json::json!({ "verdicts": verdicts }).to_string()
+                } else {
+                    reply.to_string()
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = (&stream).write_all(response.as_bytes());
+            }
+        });
+
+        (endpoint, requests)
+    }
+
+    fn client(endpoint: &str, dir: &TempDir) -> ReputationClient {
+        let config = ReputationConfig {
+            enabled: true,
+            endpoint: endpoint.to_string(),
+            batch_size: 2,
+            timeout_secs: 5,
+            ..ReputationConfig::default()
+        };
+        ReputationClient::new(config, &dir.path().join("reputation-cache.json")).unwrap()
+    }
+
+    fn hashes(list: &[&str]) -> Vec<String> {
+        list.iter().map(|h| h.to_string()).collect()
+    }
+
+    fn answered(outcome: ReputationOutcome<HashMap<String, ReputationVerdict>>) -> HashMap<String, ReputationVerdict> {
+        match outcome {
+            ReputationOutcome::Answered(verdicts) => verdicts,
+            ReputationOutcome::Disabled => panic!("reputation lookups are disabled"),
+        }
+    }
+
+    #[test]
+    fn classifies_hashes_from_the_service() {
+        let (endpoint, _) = mock_service("");
+        let dir = TempDir::new().unwrap();
+
+        let verdicts = answered(client(&endpoint, &dir).lookup(&hashes(&[&BAD.to_uppercase(), GOOD, NEW])));
+        assert!(verdicts[BAD].is_malicious());
+        assert_eq!(verdicts[GOOD].verdict, Verdict::Clean);
+        assert_eq!(verdicts[NEW].verdict, Verdict::Unknown);
+
+        let signature = verdicts[BAD].to_signature();
+        assert_eq!(signature.name, "Reputation.Malicious.aaaa00000000");
+        assert_eq!(signature.category, ThreatCategory::Ransomware);
+        assert_eq!(signature.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn queries_each_hash_once_in_batches() {
+        let (endpoint, requests) = mock_service("");
+        let dir = TempDir::new().unwrap();
+
+        client(&endpoint, &dir).lookup(&hashes(&[BAD, GOOD, BAD, &GOOD.to_uppercase(), NEW]));
+        let requests = requests.lock().unwrap();
+        assert_eq!(*requests, vec![hashes(&[BAD, GOOD]), hashes(&[NEW])]);
+    }
+
+    #[test]
+    fn cached_verdicts_survive_restart_but_unknown_expires_early() {
+        let (endpoint, requests) = mock_service("");
+        let dir = TempDir::new().unwrap();
+
+        client(&endpoint, &dir).lookup(&hashes(&[BAD, GOOD, NEW]));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        let verdicts = answered(client(&endpoint, &dir).lookup(&hashes(&[BAD, GOOD, NEW])));
+        assert_eq!(verdicts.len(), 3);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        // Once the unknown answer is older than its shorter TTL only that hash is asked again
+        let mut later = client(&endpoint, &dir);
+        later.config.unknown_ttl_secs = 0;
+        let verdicts = answered(later.lookup(&hashes(&[BAD, NEW])));
+        assert!(verdicts[BAD].is_malicious());
+        assert_eq!(requests.lock().unwrap().len(), 3);
+        assert_eq!(requests.lock().unwrap()[2], hashes(&[NEW]));
+    }
+
+    #[test]
+    fn goes_offline_after_the_first_failure() {
+        // Nothing listens on a port that was just released
+        let endpoint = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/v1/lookup", listener.local_addr().unwrap())
+        };
+        let dir = TempDir::new().unwrap();
+        let mut client = client(&endpoint, &dir);
+
+        assert!(answered(client.lookup(&hashes(&[BAD, GOOD, NEW]))).is_empty());
+        assert!(client.offline);
+    }
+
+    #[test]
+    fn malformed_response_and_cache_are_tolerated() {
+        let (endpoint, requests) = mock_service("{\"verdicts\": \"nope\"}");
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("reputation-cache.json"), "not json").unwrap();
+
+        let mut client = client(&endpoint, &dir);
+        assert!(answered(client.lookup(&hashes(&[BAD, GOOD, NEW]))).is_empty());
+        // The first bad answer stops further batches
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(client.cache.is_empty());
+    }
+
+    #[test]
+    fn default_config_makes_no_network_call() {
+        let (endpoint, requests) = mock_service("");
+        let dir = TempDir::new().unwrap();
+        let cache_path = dir.path().join("reputation-cache.json");
+
+        let mut client = ReputationClient::new(ReputationConfig::default(), &cache_path).unwrap();
+        assert!(matches!(client.lookup(&hashes(&[BAD, GOOD])), ReputationOutcome::Disabled));
+        assert!(!cache_path.exists());
+
+        // An endpoint alone does not turn lookups on
+        let config = ReputationConfig {
+            endpoint,
+            ..ReputationConfig::default()
+        };
+        let mut client = ReputationClient::new(config, &cache_path).unwrap();
+        assert!(matches!(client.lookup(&hashes(&[BAD, GOOD])), ReputationOutcome::Disabled));
+        assert!(requests.lock().unwrap().is_empty());
+    }
+}
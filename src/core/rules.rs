@@ -0,0 +1,1286 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use regex::bytes::{Regex, RegexBuilder};
+
+use super::elf::ElfInfo;
+use super::{Severity, ThreatCategory, ThreatSignature};
+
+// ==================== KURAL MOTORU (YARA ALT KÜMESİ) ====================
+//
+// Supported subset:
+//   rule Name : tag1 tag2 {
+//       meta:
+//           category = "Trojan"
+//           severity = "Critical"
+//       strings:
+//           $mz = { 4D 5A ?? [2-4] 50 45 }
+//           $txt = "stratum+tcp://" nocase wide
+//           $re = /bash -i >& \/dev\/tcp\//
+//       condition:
+//           $mz at 0 and (2 of them or #txt > 3) and filesize < 1MB
+//   }
+//
+// ELF predicates (false for other files):
+//   elf.imports("ptrace")  elf.needs("libcurl.so.4")  elf.static  elf.stripped  elf.packed  elf.rpath
+
+const MAX_STRING_HITS: usize = 1000;
+// Shorter required literals would let nearly every file through the prefilter
+const MIN_LITERAL_LEN: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    StringId(String),
+    CountId(String),
+    Int(u64),
+    Text(Vec<u8>),
+    Hex(String),
+    Regex(String, String),
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Colon,
+    Comma,
+    Assign,
+    DotDot,
+    Plus,
+    Minus,
+    Cmp(CmpOp),
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Int(u64),
+    Filesize,
+    Count(usize),
+    Add(Box<Value>, Box<Value>),
+    Sub(Box<Value>, Box<Value>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Quantifier {
+    Any,
+    All,
+    AtLeast(usize),
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Bool(bool),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+    Matched(usize),
+    At(usize, Value),
+    In(usize, Value, Value),
+    Compare(Value, CmpOp, Value),
+    Of(Quantifier, Vec<usize>),
+    Magic(Vec<u8>),
+    Elf(ElfPredicate),
+}
+
+#[derive(Debug, Clone)]
+enum ElfPredicate {
+    Imports(String),
+    Needs(String),
+    Static,
+    Stripped,
+    Packed,
+    Rpath,
+}
+
+// Where a programmatically built body signature has to start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyOffset {
+    Any,
+    At(u64),
+    Within(u64, u64),
+    FromEnd(u64),
+    FromEndWithin(u64, u64),
+}
+
+#[derive(Debug, Clone)]
+struct RuleString {
+    id: String,
+    regex: Regex,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub signature: ThreatSignature,
+    pub tags: Vec<String>,
+    strings: Vec<RuleString>,
+    condition: Condition,
+    // Bytes every match has to contain, used to skip the rule for files without them
+    literal: Option<Vec<u8>>,
+}
+
+struct ScanContext<'a> {
+    data: &'a [u8],
+    strings: &'a [RuleString],
+    // Filled on first use, so strings only tested at a fixed offset are never searched in full
+    hits: Vec<OnceCell<Vec<u64>>>,
+    filesize: u64,
+    elf: Option<&'a ElfInfo>,
+}
+
+impl ScanContext<'_> {
+    fn hits(&self, i: usize) -> &[u64] {
+        self.hits[i].get_or_init(|| {
+            self.strings[i]
+                .regex
+                .find_iter(self.data)
+                .take(MAX_STRING_HITS)
+                .map(|m| m.start() as u64)
+                .collect()
+        })
+    }
+
+    // Leftmost match starting at or after `from`, which is exact even where matches overlap
+    fn first_hit_from(&self, i: usize, from: u64) -> Option<u64> {
+        let from = usize::try_from(from).ok().filter(|&from| from <= self.data.len())?;
+        self.strings[i].regex.find_at(self.data, from).map(|m| m.start() as u64)
+    }
+}
+
+impl Rule {
+    // Builds a single-string rule from a hex body, as used by imported databases
+    pub fn from_hex(
+        signature: ThreatSignature,
+        hex: &str,
+        offset: BodyOffset,
+        magic: Option<&[u8]>,
+    ) -> Result<Rule> {
+        let regex = build_regex(&compile_hex(hex, 1)?, false, 1)?;
+        let literal = longest_literal(hex).filter(|literal| literal.len() >= MIN_LITERAL_LEN);
+        let strings = vec![RuleString {
+            id: "body".to_string(),
+            regex,
+        }];
+
+        let from_end = |n: u64| Value::Sub(Box::new(Value::Filesize), Box::new(Value::Int(n)));
+        let placement = match offset {
+            BodyOffset::Any => Condition::Matched(0),
+            BodyOffset::At(n) => Condition::At(0, Value::Int(n)),
+            BodyOffset::Within(n, shift) => {
+                let end = n.checked_add(shift).ok_or_else(|| anyhow!("body offset {}+{} overflows", n, shift))?;
+                Condition::In(0, Value::Int(n), Value::Int(end))
+            }
+            BodyOffset::FromEnd(n) => Condition::At(0, from_end(n)),
+            BodyOffset::FromEndWithin(n, shift) => Condition::In(
+                0,
+                from_end(n),
+                Value::Add(Box::new(from_end(n)), Box::new(Value::Int(shift))),
+            ),
+        };
+
+        let condition = match magic {
+            Some(magic) => Condition::And(vec![Condition::Magic(magic.to_vec()), placement]),
+            None => placement,
+        };
+
+        Ok(Rule {
+            signature,
+            tags: Vec::new(),
+            strings,
+            condition,
+            literal,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.signature.name
+    }
+
+    pub fn required_literal(&self) -> Option<&[u8]> {
+        self.literal.as_deref()
+    }
+
+    // Returns the offset of the earliest string hit (0 for string-less rules) when the rule matches
+    pub fn evaluate(&self, data: &[u8], filesize: u64, elf: Option<&ElfInfo>) -> Option<u64> {
+        let ctx = ScanContext {
+            data,
+            strings: &self.strings,
+            hits: self.strings.iter().map(|_| OnceCell::new()).collect(),
+            filesize,
+            elf,
+        };
+
+        if !self.condition.eval(&ctx) {
+            return None;
+        }
+
+        Some(
+            (0..self.strings.len())
+                .filter_map(|i| ctx.hits(i).first().copied())
+                .min()
+                .unwrap_or(0),
+        )
+    }
+}
+
+impl Condition {
+    fn eval(&self, ctx: &ScanContext) -> bool {
+        match self {
+            Condition::Bool(b) => *b,
+            Condition::And(items) => items.iter().all(|c| c.eval(ctx)),
+            Condition::Or(items) => items.iter().any(|c| c.eval(ctx)),
+            Condition::Not(inner) => !inner.eval(ctx),
+            Condition::Matched(i) => !ctx.hits(*i).is_empty(),
+            Condition::At(i, offset) => {
+                let offset = offset.eval(ctx);
+                ctx.first_hit_from(*i, offset) == Some(offset)
+            }
+            Condition::In(i, from, to) => {
+                let (from, to) = (from.eval(ctx), to.eval(ctx));
+                ctx.first_hit_from(*i, from).is_some_and(|o| o <= to)
+            }
+            Condition::Magic(magic) => ctx.data.starts_with(magic),
+            Condition::Elf(predicate) => ctx.elf.is_some_and(|elf| match predicate {
+                ElfPredicate::Imports(name) => elf.imports_symbol(name),
+                ElfPredicate::Needs(library) => elf.needs_library(library),
+                ElfPredicate::Static => elf.is_static,
+                ElfPredicate::Stripped => elf.stripped,
+                ElfPredicate::Packed => elf.is_packed(),
+                ElfPredicate::Rpath => elf.rpath.is_some() || elf.runpath.is_some(),
+            }),
+            Condition::Compare(lhs, op, rhs) => {
+                let (l, r) = (lhs.eval(ctx), rhs.eval(ctx));
+                match op {
+                    CmpOp::Lt => l < r,
+                    CmpOp::Le => l <= r,
+                    CmpOp::Gt => l > r,
+                    CmpOp::Ge => l >= r,
+                    CmpOp::Eq => l == r,
+                    CmpOp::Ne => l != r,
+                }
+            }
+            Condition::Of(quantifier, set) => {
+                let matched = set.iter().filter(|i| !ctx.hits(**i).is_empty()).count();
+                match quantifier {
+                    Quantifier::Any => matched >= 1,
+                    Quantifier::All => matched == set.len(),
+                    Quantifier::AtLeast(n) => matched >= *n,
+                }
+            }
+        }
+    }
+}
+
+impl Value {
+    fn eval(&self, ctx: &ScanContext) -> u64 {
+        match self {
+            Value::Int(n) => *n,
+            Value::Filesize => ctx.filesize,
+            Value::Count(i) => ctx.hits(*i).len() as u64,
+            Value::Add(lhs, rhs) => lhs.eval(ctx).saturating_add(rhs.eval(ctx)),
+            Value::Sub(lhs, rhs) => lhs.eval(ctx).saturating_sub(rhs.eval(ctx)),
+        }
+    }
+}
+
+pub fn parse_rules(source: &str) -> Result<Vec<Rule>> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let mut rules = Vec::new();
+
+    while !parser.at_end() {
+        rules.push(parser.parse_rule()?);
+    }
+
+    Ok(rules)
+}
+
+// ==================== SÖZCÜK ANALİZİ ====================
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<(Token, usize)> = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let after_assign = matches!(tokens.last(), Some((Token::Assign, _)));
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') && !after_assign {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') && !after_assign {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '{' && after_assign {
+            let start = i + 1;
+            while i < chars.len() && chars[i] != '}' {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            if i >= chars.len() {
+                bail!("line {}: unterminated hex string", line);
+            }
+            tokens.push((Token::Hex(chars[start..i].iter().collect()), line));
+            i += 1;
+        } else if c == '/' && after_assign {
+            let mut pattern = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '/' {
+                if chars[i] == '\\' && chars.get(i + 1) == Some(&'/') {
+                    pattern.push('/');
+                    i += 2;
+                    continue;
+                }
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    pattern.push(chars[i]);
+                    i += 1;
+                }
+                if chars[i] == '\n' {
+                    bail!("line {}: unterminated regular expression", line);
+                }
+                pattern.push(chars[i]);
+                i += 1;
+            }
+            if i >= chars.len() {
+                bail!("line {}: unterminated regular expression", line);
+            }
+            i += 1;
+            let mut flags = String::new();
+            while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                flags.push(chars[i]);
+                i += 1;
+            }
+            tokens.push((Token::Regex(pattern, flags), line));
+        } else if c == '"' {
+            let (text, next) = lex_text(&chars, i + 1, line)?;
+            tokens.push((Token::Text(text), line));
+            i = next;
+        } else if c == '$' || c == '#' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '*') {
+                i += 1;
+            }
+            let id: String = chars[start..i].iter().collect();
+            let token = if c == '$' {
+                Token::StringId(id)
+            } else {
+                Token::CountId(id)
+            };
+            tokens.push((token, line));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            tokens.push((Token::Int(parse_int(&literal, line)?), line));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+        } else {
+            let next = chars.get(i + 1).copied();
+            let (token, width) = match (c, next) {
+                ('.', Some('.')) => (Token::DotDot, 2),
+                ('<', Some('=')) => (Token::Cmp(CmpOp::Le), 2),
+                ('>', Some('=')) => (Token::Cmp(CmpOp::Ge), 2),
+                ('=', Some('=')) => (Token::Cmp(CmpOp::Eq), 2),
+                ('!', Some('=')) => (Token::Cmp(CmpOp::Ne), 2),
+                ('<', _) => (Token::Cmp(CmpOp::Lt), 1),
+                ('>', _) => (Token::Cmp(CmpOp::Gt), 1),
+                ('=', _) => (Token::Assign, 1),
+                ('+', _) => (Token::Plus, 1),
+                ('-', _) => (Token::Minus, 1),
+                ('{', _) => (Token::LBrace, 1),
+                ('}', _) => (Token::RBrace, 1),
+                ('(', _) => (Token::LParen, 1),
+                (')', _) => (Token::RParen, 1),
+                (':', _) => (Token::Colon, 1),
+                (',', _) => (Token::Comma, 1),
+                _ => bail!("line {}: unexpected character {:?}", line, c),
+            };
+            tokens.push((token, line));
+            i += width;
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn lex_text(chars: &[char], mut i: usize, line: usize) -> Result<(Vec<u8>, usize)> {
+    let mut text = Vec::new();
+
+    while i < chars.len() && chars[i] != '"' {
+        if chars[i] == '\n' {
+            bail!("line {}: unterminated string", line);
+        }
+        if chars[i] == '\\' {
+            i += 1;
+            match chars.get(i) {
+                Some('n') => text.push(b'\n'),
+                Some('r') => text.push(b'\r'),
+                Some('t') => text.push(b'\t'),
+                Some('\\') => text.push(b'\\'),
+                Some('"') => text.push(b'"'),
+                Some('x') => {
+                    let hex: String = chars.iter().skip(i + 1).take(2).collect();
+                    let byte = u8::from_str_radix(&hex, 16)
+                        .map_err(|_| anyhow!("line {}: invalid \\x escape", line))?;
+                    text.push(byte);
+                    i += 2;
+                }
+                other => bail!("line {}: invalid escape {:?}", line, other),
+            }
+            i += 1;
+            continue;
+        }
+        let mut buf = [0u8; 4];
+        text.extend_from_slice(chars[i].encode_utf8(&mut buf).as_bytes());
+        i += 1;
+    }
+
+    if i >= chars.len() {
+        bail!("line {}: unterminated string", line);
+    }
+
+    Ok((text, i + 1))
+}
+
+fn parse_int(literal: &str, line: usize) -> Result<u64> {
+    let lower = literal.to_ascii_lowercase();
+    let (digits, multiplier) = if let Some(d) = lower.strip_suffix("kb") {
+        (d, 1024)
+    } else if let Some(d) = lower.strip_suffix("mb") {
+        (d, 1024 * 1024)
+    } else {
+        (lower.as_str(), 1)
+    };
+
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else {
+        digits.parse::<u64>()
+    };
+
+    value
+        .ok()
+        .and_then(|v| v.checked_mul(multiplier))
+        .ok_or_else(|| anyhow!("line {}: invalid number {:?}", line, literal))
+}
+
+// ==================== AYRIŞTIRICI ====================
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|(_, l)| *l)
+            .unwrap_or(0)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(t, _)| t.clone())
+            .ok_or_else(|| anyhow!("unexpected end of rules"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let line = self.line();
+        let token = self.next()?;
+        if token != expected {
+            bail!("line {}: expected {:?}, found {:?}", line, expected, token);
+        }
+        Ok(())
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(k)) if k == keyword)
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if !self.is_keyword(keyword) {
+            bail!("line {}: expected '{}'", self.line(), keyword);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            other => bail!("line {}: expected identifier, found {:?}", line, other),
+        }
+    }
+
+    fn parse_rule(&mut self) -> Result<Rule> {
+        while self.is_keyword("private") || self.is_keyword("global") {
+            self.pos += 1;
+        }
+        self.expect_keyword("rule")?;
+        let name = self.ident()?;
+
+        let mut tags = Vec::new();
+        if self.peek() == Some(&Token::Colon) {
+            self.pos += 1;
+            while let Some(Token::Ident(_)) = self.peek() {
+                tags.push(self.ident()?);
+            }
+        }
+
+        self.expect(Token::LBrace)?;
+
+        let mut category = ThreatCategory::Unknown;
+        let mut severity = Severity::Warning;
+        if self.is_keyword("meta") {
+            self.pos += 1;
+            self.expect(Token::Colon)?;
+            while matches!(self.peek(), Some(Token::Ident(k)) if k != "strings" && k != "condition") {
+                let key = self.ident()?;
+                self.expect(Token::Assign)?;
+                let value = match self.next()? {
+                    Token::Text(text) => String::from_utf8_lossy(&text).to_string(),
+                    Token::Int(n) => n.to_string(),
+                    Token::Ident(word) => word,
+                    other => bail!("line {}: invalid meta value {:?}", self.line(), other),
+                };
+                match key.as_str() {
+                    "category" => category = parse_category(&value),
+                    "severity" => severity = parse_severity(&value),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut strings = Vec::new();
+        if self.is_keyword("strings") {
+            self.pos += 1;
+            self.expect(Token::Colon)?;
+            while let Some(Token::StringId(_)) = self.peek() {
+                strings.push(self.parse_string()?);
+            }
+        }
+
+        self.expect_keyword("condition")?;
+        self.expect(Token::Colon)?;
+        let condition = self.parse_or(&strings)?;
+        self.expect(Token::RBrace)?;
+
+        Ok(Rule {
+            signature: ThreatSignature {
+                name,
+                pattern: Vec::new(),
+                category,
+                severity,
+            },
+            tags,
+            strings,
+            condition,
+            literal: None,
+        })
+    }
+
+    fn parse_string(&mut self) -> Result<RuleString> {
+        let line = self.line();
+        let id = match self.next()? {
+            Token::StringId(id) => id,
+            other => bail!("line {}: expected string identifier, found {:?}", line, other),
+        };
+        self.expect(Token::Assign)?;
+
+        let regex = match self.next()? {
+            Token::Hex(body) => build_regex(&compile_hex(&body, line)?, false, line)?,
+            Token::Text(text) => {
+                let (nocase, ascii, wide) = self.parse_modifiers()?;
+                let mut alternatives = Vec::new();
+                if ascii || !wide {
+                    alternatives.push(escape_bytes(&text));
+                }
+                if wide {
+                    let widened: Vec<u8> = text.iter().flat_map(|&b| [b, 0]).collect();
+                    alternatives.push(escape_bytes(&widened));
+                }
+                build_regex(&format!("(?:{})", alternatives.join("|")), nocase, line)?
+            }
+            Token::Regex(pattern, flags) => {
+                let (nocase, _, _) = self.parse_modifiers()?;
+                let dot_all = if flags.contains('s') { "(?s)" } else { "" };
+                build_regex(
+                    &format!("{}{}", dot_all, pattern),
+                    nocase || flags.contains('i'),
+                    line,
+                )?
+            }
+            other => bail!("line {}: invalid string value {:?}", line, other),
+        };
+
+        Ok(RuleString { id, regex })
+    }
+
+    fn parse_modifiers(&mut self) -> Result<(bool, bool, bool)> {
+        let (mut nocase, mut ascii, mut wide) = (false, false, false);
+        while let Some(Token::Ident(modifier)) = self.peek() {
+            match modifier.as_str() {
+                "nocase" => nocase = true,
+                "ascii" => ascii = true,
+                "wide" => wide = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        Ok((nocase, ascii, wide))
+    }
+
+    fn parse_or(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        let mut items = vec![self.parse_and(strings)?];
+        while self.is_keyword("or") {
+            self.pos += 1;
+            items.push(self.parse_and(strings)?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Condition::Or(items) })
+    }
+
+    fn parse_and(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        let mut items = vec![self.parse_not(strings)?];
+        while self.is_keyword("and") {
+            self.pos += 1;
+            items.push(self.parse_not(strings)?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Condition::And(items) })
+    }
+
+    fn parse_not(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        if self.is_keyword("not") {
+            self.pos += 1;
+            return Ok(Condition::Not(Box::new(self.parse_not(strings)?)));
+        }
+        self.parse_primary(strings)
+    }
+
+    fn parse_primary(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        let line = self.line();
+
+        // "N of ..." has to be told apart from "N < filesize"
+        let quantifier = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Int(n)), Some(Token::Ident(k))) if k == "of" => Some(Quantifier::AtLeast(*n as usize)),
+            (Some(Token::Ident(k)), _) if k == "any" => Some(Quantifier::Any),
+            (Some(Token::Ident(k)), _) if k == "all" => Some(Quantifier::All),
+            _ => None,
+        };
+        if let Some(quantifier) = quantifier {
+            self.pos += 1;
+            self.expect_keyword("of")?;
+            let set = self.parse_string_set(strings)?;
+            return Ok(Condition::Of(quantifier, set));
+        }
+
+        match self.peek().cloned() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let inner = self.parse_or(strings)?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Ident(k)) if k == "true" || k == "false" => {
+                self.pos += 1;
+                Ok(Condition::Bool(k == "true"))
+            }
+            Some(Token::Ident(k)) if k.starts_with("elf.") => {
+                self.pos += 1;
+                Ok(Condition::Elf(self.parse_elf_predicate(&k, line)?))
+            }
+            Some(Token::StringId(id)) => {
+                self.pos += 1;
+                let index = resolve_string(strings, &id, line)?;
+                if self.is_keyword("at") {
+                    self.pos += 1;
+                    let offset = self.parse_value(strings)?;
+                    Ok(Condition::At(index, offset))
+                } else if self.is_keyword("in") {
+                    self.pos += 1;
+                    self.expect(Token::LParen)?;
+                    let from = self.parse_value(strings)?;
+                    self.expect(Token::DotDot)?;
+                    let to = self.parse_value(strings)?;
+                    self.expect(Token::RParen)?;
+                    Ok(Condition::In(index, from, to))
+                } else {
+                    Ok(Condition::Matched(index))
+                }
+            }
+            _ => {
+                let lhs = self.parse_value(strings)?;
+                let op = match self.next()? {
+                    Token::Cmp(op) => op,
+                    other => bail!("line {}: expected comparison, found {:?}", line, other),
+                };
+                let rhs = self.parse_value(strings)?;
+                Ok(Condition::Compare(lhs, op, rhs))
+            }
+        }
+    }
+
+    fn parse_elf_predicate(&mut self, name: &str, line: usize) -> Result<ElfPredicate> {
+        let mut argument = || -> Result<String> {
+            self.expect(Token::LParen)?;
+            let value = match self.next()? {
+                Token::Text(text) => String::from_utf8_lossy(&text).to_string(),
+                other => bail!("line {}: expected string argument, found {:?}", line, other),
+            };
+            self.expect(Token::RParen)?;
+            Ok(value)
+        };
+
+        Ok(match name {
+            "elf.imports" => ElfPredicate::Imports(argument()?),
+            "elf.needs" => ElfPredicate::Needs(argument()?),
+            "elf.static" => ElfPredicate::Static,
+            "elf.stripped" => ElfPredicate::Stripped,
+            "elf.packed" => ElfPredicate::Packed,
+            "elf.rpath" => ElfPredicate::Rpath,
+            _ => bail!("line {}: unknown ELF predicate {}", line, name),
+        })
+    }
+
+    fn parse_value(&mut self, strings: &[RuleString]) -> Result<Value> {
+        let mut value = self.parse_term(strings)?;
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.pos += 1;
+                    value = Value::Add(Box::new(value), Box::new(self.parse_term(strings)?));
+                }
+                Some(Token::Minus) => {
+                    self.pos += 1;
+                    value = Value::Sub(Box::new(value), Box::new(self.parse_term(strings)?));
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn parse_term(&mut self, strings: &[RuleString]) -> Result<Value> {
+        let line = self.line();
+        match self.next()? {
+            Token::Int(n) => Ok(Value::Int(n)),
+            Token::Ident(k) if k == "filesize" => Ok(Value::Filesize),
+            Token::CountId(id) => Ok(Value::Count(resolve_string(strings, &id, line)?)),
+            other => bail!("line {}: unexpected {:?} in condition", line, other),
+        }
+    }
+
+    fn parse_string_set(&mut self, strings: &[RuleString]) -> Result<Vec<usize>> {
+        let line = self.line();
+        if self.is_keyword("them") {
+            self.pos += 1;
+            return Ok((0..strings.len()).collect());
+        }
+
+        self.expect(Token::LParen)?;
+        let mut set = Vec::new();
+        loop {
+            match self.next()? {
+                Token::StringId(id) => match id.strip_suffix('*') {
+                    Some(prefix) => set.extend(
+                        strings
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, s)| s.id.starts_with(prefix))
+                            .map(|(i, _)| i),
+                    ),
+                    None => set.push(resolve_string(strings, &id, line)?),
+                },
+                other => bail!("line {}: expected string identifier, found {:?}", line, other),
+            }
+            match self.next()? {
+                Token::Comma => continue,
+                Token::RParen => break,
+                other => bail!("line {}: expected ',' or ')', found {:?}", line, other),
+            }
+        }
+        Ok(set)
+    }
+}
+
+fn resolve_string(strings: &[RuleString], id: &str, line: usize) -> Result<usize> {
+    strings
+        .iter()
+        .position(|s| s.id == id)
+        .ok_or_else(|| anyhow!("line {}: undefined string ${}", line, id))
+}
+
+pub(crate) fn parse_category(value: &str) -> ThreatCategory {
+    match value.to_ascii_lowercase().as_str() {
+        "ransomware" => ThreatCategory::Ransomware,
+        "trojan" => ThreatCategory::Trojan,
+        "spyware" => ThreatCategory::Spyware,
+        "adware" => ThreatCategory::Adware,
+        "rootkit" => ThreatCategory::Rootkit,
+        "worm" => ThreatCategory::Worm,
+        "virus" => ThreatCategory::Virus,
+        "pup" => ThreatCategory::PUP,
+        "activecontent" | "active_content" => ThreatCategory::ActiveContent,
+        _ => ThreatCategory::Unknown,
+    }
+}
+
+fn parse_severity(value: &str) -> Severity {
+    match value.to_ascii_lowercase().as_str() {
+        "ok" => Severity::Ok,
+        "info" | "low" => Severity::Info,
+        "critical" | "high" => Severity::Critical,
+        _ => Severity::Warning,
+    }
+}
+
+// ==================== DESEN DERLEME ====================
+
+fn escape_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("\\x{:02x}", b)).collect()
+}
+
+fn build_regex(pattern: &str, nocase: bool, line: usize) -> Result<Regex> {
+    RegexBuilder::new(pattern)
+        .unicode(false)
+        .case_insensitive(nocase)
+        .build()
+        .map_err(|e| anyhow!("line {}: invalid pattern: {}", line, e))
+}
+
+// Translates a hex string body such as "4D 5A ?? [2-4] (50 | 51) ~00 4?" into a byte regex
+pub(crate) fn compile_hex(body: &str, line: usize) -> Result<String> {
+    let chars: Vec<char> = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut out = String::from("(?s)");
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|p| i + p)
+                    .ok_or_else(|| anyhow!("line {}: unterminated jump", line))?;
+                let range: String = chars[i + 1..end].iter().collect();
+                out.push_str(&compile_jump(&range, line)?);
+                i = end + 1;
+            }
+            '(' => {
+                out.push_str("(?:");
+                i += 1;
+            }
+            '|' => {
+                out.push('|');
+                i += 1;
+            }
+            ')' => {
+                out.push(')');
+                i += 1;
+            }
+            '~' => {
+                let byte = hex_pair(&chars, i + 1, line)?;
+                match byte {
+                    (Some(hi), Some(lo)) => out.push_str(&format!("[^\\x{:x}{:x}]", hi, lo)),
+                    _ => bail!("line {}: '~' needs a full byte", line),
+                }
+                i += 3;
+            }
+            _ => {
+                let byte = hex_pair(&chars, i, line)?;
+                out.push_str(&match byte {
+                    (Some(hi), Some(lo)) => format!("\\x{:x}{:x}", hi, lo),
+                    (Some(hi), None) => format!("[\\x{:x}0-\\x{:x}f]", hi, hi),
+                    (None, Some(lo)) => {
+                        let class: String = (0..16).map(|hi| format!("\\x{:x}{:x}", hi, lo)).collect();
+                        format!("[{}]", class)
+                    }
+                    (None, None) => ".".to_string(),
+                });
+                i += 2;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+// Longest run of fixed bytes outside alternatives in a hex string body; any match contains it
+fn longest_literal(body: &str) -> Option<Vec<u8>> {
+    let chars: Vec<char> = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut runs: Vec<Vec<u8>> = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let width = match chars[i] {
+            '[' => chars[i..].iter().position(|&c| c == ']').map_or(chars.len() - i, |end| end + 1),
+            '(' => {
+                depth += 1;
+                1
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                1
+            }
+            '|' => 1,
+            '~' => 3,
+            _ => {
+                let nibbles = (chars[i].to_digit(16), chars.get(i + 1).and_then(|c| c.to_digit(16)));
+                if let (Some(hi), Some(lo), 0, Some(run)) = (nibbles.0, nibbles.1, depth, runs.last_mut()) {
+                    run.push((hi * 16 + lo) as u8);
+                    i += 2;
+                    continue;
+                }
+                2
+            }
+        };
+        runs.push(Vec::new());
+        i += width;
+    }
+
+    runs.into_iter().filter(|run| !run.is_empty()).max_by_key(|run| run.len())
+}
+
+fn hex_pair(chars: &[char], i: usize, line: usize) -> Result<(Option<u32>, Option<u32>)> {
+    let nibble = |c: Option<&char>| -> Result<Option<u32>> {
+        match c {
+            Some('?') => Ok(None),
+            Some(c) => c
+                .to_digit(16)
+                .map(Some)
+                .ok_or_else(|| anyhow!("line {}: invalid hex digit {:?}", line, c)),
+            None => bail!("line {}: truncated hex byte", line),
+        }
+    };
+    Ok((nibble(chars.get(i))?, nibble(chars.get(i + 1))?))
+}
+
+fn compile_jump(range: &str, line: usize) -> Result<String> {
+    let parse = |s: &str| -> Result<Option<usize>> {
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            s.parse()
+                .map(Some)
+                .map_err(|_| anyhow!("line {}: invalid jump [{}]", line, range))
+        }
+    };
+
+    Ok(match range.split_once('-') {
+        None => format!(".{{{}}}", parse(range)?.unwrap_or(0)),
+        Some((from, to)) => match (parse(from)?.unwrap_or(0), parse(to)?) {
+            (from, Some(to)) => format!(".{{{},{}}}?", from, to),
+            (from, None) => format!(".{{{},}}?", from),
+        },
+    })
+}
+
+pub fn load_rules_from_dir(dir: &Path) -> Vec<Rule> {
+    let mut files: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .map(|ext| ext == "yar" || ext == "yara")
+                    .unwrap_or(false)
+            })
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    let mut rules: Vec<Rule> = Vec::new();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+
+    for path in files {
+        let parsed = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|source| parse_rules(&source));
+
+        match parsed {
+            Ok(parsed) => {
+                for rule in parsed {
+                    // A later file redefining a rule replaces the earlier one
+                    match by_name.get(rule.name()) {
+                        Some(&i) => rules[i] = rule,
+                        None => {
+                            by_name.insert(rule.name().to_string(), rules.len());
+                            rules.push(rule);
+                        }
+                    }
+                }
+            }
+            Err(e) => log::warn!("Skipping rule file {:?}: {}", path, e),
+        }
+    }
+
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(source: &str) -> Rule {
+        let mut rules = parse_rules(source).unwrap();
+        assert_eq!(rules.len(), 1);
+        rules.remove(0)
+    }
+
+    fn matches(rule: &Rule, data: &[u8]) -> Option<u64> {
+        rule.evaluate(data, data.len() as u64, None)
+    }
+
+    #[test]
+    fn parses_meta_tags_and_strings() {
+        let rule = rule(
+            r#"
+            // comment
+            private rule Miner : linux pup {
+                meta:
+                    category = "PUP"
+                    severity = "Critical"
+                    author = "someone"
+                strings:
+                    $a = "stratum+tcp://" nocase
+                    /* block
+                       comment */
+                condition:
+                    $a
+            }
+            "#,
+        );
+
+        assert_eq!(rule.name(), "Miner");
+        assert_eq!(rule.tags, vec!["linux", "pup"]);
+        assert_eq!(rule.signature.category, ThreatCategory::PUP);
+        assert_eq!(rule.signature.severity, Severity::Critical);
+        assert_eq!(matches(&rule, b"pool: STRATUM+TCP://x"), Some(6));
+        assert_eq!(matches(&rule, b"nothing here"), None);
+    }
+
+    #[test]
+    fn wide_strings_match_utf16() {
+        let rule = rule(r#"rule W { strings: $a = "cmd" wide ascii condition: $a }"#);
+
+        assert!(matches(&rule, b"c\0m\0d\0").is_some());
+        assert!(matches(&rule, b"cmd").is_some());
+        assert!(matches(&rule, b"c m d").is_none());
+    }
+
+    #[test]
+    fn hex_strings_support_wildcards_jumps_and_alternatives() {
+        let rule = rule(r#"rule H { strings: $h = { 4D 5A ?? [2-4] (50 | 51) ~00 4? } condition: $h at 0 }"#);
+
+        assert_eq!(matches(&rule, &[0x4d, 0x5a, 0x00, 1, 2, 0x51, 0x01, 0x4f]), Some(0));
+        assert_eq!(matches(&rule, &[0x4d, 0x5a, 0x00, 1, 2, 0x51, 0x00, 0x4f]), None);
+        assert_eq!(matches(&rule, &[0x4d, 0x5a, 0x00, 1, 0x50, 0x01, 0x40]), None);
+        assert_eq!(matches(&rule, &[0x00, 0x4d, 0x5a, 0x00, 1, 2, 0x50, 0x01, 0x40]), None);
+    }
+
+    #[test]
+    fn regex_strings_and_counts() {
+        let rule = rule(r#"rule R { strings: $ip = /[0-9]+\.[0-9]+/ condition: #ip >= 2 and #ip < 4 }"#);
+
+        assert!(matches(&rule, b"1.2 3.4").is_some());
+        assert!(matches(&rule, b"1.2").is_none());
+        assert!(matches(&rule, b"1.2 3.4 5.6 7.8").is_none());
+    }
+
+    #[test]
+    fn quantifiers_over_string_sets() {
+        let source = r#"
+            rule Q {
+                strings:
+                    $a1 = "one"
+                    $a2 = "two"
+                    $b = "three"
+                condition:
+                    2 of ($a*) or all of them
+            }
+        "#;
+        let rule = rule(source);
+
+        assert!(matches(&rule, b"one two").is_some());
+        assert!(matches(&rule, b"one three").is_none());
+        assert!(matches(&rule, b"three").is_none());
+        let any = parse_rules(&source.replace("2 of ($a*) or all of them", "any of ($a1, $b)")).unwrap();
+        assert!(matches(&any[0], b"three").is_some());
+    }
+
+    #[test]
+    fn offsets_filesize_and_not() {
+        let rule = rule(
+            r#"rule O { strings: $a = "AB" condition: $a in (2..4) and not $a at 0 and filesize < 1KB }"#,
+        );
+
+        assert_eq!(matches(&rule, b"xxxAB"), Some(3));
+        assert_eq!(matches(&rule, b"ABxAB"), None);
+        assert_eq!(matches(&rule, b"xxxxxxAB"), None);
+        assert_eq!(rule.evaluate(b"xxxAB", 2048, None), None);
+    }
+
+    #[test]
+    fn string_less_rules_report_offset_zero() {
+        let rule = rule("rule Big { condition: filesize > 0x10 and true }");
+
+        assert_eq!(rule.evaluate(b"", 17, None), Some(0));
+        assert_eq!(rule.evaluate(b"", 16, None), None);
+    }
+
+    #[test]
+    fn size_suffixes_scale_numbers() {
+        assert_eq!(parse_int("2KB", 1).unwrap(), 2048);
+        assert_eq!(parse_int("1mb", 1).unwrap(), 1024 * 1024);
+        assert_eq!(parse_int("0x10", 1).unwrap(), 16);
+    }
+
+    #[test]
+    fn overflowing_numbers_are_errors() {
+        assert!(parse_int("0xffffffffffffffffkb", 1).is_err());
+        assert!(parse_int("99999999999999999999", 1).is_err());
+        assert!(parse_rules("rule X { condition: filesize < 0xffffffffffffffffmb }").is_err());
+    }
+
+    #[test]
+    fn malformed_rules_are_rejected() {
+        let broken = [
+            "rule",
+            "rule X {",
+            "rule X { condition: }",
+            "rule X { condition: $missing }",
+            "rule X { strings: $a = \"open condition: $a }",
+            "rule X { strings: $a = { 4D 5 } condition: $a }",
+            "rule X { strings: $a = { 4D [1- } condition: $a }",
+            "rule X { strings: $a = { 4G } condition: $a }",
+            "rule X { strings: $a = /unterminated condition: $a }",
+            "rule X { strings: $a = /(/ condition: $a }",
+            "rule X { strings: $a = \"\\q\" condition: $a }",
+            "rule X { condition: elf.unknown }",
+            "rule X { condition: 1 of ($a, }",
+            "rule X { condition: true } @",
+        ];
+        for source in broken {
+            assert!(parse_rules(source).is_err(), "accepted {:?}", source);
+        }
+    }
+
+    #[test]
+    fn later_rule_files_override_earlier_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.yar"), r#"rule Same { strings: $a = "old" condition: $a }"#).unwrap();
+        fs::write(dir.path().join("b.yara"), r#"rule Same { strings: $a = "new" condition: $a }"#).unwrap();
+        fs::write(dir.path().join("c.yar"), "rule Broken {").unwrap();
+        fs::write(dir.path().join("notes.txt"), "rule Ignored { condition: true }").unwrap();
+
+        let rules = load_rules_from_dir(dir.path());
+        assert_eq!(rules.len(), 1);
+        assert!(matches(&rules[0], b"new").is_some());
+        assert!(matches(&rules[0], b"old").is_none());
+    }
+
+    #[test]
+    fn body_offsets_place_hex_signatures() {
+        let signature = ThreatSignature {
+            name: "Body".to_string(),
+            pattern: Vec::new(),
+            category: ThreatCategory::Virus,
+            severity: Severity::Warning,
+        };
+        let at = Rule::from_hex(signature.clone(), "4142", BodyOffset::At(2), None).unwrap();
+        let tail = Rule::from_hex(signature.clone(), "4142", BodyOffset::FromEnd(2), None).unwrap();
+        let magic = Rule::from_hex(signature, "4142", BodyOffset::Any, Some(b"MZ")).unwrap();
+
+        assert!(matches(&at, b"xxAB").is_some());
+        assert!(matches(&at, b"xAB").is_none());
+        assert!(matches(&tail, b"xxxxAB").is_some());
+        assert!(matches(&tail, b"xxABxx").is_none());
+        assert!(matches(&magic, b"MZ..AB").is_some());
+        assert!(matches(&magic, b"..AB").is_none());
+    }
+
+    #[test]
+    fn anchored_strings_match_inside_overlapping_hits() {
+        let at = rule(r#"rule A { strings: $a = "AA" condition: $a at 1 }"#);
+        let within = rule(r#"rule I { strings: $a = "ABAB" condition: $a in (2..2) }"#);
+
+        // Non-overlapping search would only see the hits at 0 and 2
+        assert!(matches(&at, b"AAA").is_some());
+        assert!(matches(&within, b"ABABAB").is_some());
+        assert!(matches(&at, b"ABA").is_none());
+        assert!(rule(r#"rule F { strings: $a = "AA" condition: $a at 99 }"#).evaluate(b"AA", 2, None).is_none());
+    }
+
+    #[test]
+    fn hex_bodies_expose_their_longest_literal() {
+        assert_eq!(longest_literal("4D 5A ?? 50 45 00 00 [2-4] 41"), Some(vec![0x50, 0x45, 0x00, 0x00]));
+        assert_eq!(longest_literal("( 41 42 43 44 | 45 ) 46 47"), Some(vec![0x46, 0x47]));
+        assert_eq!(longest_literal("4? ~00 [10-20] 11"), Some(vec![0x11]));
+        assert_eq!(longest_literal("?? [-] ??"), None);
+    }
+
+    #[test]
+    fn overflowing_body_offsets_are_rejected() {
+        let signature = ThreatSignature {
+            name: "Overflow".to_string(),
+            pattern: Vec::new(),
+            category: ThreatCategory::Virus,
+            severity: Severity::Warning,
+        };
+
+        assert!(Rule::from_hex(signature, "414243", BodyOffset::Within(u64::MAX, 1), None).is_err());
+    }
+}
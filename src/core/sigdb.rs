@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::Engine;
+
+// ==================== İMZA VERİTABANI MANİFESTOSU ====================
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+// Last installed database version. It lives outside the signature directory, so deleting
+// the directory cannot quietly fall back to an older (or the built-in) database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledVersion {
+    pub version: u64,
+    // Set by a rollback; mirror updates leave the database alone until it is cleared
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl InstalledVersion {
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| anyhow!("Malformed version record: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureManifest {
+    pub version: u64,
+    pub built: DateTime<Local>,
+    // File name -> SHA-256 of its contents
+    pub files: BTreeMap<String, String>,
+}
+
+impl SignatureManifest {
+    pub fn build(dir: &Path, version: u64) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for name in database_files(dir)? {
+            files.insert(name.clone(), Engine::calculate_file_hash(&dir.join(&name))?);
+        }
+
+        Ok(SignatureManifest {
+            version,
+            built: Local::now(),
+            files,
+        })
+    }
+
+    pub fn read(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let manifest = serde_json::from_slice(&fs::read(&path)?)
+            .map_err(|e| anyhow!("Malformed signature manifest: {}", e))?;
+        Ok(Some(manifest))
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    // Every listed file must be present and unchanged, and nothing unlisted may sit beside them
+    pub fn verify(&self, dir: &Path) -> Result<()> {
+        for (name, expected) in &self.files {
+            let path = dir.join(name);
+            if !path.is_file() {
+                bail!("Signature file {} listed in the manifest is missing", name);
+            }
+            let actual = Engine::calculate_file_hash(&path)?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                bail!("Signature file {} does not match its manifest checksum", name);
+            }
+        }
+
+        for name in database_files(dir)? {
+            if !self.files.contains_key(&name) {
+                bail!("Signature file {} is not listed in the manifest", name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn database_files(dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    if !dir.exists() {
+        return Ok(names);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == MANIFEST_FILE || name.starts_with('.') || name.ends_with(".tmp") {
+            continue;
+        }
+        names.push(name);
+    }
+
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn manifest_round_trips_and_verifies() {
+        let dir = database(&[("main.ndb", "A:0:*:414243"), ("extra.yar", "rule X { condition: true }")]);
+        SignatureManifest::build(dir.path(), 7).unwrap().write(dir.path()).unwrap();
+
+        let manifest = SignatureManifest::read(dir.path()).unwrap().unwrap();
+        assert_eq!(manifest.version, 7);
+        assert_eq!(manifest.files.keys().collect::<Vec<_>>(), vec!["extra.yar", "main.ndb"]);
+        manifest.verify(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn verify_rejects_tampered_missing_and_unlisted_files() {
+        let dir = database(&[("main.ndb", "A:0:*:414243")]);
+        let manifest = SignatureManifest::build(dir.path(), 1).unwrap();
+
+        fs::write(dir.path().join("main.ndb"), "A:0:*:414244").unwrap();
+        assert!(manifest.verify(dir.path()).is_err());
+
+        fs::remove_file(dir.path().join("main.ndb")).unwrap();
+        assert!(manifest.verify(dir.path()).is_err());
+
+        let dir = database(&[("main.ndb", "A:0:*:414243")]);
+        let manifest = SignatureManifest::build(dir.path(), 1).unwrap();
+        fs::write(dir.path().join("planted.hdb"), "x").unwrap();
+        assert!(manifest.verify(dir.path()).is_err());
+    }
+
+    #[test]
+    fn manifest_is_optional_but_must_be_well_formed() {
+        let dir = database(&[]);
+        assert!(SignatureManifest::read(dir.path()).unwrap().is_none());
+
+        fs::write(dir.path().join(MANIFEST_FILE), "{\"version\": \"seven\"}").unwrap();
+        assert!(SignatureManifest::read(dir.path()).is_err());
+    }
+
+    #[test]
+    fn database_files_skip_manifest_hidden_and_temporary_files() {
+        let dir = database(&[(MANIFEST_FILE, "{}"), (".lock", ""), ("a.ndb.tmp", ""), ("b.hdb", "")]);
+        fs::create_dir(dir.path().join("history")).unwrap();
+
+        assert_eq!(database_files(dir.path()).unwrap(), vec!["b.hdb"]);
+        assert!(database_files(&dir.path().join("absent")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn installed_version_defaults_to_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("signature-version.json");
+
+        assert_eq!(InstalledVersion::load(&path).unwrap(), InstalledVersion::default());
+        InstalledVersion { version: 12, pinned: true }.save(&path).unwrap();
+        assert_eq!(InstalledVersion::load(&path).unwrap(), InstalledVersion { version: 12, pinned: true });
+
+        // Records written before pinning existed still load
+        fs::write(&path, "{\"version\": 3}").unwrap();
+        assert_eq!(InstalledVersion::load(&path).unwrap(), InstalledVersion { version: 3, pinned: false });
+
+        fs::write(&path, "garbage").unwrap();
+        assert!(InstalledVersion::load(&path).is_err());
+    }
+}
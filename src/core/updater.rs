@@ -0,0 +1,602 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::hashes::to_hex;
+use super::sigdb::{self, SignatureManifest};
+
+// ==================== İMZA GÜNCELLEME İSTEMCİSİ ====================
+//
+// Mirror layout:
+//   <mirror>/index.json       UpdateIndex
+//   <mirror>/<package url>    SignaturePackage (full or delta), checked against the index digest
+
+const STAGING_DIR: &str = "signatures.staging";
+// Downloads stage separately, since they run while the engine may be rolling back through STAGING_DIR
+const DOWNLOAD_DIR: &str = "signatures.download";
+const HISTORY_DIR: &str = "signature-history";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateConfig {
+    pub mirror_url: String,
+    pub keep_versions: usize,
+    pub timeout_secs: u64,
+    pub enabled: bool,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        // Nothing is fetched until the user configures a mirror
+        UpdateConfig {
+            mirror_url: String::new(),
+            keep_versions: 3,
+            timeout_secs: 30,
+            enabled: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageRef {
+    pub version: u64,
+    pub url: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaRef {
+    pub from: u64,
+    pub to: u64,
+    pub url: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateIndex {
+    pub latest: u64,
+    pub full: PackageRef,
+    #[serde(default)]
+    pub deltas: Vec<DeltaRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignaturePackage {
+    pub version: u64,
+    // Set for delta packages, which apply on top of exactly this version
+    #[serde(default)]
+    pub base_version: Option<u64>,
+    pub built: DateTime<Local>,
+    // File name -> contents; signature formats are all text
+    pub files: BTreeMap<String, String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+    // Integrity manifest of the resulting database
+    pub manifest: SignatureManifest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum UpdateOutcome {
+    Disabled,
+    UpToDate(u64),
+    // A rolled-back database stays in place until updates are resumed
+    Pinned(u64),
+    Updated { from: u64, to: u64, packages: usize },
+}
+
+pub struct Updater {
+    config: UpdateConfig,
+    client: reqwest::blocking::Client,
+}
+
+impl Updater {
+    pub fn new(config: UpdateConfig) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Updater { config, client })
+    }
+
+    fn url(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return path.to_string();
+        }
+        format!(
+            "{}/{}",
+            self.config.mirror_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+
+    fn fetch(&self, path: &str) -> Result<Vec<u8>> {
+        let response = self.client.get(self.url(path)).send()?.error_for_status()?;
+        Ok(response.bytes()?.to_vec())
+    }
+
+    pub fn fetch_index(&self) -> Result<UpdateIndex> {
+        let bytes = self.fetch("index.json")?;
+        serde_json::from_slice(&bytes).map_err(|e| anyhow!("Malformed update index: {}", e))
+    }
+
+    fn fetch_package(&self, url: &str, sha256: &str) -> Result<SignaturePackage> {
+        let bytes = self.fetch(url)?;
+        let actual = to_hex(&Sha256::digest(&bytes));
+        if !actual.eq_ignore_ascii_case(sha256) {
+            bail!("Package {} does not match the digest published in the index", url);
+        }
+        serde_json::from_slice(&bytes).map_err(|e| anyhow!("Malformed signature package {}: {}", url, e))
+    }
+
+    // Follows the delta chain from the active version; falls back to the full package
+    // when the chain is incomplete
+    pub fn plan(&self, index: &UpdateIndex, active_version: u64) -> Option<Vec<PackageRef>> {
+        if index.latest <= active_version {
+            return None;
+        }
+
+        let mut chain = Vec::new();
+        let mut version = active_version;
+        while version < index.latest {
+            match index.deltas.iter().find(|d| d.from == version && d.to > version) {
+                Some(delta) => {
+                    chain.push(PackageRef {
+                        version: delta.to,
+                        url: delta.url.clone(),
+                        sha256: delta.sha256.clone(),
+                    });
+                    version = delta.to;
+                }
+                None => return Some(vec![index.full.clone()]),
+            }
+        }
+
+        Some(chain)
+    }
+
+    // Builds the new database in a staging directory next to the active one and verifies it
+    pub fn stage(&self, signature_dir: &Path, steps: &[PackageRef], active_version: u64) -> Result<PathBuf> {
+        let staging = sibling(signature_dir, DOWNLOAD_DIR)?;
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+
+        let mut version = active_version;
+        for (i, step) in steps.iter().enumerate() {
+            let package = self.fetch_package(&step.url, &step.sha256)?;
+
+            match package.base_version {
+                Some(base) => {
+                    if base != version {
+                        bail!("Delta package v{} expects base v{}, have v{}", package.version, base, version);
+                    }
+                    // The first delta starts from a copy of the active database
+                    if i == 0 {
+                        copy_database(signature_dir, &staging)?;
+                    }
+                }
+                None => {
+                    fs::remove_dir_all(&staging)?;
+                    fs::create_dir_all(&staging)?;
+                }
+            }
+
+            for name in &package.removed {
+                let path = staging.join(checked_name(name)?);
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+            for (name, content) in &package.files {
+                fs::write(staging.join(checked_name(name)?), content)?;
+            }
+
+            if package.manifest.version != package.version {
+                bail!("Package v{} carries a manifest for v{}", package.version, package.manifest.version);
+            }
+            package.manifest.write(&staging)?;
+            package.manifest.verify(&staging)?;
+            version = package.version;
+        }
+
+        Ok(staging)
+    }
+}
+
+// Only bare file names are accepted from a package
+fn checked_name(name: &str) -> Result<&str> {
+    if name.is_empty()
+        || name.contains('/')
+        || name.contains('\\')
+        || name.starts_with('.')
+        || name == sigdb::MANIFEST_FILE
+    {
+        bail!("Invalid file name {:?} in signature package", name);
+    }
+    Ok(name)
+}
+
+fn sibling(signature_dir: &Path, name: &str) -> Result<PathBuf> {
+    signature_dir
+        .parent()
+        .map(|parent| parent.join(name))
+        .ok_or_else(|| anyhow!("Signature directory has no parent"))
+}
+
+fn copy_database(from: &Path, to: &Path) -> Result<()> {
+    for name in sigdb::database_files(from)? {
+        fs::copy(from.join(&name), to.join(&name))?;
+    }
+    Ok(())
+}
+
+pub fn history_dir(signature_dir: &Path) -> Result<PathBuf> {
+    let history = sibling(signature_dir, HISTORY_DIR)?;
+    fs::create_dir_all(&history)?;
+    Ok(history)
+}
+
+// Versions kept for rollback, newest first
+pub fn history_versions(signature_dir: &Path) -> Result<Vec<u64>> {
+    let mut versions: Vec<u64> = fs::read_dir(history_dir(signature_dir)?)?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_string_lossy().strip_prefix('v')?.parse().ok())
+        .collect();
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(versions)
+}
+
+// Moves the active database into history and the replacement into place.
+// Each step is a single rename, so an interruption leaves a recoverable state.
+pub fn swap_in(signature_dir: &Path, replacement: &Path, active_version: u64, keep_versions: usize) -> Result<()> {
+    let history = history_dir(signature_dir)?;
+
+    if signature_dir.exists() {
+        let archived = history.join(format!("v{}", active_version));
+        if archived.exists() {
+            fs::remove_dir_all(&archived)?;
+        }
+        fs::rename(signature_dir, &archived)?;
+    }
+    fs::rename(replacement, signature_dir)?;
+
+    for version in history_versions(signature_dir)?.into_iter().skip(keep_versions) {
+        fs::remove_dir_all(history.join(format!("v{}", version)))?;
+    }
+
+    Ok(())
+}
+
+// Takes a version out of history so it can be swapped back in
+pub fn take_from_history(signature_dir: &Path, version: u64) -> Result<PathBuf> {
+    let source = history_dir(signature_dir)?.join(format!("v{}", version));
+    if !source.exists() {
+        bail!("Signature database v{} is not available for rollback", version);
+    }
+    let staging = sibling(signature_dir, STAGING_DIR)?;
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::rename(&source, &staging)?;
+    Ok(staging)
+}
+
+pub fn return_to_history(signature_dir: &Path, staging: &Path, version: u64) -> Result<()> {
+    fs::rename(staging, history_dir(signature_dir)?.join(format!("v{}", version)))?;
+    Ok(())
+}
+
+pub fn discard_staging(signature_dir: &Path) {
+    if let Ok(staging) = sibling(signature_dir, DOWNLOAD_DIR) {
+        let _ = fs::remove_dir_all(staging);
+    }
+}
+
+// Restores the newest archived database if a swap was interrupted between its two renames
+pub fn recover_interrupted_swap(signature_dir: &Path) -> Result<()> {
+    if signature_dir.exists() {
+        return Ok(());
+    }
+    if let Some(version) = history_versions(signature_dir)?.first() {
+        log::warn!("Active signature database missing, restoring v{}", version);
+        fs::rename(history_dir(signature_dir)?.join(format!("v{}", version)), signature_dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use tempfile::TempDir;
+
+    // Serves fixed paths from memory over HTTP/1.1, one request per connection
+    pub(in crate::core) fn serve(files: HashMap<String, Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                if reader.read_line(&mut request).is_err() {
+                    continue;
+                }
+                let mut header = String::new();
+                while matches!(reader.read_line(&mut header), Ok(n) if n > 0) && header != "\r\n" {
+                    header.clear();
+                }
+
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let response = match files.get(path) {
+                    Some(body) => {
+                        let mut response =
+                            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len())
+                                .into_bytes();
+                        response.extend_from_slice(body);
+                        response
+                    }
+                    None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+                };
+                let _ = (&stream).write_all(&response);
+            }
+        });
+        url
+    }
+
+    // Builds a package whose manifest describes the database that results from applying it
+    pub(in crate::core) fn package(
+        version: u64,
+        base_version: Option<u64>,
+        files: &[(&str, &str)],
+        removed: &[&str],
+        result: &[(&str, &str)],
+    ) -> Vec<u8> {
+        let dir = TempDir::new().unwrap();
+        for (name, content) in result {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        let package = SignaturePackage {
+            version,
+            base_version,
+            built: Local::now(),
+            files: files.iter().map(|(n, c)| (n.to_string(), c.to_string())).collect(),
+            removed: removed.iter().map(|n| n.to_string()).collect(),
+            manifest: SignatureManifest::build(dir.path(), version).unwrap(),
+        };
+        serde_json::to_vec(&package).unwrap()
+    }
+
+    pub(in crate::core) fn package_ref(version: u64, url: &str, bytes: &[u8]) -> PackageRef {
+        PackageRef {
+            version,
+            url: url.to_string(),
+            sha256: to_hex(&Sha256::digest(bytes)),
+        }
+    }
+
+    fn updater(mirror_url: &str) -> Updater {
+        Updater::new(UpdateConfig {
+            mirror_url: mirror_url.to_string(),
+            timeout_secs: 5,
+            enabled: true,
+            ..UpdateConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn signature_dir(root: &TempDir) -> PathBuf {
+        let dir = root.path().join("signatures");
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read(dir: &Path, name: &str) -> String {
+        fs::read_to_string(dir.join(name)).unwrap()
+    }
+
+    fn index(latest: u64, full: PackageRef, deltas: Vec<DeltaRef>) -> UpdateIndex {
+        UpdateIndex { latest, full, deltas }
+    }
+
+    fn delta(from: u64, to: u64) -> DeltaRef {
+        DeltaRef {
+            from,
+            to,
+            url: format!("delta-{}-{}.json", from, to),
+            sha256: String::new(),
+        }
+    }
+
+    #[test]
+    fn default_config_fetches_nothing() {
+        let config = UpdateConfig::default();
+        assert!(!config.enabled);
+        assert!(config.mirror_url.is_empty());
+    }
+
+    #[test]
+    fn fetches_and_parses_the_index() {
+        let index_json = r#"{
+            "latest": 3,
+            "full": {"version": 3, "url": "full-3.json", "sha256": "ab"},
+            "deltas": [{"from": 2, "to": 3, "url": "delta-2-3.json", "sha256": "cd"}]
+        }"#;
+        let url = serve(HashMap::from([("/index.json".to_string(), index_json.as_bytes().to_vec())]));
+
+        let index = updater(&format!("{}/", url)).fetch_index().unwrap();
+        assert_eq!(index.latest, 3);
+        assert_eq!(index.full.url, "full-3.json");
+        assert_eq!(index.deltas.len(), 1);
+        assert_eq!((index.deltas[0].from, index.deltas[0].to), (2, 3));
+    }
+
+    #[test]
+    fn rejects_malformed_or_missing_index() {
+        let url = serve(HashMap::from([("/index.json".to_string(), b"{\"latest\": \"x\"}".to_vec())]));
+        let err = updater(&url).fetch_index().unwrap_err();
+        assert!(err.to_string().contains("Malformed update index"));
+
+        let empty = serve(HashMap::new());
+        assert!(updater(&empty).fetch_index().is_err());
+    }
+
+    #[test]
+    fn plans_delta_chain_or_falls_back_to_full() {
+        let updater = updater("http://127.0.0.1:9");
+        let full = PackageRef {
+            version: 4,
+            url: "full-4.json".to_string(),
+            sha256: String::new(),
+        };
+
+        let complete = index(4, full.clone(), vec![delta(2, 3), delta(3, 4)]);
+        let steps = updater.plan(&complete, 2).unwrap();
+        assert_eq!(steps.iter().map(|s| s.version).collect::<Vec<_>>(), vec![3, 4]);
+
+        // No delta starts from v1
+        let steps = updater.plan(&complete, 1).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].url, "full-4.json");
+
+        assert!(updater.plan(&complete, 4).is_none());
+        assert!(updater.plan(&complete, 5).is_none());
+
+        // A delta that does not advance the version is ignored
+        let looping = index(4, full, vec![delta(2, 2)]);
+        assert_eq!(updater.plan(&looping, 2).unwrap()[0].url, "full-4.json");
+    }
+
+    #[test]
+    fn stages_and_verifies_full_and_delta_packages() {
+        let full = package(1, None, &[("main.ndb", "a"), ("old.hdb", "b")], &[], &[("main.ndb", "a"), ("old.hdb", "b")]);
+        let delta = package(2, Some(1), &[("main.ndb", "c")], &["old.hdb"], &[("main.ndb", "c")]);
+        let url = serve(HashMap::from([
+            ("/full-1.json".to_string(), full.clone()),
+            ("/delta-1-2.json".to_string(), delta.clone()),
+        ]));
+        let root = TempDir::new().unwrap();
+        let signatures = signature_dir(&root);
+        let updater = updater(&url);
+
+        let staging = updater.stage(&signatures, &[package_ref(1, "full-1.json", &full)], 0).unwrap();
+        assert_eq!(read(&staging, "old.hdb"), "b");
+        assert_eq!(SignatureManifest::read(&staging).unwrap().unwrap().version, 1);
+        swap_in(&signatures, &staging, 0, 3).unwrap();
+
+        let staging = updater.stage(&signatures, &[package_ref(2, "delta-1-2.json", &delta)], 1).unwrap();
+        assert_eq!(read(&staging, "main.ndb"), "c");
+        assert!(!staging.join("old.hdb").exists());
+        SignatureManifest::read(&staging).unwrap().unwrap().verify(&staging).unwrap();
+
+        // The active database is untouched until the swap
+        assert_eq!(read(&signatures, "main.ndb"), "a");
+    }
+
+    #[test]
+    fn rejects_package_with_wrong_digest() {
+        let full = package(1, None, &[("main.ndb", "a")], &[], &[("main.ndb", "a")]);
+        let url = serve(HashMap::from([("/full-1.json".to_string(), full.clone())]));
+        let root = TempDir::new().unwrap();
+
+        let mut step = package_ref(1, "full-1.json", &full);
+        step.sha256 = to_hex(&Sha256::digest(b"something else"));
+        let err = updater(&url).stage(&signature_dir(&root), &[step], 0).unwrap_err();
+        assert!(err.to_string().contains("does not match the digest"));
+    }
+
+    #[test]
+    fn rejects_package_whose_files_do_not_match_its_manifest() {
+        // The manifest was built for different contents than the package ships
+        let tampered = package(1, None, &[("main.ndb", "tampered")], &[], &[("main.ndb", "a")]);
+        let wrong_version = package(2, None, &[("main.ndb", "a")], &[], &[("main.ndb", "a")]);
+        let mut wrong_version: SignaturePackage = serde_json::from_slice(&wrong_version).unwrap();
+        wrong_version.manifest.version = 7;
+        let wrong_version = serde_json::to_vec(&wrong_version).unwrap();
+        let url = serve(HashMap::from([
+            ("/tampered.json".to_string(), tampered.clone()),
+            ("/wrong-version.json".to_string(), wrong_version.clone()),
+            ("/garbage.json".to_string(), b"not json".to_vec()),
+        ]));
+        let root = TempDir::new().unwrap();
+        let signatures = signature_dir(&root);
+        let updater = updater(&url);
+
+        assert!(updater.stage(&signatures, &[package_ref(1, "tampered.json", &tampered)], 0).is_err());
+        let err = updater
+            .stage(&signatures, &[package_ref(2, "wrong-version.json", &wrong_version)], 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("carries a manifest for v7"));
+        let err = updater
+            .stage(&signatures, &[package_ref(1, "garbage.json", b"not json")], 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("Malformed signature package"));
+    }
+
+    #[test]
+    fn rejects_delta_for_another_base_and_unsafe_names() {
+        let delta = package(3, Some(2), &[("main.ndb", "c")], &[], &[("main.ndb", "c")]);
+        let escaping = package(1, None, &[("../escape.ndb", "x")], &[], &[]);
+        let url = serve(HashMap::from([
+            ("/delta-2-3.json".to_string(), delta.clone()),
+            ("/escaping.json".to_string(), escaping.clone()),
+        ]));
+        let root = TempDir::new().unwrap();
+        let signatures = signature_dir(&root);
+        let updater = updater(&url);
+
+        let err = updater
+            .stage(&signatures, &[package_ref(3, "delta-2-3.json", &delta)], 1)
+            .unwrap_err();
+        assert!(err.to_string().contains("expects base v2"));
+        assert!(updater.stage(&signatures, &[package_ref(1, "escaping.json", &escaping)], 0).is_err());
+        assert!(!root.path().join("escape.ndb").exists());
+
+        for name in ["", "a/b", "a\\b", ".hidden", sigdb::MANIFEST_FILE] {
+            assert!(checked_name(name).is_err(), "{:?}", name);
+        }
+        assert!(checked_name("main.ndb").is_ok());
+    }
+
+    #[test]
+    fn swaps_keep_history_for_rollback() {
+        let root = TempDir::new().unwrap();
+        let signatures = signature_dir(&root);
+
+        for version in 1..=5u64 {
+            let replacement = root.path().join("next");
+            fs::create_dir_all(&replacement).unwrap();
+            fs::write(replacement.join("main.ndb"), version.to_string()).unwrap();
+            swap_in(&signatures, &replacement, version - 1, 2).unwrap();
+        }
+        assert_eq!(read(&signatures, "main.ndb"), "5");
+        assert_eq!(history_versions(&signatures).unwrap(), vec![4, 3]);
+
+        let staging = take_from_history(&signatures, 4).unwrap();
+        assert_eq!(read(&staging, "main.ndb"), "4");
+        swap_in(&signatures, &staging, 5, 2).unwrap();
+        assert_eq!(read(&signatures, "main.ndb"), "4");
+        assert_eq!(history_versions(&signatures).unwrap(), vec![5, 3]);
+
+        assert!(take_from_history(&signatures, 1).is_err());
+    }
+
+    #[test]
+    fn recovers_from_interrupted_swap() {
+        let root = TempDir::new().unwrap();
+        let signatures = signature_dir(&root);
+        fs::write(signatures.join("main.ndb"), "1").unwrap();
+        fs::rename(&signatures, history_dir(&signatures).unwrap().join("v1")).unwrap();
+
+        recover_interrupted_swap(&signatures).unwrap();
+        assert_eq!(read(&signatures, "main.ndb"), "1");
+        assert!(history_versions(&signatures).unwrap().is_empty());
+    }
+}
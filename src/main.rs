@@ -49,13 +49,28 @@ fn start_background_services(
                 log::error!("Failed to update threat database: {}", e);
             }
         }
+
+        // Being offline is normal; the local database keeps protecting
+        match core::Engine::update_from_mirror(&engine_clone) {
+            Ok(core::UpdateOutcome::Disabled) => log::info!("Signature updates are disabled"),
+            Ok(core::UpdateOutcome::UpToDate(version)) => {
+                log::info!("Threat database v{} is up to date", version);
+            }
+            Ok(core::UpdateOutcome::Pinned(version)) => {
+                log::info!("Threat database pinned at v{} after a rollback", version);
+            }
+            Ok(core::UpdateOutcome::Updated { from, to, packages }) => {
+                log::info!("Threat database updated from v{} to v{} ({} packages)", from, to, packages);
+            }
+            Err(e) => log::warn!("Signature update failed: {}", e),
+        }
     });
 
     // Start real-time protection
     let engine_clone = engine.clone();
     std::thread::spawn(move || {
         log::info!("Starting real-time protection...");
-        if let Ok(engine) = engine_clone.lock() {
+        if let Ok(mut engine) = engine_clone.lock() {
             if let Err(e) = engine.start_realtime_protection() {
                 log::error!("Failed to start real-time protection: {}", e);
             }
@@ -207,16 +207,16 @@ fn create_dashboard_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkB
     cards_box.set_margin_top(24);
 
     // Protection status card
-    let protection_card = create_status_card(
+    let (protection_card, protection_details) = create_status_card(
         "Protection",
         "Active",
         "security-high-symbolic",
-        &["Real-time: On", "Database: Up to date"],
+        &["Real-time: Off", "Database: Loading..."],
     );
     cards_box.append(&protection_card);
 
     // System health card
-    let health_card = create_status_card(
+    let (health_card, _) = create_status_card(
         "System Health",
         "Good",
         "heart-symbolic",
@@ -225,7 +225,7 @@ fn create_dashboard_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkB
     cards_box.append(&health_card);
 
     // Privacy status card
-    let privacy_card = create_status_card(
+    let (privacy_card, _) = create_status_card(
         "Privacy",
         "Secure",
         "user-not-tracked-symbolic",
@@ -235,10 +235,29 @@ fn create_dashboard_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkB
 
     page.append(&cards_box);
 
+    // Refresh protection and database status; try_lock keeps the UI responsive while the engine is busy
+    let state_clone = state.clone();
+    let realtime_label = protection_details[0].clone();
+    let database_label = protection_details[1].clone();
+    glib::timeout_add_local(Duration::from_secs(5), move || {
+        if let Ok(engine) = state_clone.engine.try_lock() {
+            let realtime = if engine.is_realtime_protection_active() { "On" } else { "Off" };
+            realtime_label.set_text(&format!("Real-time: {}", realtime));
+            if let Ok(info) = engine.get_signature_database_info() {
+                let text = match info.built {
+                    Some(built) => format!("Database: v{} ({})", info.version, built.format("%Y-%m-%d")),
+                    None => "Database: Built-in".to_string(),
+                };
+                database_label.set_text(&text);
+            }
+        }
+        glib::ControlFlow::Continue
+    });
+
     page
 }
 
-fn create_status_card(title: &str, status: &str, icon: &str, details: &[&str]) -> GtkBox {
+fn create_status_card(title: &str, status: &str, icon: &str, details: &[&str]) -> (GtkBox, Vec<Label>) {
     let card = GtkBox::new(Orientation::Vertical, 8);
     card.set_css_classes(&["card"]);
     card.set_size_request(200, 150);
@@ -260,13 +279,15 @@ fn create_status_card(title: &str, status: &str, icon: &str, details: &[&str]) -
     status_label.set_css_classes(&["success"]);
     card.append(&status_label);
 
+    let mut detail_labels = Vec::new();
     for detail in details {
         let detail_label = Label::new(Some(*detail));
         detail_label.set_css_classes(&["caption"]);
         card.append(&detail_label);
+        detail_labels.push(detail_label);
     }
 
-    card
+    (card, detail_labels)
 }
 
 fn create_scan_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkBox {
@@ -376,10 +397,14 @@ fn create_scan_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkBox {
             target_paths: vec![PathBuf::from("/home")],
             scan_type: core::ScanType::Quick,
             heuristic_enabled: true,
+            heuristic_threshold: 0.6,
             cloud_lookup_enabled: false,
             max_file_size: 100 * 1024 * 1024, // 100MB
             excluded_extensions: vec![".tmp".to_string(), ".log".to_string()],
-            excluded_paths: vec![],
+            excluded_paths: vec![PathBuf::from("**/node_modules/**")],
+            worker_threads: 0, // one per CPU core
+            archive_limits: core::ArchiveLimits::default(),
+            terminate_processes: false,
         };
 
         // Start scan in background
@@ -420,7 +445,7 @@ fn create_scan_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkBox {
                             let toast = Toast::new(&format!("Threat found: {}", threat.signature.name));
                             toast_overlay.add_toast(toast);
                         }
-                        ScanEvent::Completed { threats_found, files_scanned } => {
+                        ScanEvent::Completed { threats_found, files_scanned, cache } => {
                             progress_bar.set_fraction(1.0);
                             progress_label.set_text("Scan completed");
                             cancel_btn.set_sensitive(false);
@@ -429,7 +454,10 @@ fn create_scan_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkBox {
                                 results_label.set_text(&format!("{} threats found in {} files", threats_found, files_scanned));
                                 results_label.set_css_classes(&["error"]);
                             } else {
-                                results_label.set_text(&format!("No threats found in {} files", files_scanned));
+                                results_label.set_text(&format!(
+                                    "No threats found in {} files ({} unchanged since the last scan)",
+                                    files_scanned, cache.hits
+                                ));
                                 results_label.set_css_classes(&["success"]);
                             }
                             
@@ -703,6 +731,22 @@ fn create_system_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkBox
 
     security_group.add(&security_btn);
 
+    let integrity_btn = Button::builder()
+        .label("Check File Integrity")
+        .halign(Align::Start)
+        .margin_top(12)
+        .build();
+
+    security_group.add(&integrity_btn);
+
+    let packages_btn = Button::builder()
+        .label("Verify Package Files")
+        .halign(Align::Start)
+        .margin_top(12)
+        .build();
+
+    security_group.add(&packages_btn);
+
     // Quarantine section
     let quarantine_group = PreferencesGroup::new();
     quarantine_group.set_title("Quarantine");
@@ -766,6 +810,79 @@ fn create_system_page(state: &AppState, toast_overlay: &ToastOverlay) -> GtkBox
         }
     });
 
+    // Integrity check handler; hashing the system directories takes a while, so it runs on
+    // a worker thread and only holds the engine lock to look up the baseline
+    let state_clone = state.clone();
+    let toast_overlay_clone = toast_overlay.clone();
+    integrity_btn.connect_clicked(move |button| {
+        let baseline_path = match state_clone.engine.lock() {
+            Ok(engine) => engine.integrity_baseline_path().ok(),
+            Err(_) => return,
+        };
+        button.set_sensitive(false);
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(core::Engine::check_integrity(baseline_path));
+        });
+
+        let button = button.clone();
+        let toast_overlay = toast_overlay_clone.clone();
+        glib::timeout_add_local(Duration::from_millis(200), move || {
+            let result = match rx.try_recv() {
+                Ok(result) => result,
+                Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => Err(anyhow::anyhow!("Integrity check stopped")),
+            };
+            let message = match result {
+                // A single summary item when nothing changed or there is no baseline to compare against
+                Ok(items) => match items.as_slice() {
+                    [item] if item.id == "integrity" => format!("File integrity: {}", item.description),
+                    _ => format!("File integrity: {} changes since the baseline", items.len()),
+                },
+                Err(e) => format!("Error: {}", e),
+            };
+            toast_overlay.add_toast(Toast::new(&message));
+            button.set_sensitive(true);
+            glib::ControlFlow::Break
+        });
+    });
+
+    // Package verification handler; reads every packaged system binary, so it runs on a
+    // worker thread with a copy of the configured prefixes
+    let state_clone = state.clone();
+    let toast_overlay_clone = toast_overlay.clone();
+    packages_btn.connect_clicked(move |button| {
+        let path_prefixes = match state_clone.engine.lock() {
+            Ok(engine) => engine.package_verify_config.path_prefixes.clone(),
+            Err(_) => return,
+        };
+        button.set_sensitive(false);
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(core::Engine::check_packages(&path_prefixes));
+        });
+
+        let button = button.clone();
+        let toast_overlay = toast_overlay_clone.clone();
+        glib::timeout_add_local(Duration::from_millis(200), move || {
+            let message = match rx.try_recv() {
+                Ok(items) => {
+                    let failed = items.iter().filter(|i| matches!(i.status, core::AuditStatus::Fail)).count();
+                    if failed > 0 {
+                        format!("Package verification: {} files differ from their packages", failed)
+                    } else {
+                        "Package verification: installed files match their packages".to_string()
+                    }
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => "Error: Package verification stopped".to_string(),
+            };
+            toast_overlay.add_toast(Toast::new(&message));
+            button.set_sensitive(true);
+            glib::ControlFlow::Break
+        });
+    });
+
     page
 }
 